@@ -0,0 +1,180 @@
+#ifndef PATH2REGEX_H
+#define PATH2REGEX_H
+
+/* Regenerate with `cbindgen --config cbindgen.toml --output include/path2regex.h` */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * An opaque handle of a [`Compiler`](struct.Compiler.html)
+ */
+typedef struct P2rCompiler P2rCompiler;
+
+/**
+ * An opaque handle of a [`Matcher`](struct.Matcher.html)
+ */
+typedef struct P2rMatcher P2rMatcher;
+
+/**
+ * The Configuration of a [`P2rMatcher`], `NULL` strings keep their default value
+ */
+typedef struct P2rMatcherOptions {
+  /**
+   * Set the default delimiter for repeat parameters.
+   */
+  const char *delimiter;
+  /**
+   * List of characters to automatically consider prefixes when parsing.
+   */
+  const char *prefixes;
+  /**
+   * List of characters that can also be "end" characters.
+   */
+  const char *ends_with;
+  /**
+   * When `true` the regexp will be case sensitive.
+   */
+  bool sensitive;
+  /**
+   * When `true` the regexp won't allow an optional trailing delimiter to match.
+   */
+  bool strict;
+  /**
+   * When `true` the regexp will match to the end of the string.
+   */
+  bool end;
+  /**
+   * When `true` the regexp will match from the beginning of the string.
+   */
+  bool start;
+} P2rMatcherOptions;
+
+/**
+ * The Configuration of a [`P2rCompiler`], `NULL` strings keep their default value
+ */
+typedef struct P2rCompilerOptions {
+  /**
+   * Set the default delimiter for repeat parameters.
+   */
+  const char *delimiter;
+  /**
+   * List of characters to automatically consider prefixes when parsing.
+   */
+  const char *prefixes;
+  /**
+   * When `true` the regexp will be case sensitive.
+   */
+  bool sensitive;
+  /**
+   * When `false` the function can produce an invalid (unmatched) path.
+   */
+  bool validate;
+} P2rCompilerOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Get the default configuration of a [`P2rMatcher`]
+ */
+struct P2rMatcherOptions p2r_matcher_options_default(void);
+
+/**
+ * Get the default configuration of a [`P2rCompiler`]
+ */
+struct P2rCompilerOptions p2r_compiler_options_default(void);
+
+/**
+ * Create a matcher, returns `NULL` on error
+ *
+ * # Safety
+ *
+ * `pattern` must be a valid C string, `options` and `out_err` must be `NULL` or valid pointers.
+ * The matcher must be released with [`p2r_matcher_free`].
+ */
+struct P2rMatcher *p2r_matcher_new(const char *pattern,
+                                   const struct P2rMatcherOptions *options,
+                                   char **out_err);
+
+/**
+ * Match a path, returns `true` when it matches
+ *
+ * The params are written as a JSON object to `out_json` (when it is not `NULL`),
+ * and must be released with [`p2r_string_free`].
+ *
+ * # Safety
+ *
+ * `matcher` must come from [`p2r_matcher_new`], `path` must be a valid C string
+ * and `out_json` must be `NULL` or a valid pointer.
+ */
+bool p2r_matcher_find(const struct P2rMatcher *matcher, const char *path, char **out_json);
+
+/**
+ * Release a matcher
+ *
+ * # Safety
+ *
+ * `matcher` must be `NULL` or come from [`p2r_matcher_new`], and must not be used afterwards.
+ */
+void p2r_matcher_free(struct P2rMatcher *matcher);
+
+/**
+ * Create a compiler, returns `NULL` on error
+ *
+ * # Safety
+ *
+ * `pattern` must be a valid C string, `options` and `out_err` must be `NULL` or valid pointers.
+ * The compiler must be released with [`p2r_compiler_free`].
+ */
+struct P2rCompiler *p2r_compiler_new(const char *pattern,
+                                     const struct P2rCompilerOptions *options,
+                                     char **out_err);
+
+/**
+ * Render the parameters given as a JSON object into a path, returns `NULL` on error
+ *
+ * The path must be released with [`p2r_string_free`].
+ *
+ * # Safety
+ *
+ * `compiler` must come from [`p2r_compiler_new`], `data_json` must be a valid C string
+ * and `out_err` must be `NULL` or a valid pointer.
+ */
+char *p2r_compiler_render(const struct P2rCompiler *compiler,
+                          const char *data_json,
+                          char **out_err);
+
+/**
+ * Release a compiler
+ *
+ * # Safety
+ *
+ * `compiler` must be `NULL` or come from [`p2r_compiler_new`], and must not be used afterwards.
+ */
+void p2r_compiler_free(struct P2rCompiler *compiler);
+
+/**
+ * Get the message of the last error on the current thread, `NULL` if the last call succeeded
+ *
+ * The message is owned by the library and stays valid until the next call on the same thread.
+ */
+const char *p2r_last_error_message(void);
+
+/**
+ * Release a string returned by the library
+ *
+ * # Safety
+ *
+ * `s` must be `NULL` or a string returned by the library, and must not be used afterwards.
+ */
+void p2r_string_free(char *s);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* PATH2REGEX_H */
@@ -0,0 +1,465 @@
+#![forbid(unsafe_code)]
+#![deny(unreachable_pub, missing_docs, rust_2018_idioms)]
+#![doc = include_str!("../README.md")]
+
+use path2regex::{Parser, Token};
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Generate a typed struct of the parameters of a route for the function it is placed on
+///
+/// `#[path_route("/user/:id")]` on `fn handle_user` generates `HandleUserParams` with a
+/// `pub id: String` field, the `PATH` constant and a `parse_params(path: &str) -> Option<Self>`
+/// function. Optional parameters (`?`) are `Option<String>`, repeated parameters (`+` and `*`)
+/// are `Vec<String>`, unnamed parameters are called `param_0`, `param_1`... The path is checked
+/// at compile time.
+///
+/// A quoted name like `:"user.id"` is a field with `_` for the characters an identifier can't
+/// hold, like `user_id`, and two parameters with the same field fail to compile:
+///
+/// ```compile_fail
+/// #[path2regex_macros::path_route("/users/:\"user.id\"/:user_id")]
+/// fn handle_user() {}
+/// ```
+///
+/// The generated code uses `path2regex::Matcher`, which needs the `match` feature.
+#[proc_macro_attribute]
+pub fn path_route(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let generated = expand(attr, &item).unwrap_or_else(|message| {
+        format!("::std::compile_error!({message:?});")
+            .parse()
+            .expect("a compile_error! call is valid")
+    });
+    let mut output = item;
+    output.extend(generated);
+    output
+}
+
+/// Implement `FromParams`, `TryFrom<&MatchResult>` and `ToParams` of path2regex for a struct
+///
+/// Each named field is a parameter parsed with `FromStr` and rendered with `Display`.
+/// `Option<T>` fields are optional parameters, missing when absent or empty, and `Vec<T>`
+/// fields are repeated parameters. The field attribute `#[param(...)]` takes:
+///
+/// - `name = "postId"`: the name of the parameter, instead of the name of the field
+/// - `parse = "path::to::parse"`: a `fn(&str) -> Result<T, E>` used instead of `FromStr`
+/// - `display = "path::to::display"`: a `fn(&T) -> String` used instead of `Display`
+///
+/// The generated code needs the `match` and `compile` features of path2regex.
+#[proc_macro_derive(PathParams, attributes(param))]
+pub fn derive_path_params(item: TokenStream) -> TokenStream {
+    expand_path_params(item).unwrap_or_else(|message| {
+        format!("::std::compile_error!({message:?});")
+            .parse()
+            .expect("a compile_error! call is valid")
+    })
+}
+
+/// The kind of a field of `PathParams`
+#[derive(PartialEq, Eq)]
+enum FieldKind {
+    Required,
+    Optional,
+    Repeated,
+}
+
+/// A field of `PathParams`
+struct Field {
+    ident: String,
+    name: String,
+    kind: FieldKind,
+    parse: String,
+    display: String,
+}
+
+fn expand_path_params(item: TokenStream) -> Result<TokenStream, String> {
+    let (name, body) = struct_body(item)?;
+    let fields = split_top_level(body)
+        .into_iter()
+        .map(parse_field)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut extract = String::new();
+    let mut insert = String::new();
+    for Field {
+        ident,
+        name,
+        kind,
+        parse,
+        display,
+    } in fields.iter()
+    {
+        let extract_fn = match kind {
+            FieldKind::Required => "required",
+            FieldKind::Optional => "optional",
+            FieldKind::Repeated => "repeated",
+        };
+        extract +=
+            &format!("{ident}: ::path2regex::__private::{extract_fn}(params, {name:?}, {parse})?,");
+        let to_value = format!("::path2regex::__private::Value::String({display}(value))");
+        insert += &match kind {
+            FieldKind::Required => format!(
+                "{{ let value = &self.{ident}; params.insert({name:?}.to_owned(), {to_value}); }}"
+            ),
+            FieldKind::Optional => format!(
+                "if let ::std::option::Option::Some(value) = &self.{ident} {{ params.insert({name:?}.to_owned(), {to_value}); }}"
+            ),
+            FieldKind::Repeated => format!(
+                "params.insert({name:?}.to_owned(), ::path2regex::__private::Value::Array(self.{ident}.iter().map(|value| {to_value}).collect()));"
+            ),
+        };
+    }
+
+    let code = format!(
+        r#"
+        impl ::path2regex::FromParams for {name} {{
+            fn from_params(params: &::path2regex::__private::Value) -> ::path2regex::__private::Result<Self> {{
+                ::std::result::Result::Ok(Self {{ {extract} }})
+            }}
+        }}
+
+        impl ::std::convert::TryFrom<&::path2regex::MatchResult> for {name} {{
+            type Error = ::path2regex::__private::Error;
+
+            fn try_from(result: &::path2regex::MatchResult) -> ::path2regex::__private::Result<Self> {{
+                <Self as ::path2regex::FromParams>::from_params(&result.params)
+            }}
+        }}
+
+        impl ::path2regex::ToParams for {name} {{
+            fn to_data(&self) -> ::path2regex::__private::Value {{
+                #[allow(unused_mut)]
+                let mut params = ::path2regex::__private::Map::new();
+                {insert}
+                ::path2regex::__private::Value::Object(params)
+            }}
+        }}
+        "#
+    );
+    code.parse()
+        .map_err(|err| format!("Failed to generate the path params: {err}"))
+}
+
+/// Find the name and the fields of the struct
+fn struct_body(item: TokenStream) -> Result<(String, TokenStream), String> {
+    let mut tokens = item.into_iter();
+    while let Some(token) = tokens.next() {
+        if !matches!(&token, TokenTree::Ident(ident) if ident.to_string() == "struct") {
+            continue;
+        }
+        let name = match tokens.next() {
+            Some(TokenTree::Ident(name)) => name.to_string(),
+            _ => break,
+        };
+        return match tokens.next() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                Ok((name, group.stream()))
+            }
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '<' => {
+                Err("`PathParams` doesn't support generic structs".to_owned())
+            }
+            _ => Err("`PathParams` needs a struct with named fields".to_owned()),
+        };
+    }
+    Err("`PathParams` must be derived for a struct".to_owned())
+}
+
+/// Split the tokens on the commas out of angle brackets
+fn split_top_level(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![vec![]];
+    let mut depth = 0usize;
+    for token in tokens {
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                '>' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    parts.push(vec![]);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        parts.last_mut().expect("parts is never empty").push(token);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Read a field with its `#[param(...)]` attributes
+fn parse_field(tokens: Vec<TokenTree>) -> Result<Field, String> {
+    let mut name = None;
+    let mut parse = None;
+    let mut display = None;
+    let mut tokens = tokens.into_iter().peekable();
+
+    // Attributes and visibility
+    let ident = loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '#' => {
+                if let Some(TokenTree::Group(group)) = tokens.next() {
+                    let mut attr = group.stream().into_iter();
+                    match (attr.next(), attr.next()) {
+                        (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(args)))
+                            if ident.to_string() == "param" =>
+                        {
+                            for arg in split_top_level(args.stream()) {
+                                match arg.as_slice() {
+                                    [TokenTree::Ident(key), TokenTree::Punct(eq), TokenTree::Literal(value)]
+                                        if eq.as_char() == '=' =>
+                                    {
+                                        let value = value.to_string();
+                                        let value = unquote(&value).ok_or_else(|| {
+                                            format!("Expected a string literal, found `{value}`")
+                                        })?;
+                                        match key.to_string().as_str() {
+                                            "name" => name = Some(value),
+                                            "parse" => parse = Some(value),
+                                            "display" => display = Some(value),
+                                            key => {
+                                                return Err(format!(
+                                                    "Unknown `param` attribute `{key}`"
+                                                ))
+                                            }
+                                        }
+                                    }
+                                    _ => {
+                                        return Err("Expected `#[param(name = \"...\")]`".to_owned())
+                                    }
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "pub" => {
+                if matches!(tokens.peek(), Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis)
+                {
+                    tokens.next();
+                }
+            }
+            Some(TokenTree::Ident(ident)) => break ident.to_string(),
+            _ => return Err("`PathParams` needs a struct with named fields".to_owned()),
+        }
+    };
+    match tokens.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == ':' => {}
+        _ => return Err("`PathParams` needs a struct with named fields".to_owned()),
+    }
+
+    // The kind of the field is given by the last segment of the type before `<`
+    let ty = tokens.collect::<Vec<_>>();
+    let outer = ty
+        .iter()
+        .take_while(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == '<'))
+        .filter_map(|token| match token {
+            TokenTree::Ident(ident) => Some(ident.to_string()),
+            _ => None,
+        })
+        .last();
+    let kind = match outer.as_deref() {
+        Some("Option") => FieldKind::Optional,
+        Some("Vec") => FieldKind::Repeated,
+        _ => FieldKind::Required,
+    };
+
+    Ok(Field {
+        name: name.unwrap_or_else(|| ident.trim_start_matches("r#").to_owned()),
+        ident,
+        kind,
+        parse: parse.unwrap_or_else(|| "::path2regex::__private::from_str".to_owned()),
+        display: display.unwrap_or_else(|| "::std::string::ToString::to_string".to_owned()),
+    })
+}
+
+fn expand(attr: TokenStream, item: &TokenStream) -> Result<TokenStream, String> {
+    let path = parse_path(attr)?;
+    let name = function_name(item)?;
+    let tokens = Parser::new()
+        .parse_str(&path)
+        .map_err(|err| format!("Invalid route \"{path}\": {err}"))?;
+
+    let mut fields = String::new();
+    let mut values = String::new();
+    let mut names = std::collections::HashMap::new();
+    for token in tokens.iter() {
+        let key = match token {
+            Token::Key(key) if !key.pattern.is_empty() => key,
+            _ => continue,
+        };
+        let field = field_name(&key.name);
+        if let Some(other) = names.insert(field.clone(), key.name.as_str()) {
+            return Err(format!(
+                "The parameters \"{other}\" and \"{}\" of \"{path}\" both have the field `{field}`",
+                key.name
+            ));
+        }
+        let (ty, value) = match key.modifier.as_str() {
+            "+" | "*" => (
+                "::std::vec::Vec<::std::string::String>",
+                format!("params({:?})", key.name),
+            ),
+            "?" => (
+                "::std::option::Option<::std::string::String>",
+                format!("param({:?}).filter(|value| !value.is_empty())", key.name),
+            ),
+            _ => ("::std::string::String", format!("param({:?})?", key.name)),
+        };
+        let doc = format!("The `{}` parameter", key.name);
+        fields += &format!("#[doc = {doc:?}] pub {field}: {ty},");
+        values += &format!("{field}: {value},");
+    }
+
+    let struct_name = format!("{}Params", camel_case(&name));
+    let doc = format!("The parameters of the `{path}` route of `{name}`");
+    let code = format!(
+        r#"
+        #[doc = {doc:?}]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct {struct_name} {{ {fields} }}
+
+        impl {struct_name} {{
+            /// The path of the route
+            pub const PATH: &'static str = {path:?};
+
+            /// Match a path and extract the parameters
+            pub fn parse_params(path: &str) -> ::std::option::Option<Self> {{
+                static MATCHER: ::std::sync::OnceLock<::path2regex::Matcher> =
+                    ::std::sync::OnceLock::new();
+                let matcher = MATCHER.get_or_init(|| {{
+                    ::path2regex::Matcher::new(Self::PATH).expect("the path is checked by `path_route`")
+                }});
+                let result = matcher.find(path)?;
+                #[allow(unused_variables)]
+                let param = |name: &str| {{
+                    result.params.get(name).and_then(|value| value.as_str()).map(|value| value.to_owned())
+                }};
+                #[allow(unused_variables)]
+                let params = |name: &str| -> ::std::vec::Vec<::std::string::String> {{
+                    result.params.get(name).and_then(|value| value.as_array()).map(|values| {{
+                        values.iter().filter_map(|value| value.as_str()).map(|value| value.to_owned()).collect()
+                    }}).unwrap_or_default()
+                }};
+                ::std::option::Option::Some(Self {{ {values} }})
+            }}
+        }}
+        "#
+    );
+    code.parse()
+        .map_err(|err| format!("Failed to generate the route params: {err}"))
+}
+
+/// Read the path from the string literal of the attribute
+fn parse_path(attr: TokenStream) -> Result<String, String> {
+    let mut tokens = attr.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        _ => return Err("Expected a path like `#[path_route(\"/user/:id\")]`".to_owned()),
+    };
+    unquote(&literal).ok_or_else(|| format!("Expected a string literal, found `{literal}`"))
+}
+
+/// Get the value of a string literal
+fn unquote(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let raw = &raw[hashes..raw.len() - hashes];
+        return raw
+            .strip_prefix('"')
+            .and_then(|raw| raw.strip_suffix('"'))
+            .map(|raw| raw.to_owned());
+    }
+
+    let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            '\n' => {
+                while chars.as_str().starts_with(char::is_whitespace) {
+                    chars.next();
+                }
+            }
+            'x' => {
+                let code = chars.as_str().get(..2)?;
+                value.push(u8::from_str_radix(code, 16).ok()? as char);
+                chars.nth(1);
+            }
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let end = rest.find('}')?;
+                value.push(char::from_u32(u32::from_str_radix(&rest[..end], 16).ok()?)?);
+                chars = rest[end + 1..].chars();
+            }
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+/// Find the name of the function the attribute is placed on
+fn function_name(item: &TokenStream) -> Result<String, String> {
+    let mut tokens = item.clone().into_iter();
+    while let Some(token) = tokens.next() {
+        if matches!(&token, TokenTree::Ident(ident) if ident.to_string() == "fn") {
+            if let Some(TokenTree::Ident(name)) = tokens.next() {
+                return Ok(name.to_string().trim_start_matches("r#").to_owned());
+            }
+        }
+    }
+    Err("`path_route` must be placed on a function".to_owned())
+}
+
+/// `handle_user` to `HandleUser`
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Turn a parameter name into a field name
+fn field_name(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+        "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override",
+        "priv", "try", "typeof", "unsized", "virtual", "yield",
+    ];
+
+    // A quoted name like `:"user.id"` can hold characters an identifier can't
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        let name = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        return field_name(&name);
+    }
+
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        format!("param_{name}")
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else if matches!(name, "self" | "Self" | "super" | "crate" | "_") {
+        format!("{name}_")
+    } else if KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.to_owned()
+    }
+}
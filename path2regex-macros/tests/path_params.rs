@@ -0,0 +1,143 @@
+use std::{fmt, str::FromStr};
+
+use path2regex::{Compiler, FromParams, MatchResult, Matcher, ToParams};
+use path2regex_macros::PathParams;
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Comments,
+    Likes,
+}
+
+impl FromStr for Tab {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "comments" => Ok(Tab::Comments),
+            "likes" => Ok(Tab::Likes),
+            _ => Err(format!("unknown tab {value:?}")),
+        }
+    }
+}
+
+impl fmt::Display for Tab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Tab::Comments => "comments",
+            Tab::Likes => "likes",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, PathParams)]
+struct UserPost {
+    pub user_id: u64,
+    #[param(name = "postId")]
+    post: String,
+    tab: Option<Tab>,
+}
+
+const USER_POST: &str = "/users/:user_id(\\d+)/posts/:postId/:tab?";
+
+fn parse_hex(value: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(value, 16)
+}
+
+fn display_hex(value: &u32) -> String {
+    format!("{value:x}")
+}
+
+#[derive(Debug, PartialEq, PathParams)]
+struct Files {
+    #[param(parse = "parse_hex", display = "display_hex")]
+    r#ref: u32,
+    path: Vec<String>,
+}
+
+#[test]
+fn should_extract_typed_params() -> anyhow::Result<()> {
+    let matcher = Matcher::new(USER_POST)?;
+    let result = matcher.find("/users/7/posts/hello/likes").unwrap();
+    let post = UserPost::try_from(&result)?;
+    assert_eq!(
+        post,
+        UserPost {
+            user_id: 7,
+            post: "hello".to_owned(),
+            tab: Some(Tab::Likes),
+        }
+    );
+    let post = matcher.find_as::<UserPost, _>("/users/7/posts/hello")?;
+    assert_eq!(post.map(|post| post.tab), Some(None));
+    assert!(matcher.find_as::<UserPost, _>("/nope")?.is_none());
+    Ok(())
+}
+
+#[test]
+fn should_render_typed_params() -> anyhow::Result<()> {
+    let compiler = Compiler::new(USER_POST)?;
+    let post = UserPost {
+        user_id: 7,
+        post: "hello".to_owned(),
+        tab: Some(Tab::Comments),
+    };
+    assert_eq!(
+        post.to_data(),
+        json!({"user_id": "7", "postId": "hello", "tab": "comments"})
+    );
+    assert_eq!(
+        compiler.render_params(&post)?,
+        "/users/7/posts/hello/comments"
+    );
+    let post = UserPost { tab: None, ..post };
+    assert_eq!(compiler.render_params(&post)?, "/users/7/posts/hello");
+    Ok(())
+}
+
+#[test]
+fn should_convert_repeated_and_custom_params() -> anyhow::Result<()> {
+    let matcher = Matcher::new("/files/:ref/:path*")?;
+    let files = matcher.find_as::<Files, _>("/files/ff/a/b")?.unwrap();
+    assert_eq!(
+        files,
+        Files {
+            r#ref: 255,
+            path: vec!["a".to_owned(), "b".to_owned()],
+        }
+    );
+    assert_eq!(
+        matcher
+            .find_as::<Files, _>("/files/10")?
+            .unwrap()
+            .path
+            .len(),
+        0
+    );
+    let compiler = Compiler::new("/files/:ref/:path*")?;
+    assert_eq!(compiler.render_params(&files)?, "/files/ff/a/b");
+    Ok(())
+}
+
+#[test]
+fn should_report_the_invalid_field() {
+    let err = UserPost::from_params(&json!({"user_id": "7"})).unwrap_err();
+    assert_eq!(err.to_string(), "Expected \"postId\" in the params");
+
+    let err = UserPost::from_params(&json!({"user_id": "x", "postId": "a"})).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Failed to parse \"user_id\" from \"x\": invalid digit found in string"
+    );
+
+    let result = MatchResult {
+        params: json!({"user_id": "7", "postId": "a", "tab": "feed"}),
+        ..Default::default()
+    };
+    let err = UserPost::try_from(&result).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Failed to parse \"tab\" from \"feed\": unknown tab \"feed\""
+    );
+}
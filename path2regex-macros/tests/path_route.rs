@@ -0,0 +1,67 @@
+use path2regex_macros::path_route;
+
+#[path_route("/user/:id")]
+fn handle_user() -> &'static str {
+    "user"
+}
+
+#[path_route(r"/files/:name(\d+)/([^/]+)/:type?")]
+fn serve_file() {}
+
+#[test]
+fn should_generate_route_params() {
+    assert_eq!(handle_user(), "user");
+    assert_eq!(HandleUserParams::PATH, "/user/:id");
+    assert_eq!(
+        HandleUserParams::parse_params("/user/123"),
+        Some(HandleUserParams {
+            id: "123".to_owned()
+        })
+    );
+    assert_eq!(HandleUserParams::parse_params("/users/123"), None);
+}
+
+#[test]
+fn should_generate_optional_and_unnamed_params() {
+    serve_file();
+    assert_eq!(
+        ServeFileParams::parse_params("/files/42/a.txt"),
+        Some(ServeFileParams {
+            name: "42".to_owned(),
+            param_0: "a.txt".to_owned(),
+            r#type: None,
+        })
+    );
+    let params = ServeFileParams::parse_params("/files/42/a/raw").unwrap();
+    assert_eq!(params.param_0, "a");
+    assert_eq!(params.r#type.as_deref(), Some("raw"));
+}
+
+#[path_route("/docs/:path+")]
+fn serve_docs() {}
+
+#[test]
+fn should_generate_repeated_params() {
+    serve_docs();
+    assert_eq!(
+        ServeDocsParams::parse_params("/docs/guide/intro"),
+        Some(ServeDocsParams {
+            path: vec!["guide".to_owned(), "intro".to_owned()]
+        })
+    );
+    assert_eq!(ServeDocsParams::parse_params("/docs"), None);
+}
+
+#[path_route("/orgs/:\"org.slug\"")]
+fn show_org() {}
+
+#[test]
+fn should_generate_fields_of_quoted_names() {
+    show_org();
+    assert_eq!(
+        ShowOrgParams::parse_params("/orgs/acme"),
+        Some(ShowOrgParams {
+            org_slug: "acme".to_owned()
+        })
+    );
+}
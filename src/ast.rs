@@ -69,23 +69,212 @@ pub struct Key {
     pub pattern: String,
     /// The modifier for the parameter
     pub modifier: String,
+    /// The value of an optional parameter missing from the path, like `1` in `:page?=1`
+    pub default: Option<String>,
+}
+
+impl Key {
+    /// Set the name of the parameter
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the prefix of the parameter
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Set the suffix of the parameter
+    pub fn with_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+
+    /// Set the regular in the parameter
+    pub fn with_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.pattern = pattern.into();
+        self
+    }
+
+    /// Set the modifier for the parameter, like `?`, `+` or `*`
+    pub fn with_modifier(mut self, modifier: impl Into<String>) -> Self {
+        self.modifier = modifier.into();
+        self
+    }
+
+    /// Set the value of the optional parameter missing from the path
+    pub fn with_default(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
+    /// Whether the key was generated for an unnamed pattern, like `(\d+)`
+    #[inline]
+    fn is_unnamed(&self) -> bool {
+        !self.name.is_empty() && self.name.chars().all(|c| c.is_ascii_digit())
+    }
+}
+
+/// Write `text` escaping the characters that have a meaning in a path
+fn write_escaped(f: &mut std::fmt::Formatter<'_>, text: &str) -> std::fmt::Result {
+    text.chars().try_for_each(|c| write_escaped_char(f, c))
+}
+
+/// Write `c` escaping it when it has a meaning in a path
+fn write_escaped_char(f: &mut std::fmt::Formatter<'_>, c: char) -> std::fmt::Result {
+    // `=` starts the default value of a key before it
+    if matches!(
+        c,
+        '{' | '}' | '(' | ')' | ':' | '*' | '+' | '?' | '=' | '\\'
+    ) {
+        f.write_str("\\")?;
+    }
+    write!(f, "{c}")
 }
 
 impl std::fmt::Display for Key {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
+        f.write_str("{")?;
+        write_escaped(f, &self.prefix)?;
+        if !self.name.is_empty() && !self.is_unnamed() {
+            let plain = self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if plain {
+                write!(f, ":{}", self.name)?;
+            } else {
+                write!(f, ":\"{}\"", self.name)?;
+            }
+        }
+        if !self.pattern.is_empty() {
+            write!(f, "({})", self.pattern)?;
+        }
+        write_escaped(f, &self.suffix)?;
+        write!(f, "}}{}", self.modifier)?;
+        if let Some(default) = &self.default {
+            f.write_str("=")?;
+            for c in default.chars() {
+                if matches!(c, '/' | '#') {
+                    f.write_str("\\")?;
+                }
+                write_escaped_char(f, c)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl std::fmt::Debug for Key {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Key")
-            .field("name", &self.name)
+        let mut s = f.debug_struct("Key");
+        s.field("name", &self.name)
             .field("prefix", &self.prefix)
             .field("suffix", &self.suffix)
             .field("pattern", &self.pattern)
-            .field("modifier", &self.modifier)
-            .finish()
+            .field("modifier", &self.modifier);
+        if let Some(default) = &self.default {
+            s.field("default", default);
+        }
+        s.finish()
+    }
+}
+
+/// The fields of the key as a JSON object, leaving out `default` when unset like `Serialize`
+#[cfg(any(feature = "compile", feature = "match"))]
+impl From<&Key> for serde_json::Value {
+    fn from(key: &Key) -> Self {
+        let mut value = serde_json::json!({
+            "name": key.name,
+            "prefix": key.prefix,
+            "suffix": key.suffix,
+            "pattern": key.pattern,
+            "modifier": key.modifier,
+        });
+        if let Some(default) = &key.default {
+            value["default"] = default.as_str().into();
+        }
+        value
+    }
+}
+
+#[cfg(any(feature = "compile", feature = "match"))]
+impl From<Key> for serde_json::Value {
+    #[inline]
+    fn from(key: Key) -> Self {
+        Self::from(&key)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde_core::Serialize for Key {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde_core::Serializer,
+    {
+        use serde_core::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Key", 6)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("prefix", &self.prefix)?;
+        state.serialize_field("suffix", &self.suffix)?;
+        state.serialize_field("pattern", &self.pattern)?;
+        state.serialize_field("modifier", &self.modifier)?;
+        match &self.default {
+            Some(default) => state.serialize_field("default", default)?,
+            None => state.skip_field("default")?,
+        }
+        state.end()
+    }
+}
+
+/// Missing fields are deserialized as empty strings
+#[cfg(feature = "serde")]
+impl<'de> serde_core::Deserialize<'de> for Key {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde_core::Deserializer<'de>,
+    {
+        use serde_core::de::{IgnoredAny, MapAccess, Visitor};
+
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = Key;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("struct Key")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut key = Key::default();
+                while let Some(field) = map.next_key::<String>()? {
+                    match field.as_str() {
+                        "name" => key.name = map.next_value()?,
+                        "prefix" => key.prefix = map.next_value()?,
+                        "suffix" => key.suffix = map.next_value()?,
+                        "pattern" => key.pattern = map.next_value()?,
+                        "modifier" => key.modifier = map.next_value()?,
+                        "default" => key.default = map.next_value()?,
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(key)
+            }
+        }
+
+        deserializer.deserialize_struct(
+            "Key",
+            &["name", "prefix", "suffix", "pattern", "modifier", "default"],
+            KeyVisitor,
+        )
     }
 }
 
@@ -98,30 +287,38 @@ pub enum Token {
     Key(Key),
 }
 
+impl Token {
+    /// Get the modifier of a key, `""` for a required key, or `None` for static text
+    #[inline]
+    pub fn modifier(&self) -> Option<&str> {
+        match self {
+            Token::Static(_) => None,
+            Token::Key(key) => Some(&key.modifier),
+        }
+    }
+
+    /// Whether the token is a key with a modifier, like `?`, `+` or `*`
+    #[inline]
+    pub fn has_modifier(&self) -> bool {
+        self.modifier()
+            .map_or(false, |modifier| !modifier.is_empty())
+    }
+}
+
 impl std::fmt::Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Token::Static(s) => f.write_str(s),
-            Token::Key(Key {
-                name,
-                prefix,
-                suffix,
-                pattern,
-                modifier,
-            }) => f
-                .debug_struct("Token")
-                .field("name", name)
-                .field("prefix", prefix)
-                .field("suffix", suffix)
-                .field("pattern", pattern)
-                .field("modifier", modifier)
-                .finish(),
+            Token::Static(s) => write_escaped(f, s),
+            Token::Key(key) => std::fmt::Display::fmt(key, f),
         }
     }
 }
 
 impl std::fmt::Debug for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Display::fmt(&self, f)
+        match self {
+            Token::Static(s) => f.debug_tuple("Static").field(s).finish(),
+            Token::Key(key) => f.debug_tuple("Key").field(key).finish(),
+        }
     }
 }
@@ -0,0 +1,338 @@
+//! C API of the [`Matcher`](struct.Matcher.html) and the [`Compiler`](struct.Compiler.html)
+//!
+//! Build the library with `cargo rustc --release --features capi --crate-type cdylib`
+//! and include `include/path2regex.h`.
+//!
+//! Every function catches panics and checks its pointers. Failures are reported
+//! through `out_err` (when it is not `NULL`) and [`p2r_last_error_message`].
+//! Strings returned by the library must be released with [`p2r_string_free`].
+#![allow(unsafe_code)]
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{internal::DataValue, Compiler, CompilerOptions, Matcher, MatcherOptions};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An opaque handle of a [`Matcher`](struct.Matcher.html)
+pub struct P2rMatcher(Matcher);
+
+/// An opaque handle of a [`Compiler`](struct.Compiler.html)
+pub struct P2rCompiler(Compiler);
+
+/// The Configuration of a [`P2rMatcher`], `NULL` strings keep their default value
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct P2rMatcherOptions {
+    /// Set the default delimiter for repeat parameters.
+    pub delimiter: *const c_char,
+    /// List of characters to automatically consider prefixes when parsing.
+    pub prefixes: *const c_char,
+    /// List of characters that can also be "end" characters.
+    pub ends_with: *const c_char,
+    /// When `true` the regexp will be case sensitive.
+    pub sensitive: bool,
+    /// When `true` the regexp won't allow an optional trailing delimiter to match.
+    pub strict: bool,
+    /// When `true` the regexp will match to the end of the string.
+    pub end: bool,
+    /// When `true` the regexp will match from the beginning of the string.
+    pub start: bool,
+}
+
+/// The Configuration of a [`P2rCompiler`], `NULL` strings keep their default value
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct P2rCompilerOptions {
+    /// Set the default delimiter for repeat parameters.
+    pub delimiter: *const c_char,
+    /// List of characters to automatically consider prefixes when parsing.
+    pub prefixes: *const c_char,
+    /// When `true` the regexp will be case sensitive.
+    pub sensitive: bool,
+    /// When `false` the function can produce an invalid (unmatched) path.
+    pub validate: bool,
+}
+
+/// Get the default configuration of a [`P2rMatcher`]
+#[no_mangle]
+pub extern "C" fn p2r_matcher_options_default() -> P2rMatcherOptions {
+    let MatcherOptions {
+        sensitive,
+        strict,
+        end,
+        start,
+        ..
+    } = MatcherOptions::default();
+    P2rMatcherOptions {
+        delimiter: ptr::null(),
+        prefixes: ptr::null(),
+        ends_with: ptr::null(),
+        sensitive,
+        strict,
+        end,
+        start,
+    }
+}
+
+/// Get the default configuration of a [`P2rCompiler`]
+#[no_mangle]
+pub extern "C" fn p2r_compiler_options_default() -> P2rCompilerOptions {
+    let CompilerOptions {
+        sensitive,
+        validate,
+        ..
+    } = CompilerOptions::default();
+    P2rCompilerOptions {
+        delimiter: ptr::null(),
+        prefixes: ptr::null(),
+        sensitive,
+        validate,
+    }
+}
+
+/// Create a matcher, returns `NULL` on error
+///
+/// # Safety
+///
+/// `pattern` must be a valid C string, `options` and `out_err` must be `NULL` or valid pointers.
+/// The matcher must be released with [`p2r_matcher_free`].
+#[no_mangle]
+pub unsafe extern "C" fn p2r_matcher_new(
+    pattern: *const c_char,
+    options: *const P2rMatcherOptions,
+    out_err: *mut *mut c_char,
+) -> *mut P2rMatcher {
+    guard(out_err, ptr::null_mut(), || {
+        let pattern = to_str(pattern, "pattern")?;
+        let options = matcher_options(options)?;
+        let matcher = Matcher::new_with_options(pattern, options)?;
+        Ok(Box::into_raw(Box::new(P2rMatcher(matcher))))
+    })
+}
+
+/// Match a path, returns `true` when it matches
+///
+/// The params are written as a JSON object to `out_json` (when it is not `NULL`),
+/// and must be released with [`p2r_string_free`].
+///
+/// # Safety
+///
+/// `matcher` must come from [`p2r_matcher_new`], `path` must be a valid C string
+/// and `out_json` must be `NULL` or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn p2r_matcher_find(
+    matcher: *const P2rMatcher,
+    path: *const c_char,
+    out_json: *mut *mut c_char,
+) -> bool {
+    if !out_json.is_null() {
+        *out_json = ptr::null_mut();
+    }
+    guard(ptr::null_mut(), false, || {
+        let matcher = matcher
+            .as_ref()
+            .ok_or_else(|| anyhow!("`matcher` is null"))?;
+        let path = to_str(path, "path")?;
+        match matcher.0.find(path) {
+            Some(result) => {
+                if !out_json.is_null() {
+                    let json = serde_json::to_string(&result.params)?;
+                    *out_json = to_c_string(json)?.into_raw();
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    })
+}
+
+/// Release a matcher
+///
+/// # Safety
+///
+/// `matcher` must be `NULL` or come from [`p2r_matcher_new`], and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn p2r_matcher_free(matcher: *mut P2rMatcher) {
+    if !matcher.is_null() {
+        drop(Box::from_raw(matcher));
+    }
+}
+
+/// Create a compiler, returns `NULL` on error
+///
+/// # Safety
+///
+/// `pattern` must be a valid C string, `options` and `out_err` must be `NULL` or valid pointers.
+/// The compiler must be released with [`p2r_compiler_free`].
+#[no_mangle]
+pub unsafe extern "C" fn p2r_compiler_new(
+    pattern: *const c_char,
+    options: *const P2rCompilerOptions,
+    out_err: *mut *mut c_char,
+) -> *mut P2rCompiler {
+    guard(out_err, ptr::null_mut(), || {
+        let pattern = to_str(pattern, "pattern")?;
+        let options = compiler_options(options)?;
+        let compiler = Compiler::new_with_options(pattern, options)?;
+        Ok(Box::into_raw(Box::new(P2rCompiler(compiler))))
+    })
+}
+
+/// Render the parameters given as a JSON object into a path, returns `NULL` on error
+///
+/// The path must be released with [`p2r_string_free`].
+///
+/// # Safety
+///
+/// `compiler` must come from [`p2r_compiler_new`], `data_json` must be a valid C string
+/// and `out_err` must be `NULL` or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn p2r_compiler_render(
+    compiler: *const P2rCompiler,
+    data_json: *const c_char,
+    out_err: *mut *mut c_char,
+) -> *mut c_char {
+    guard(out_err, ptr::null_mut(), || {
+        let compiler = compiler
+            .as_ref()
+            .ok_or_else(|| anyhow!("`compiler` is null"))?;
+        let data: DataValue = serde_json::from_str(to_str(data_json, "data_json")?)?;
+        let path = compiler.0.render(&data)?;
+        Ok(to_c_string(path)?.into_raw())
+    })
+}
+
+/// Release a compiler
+///
+/// # Safety
+///
+/// `compiler` must be `NULL` or come from [`p2r_compiler_new`], and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn p2r_compiler_free(compiler: *mut P2rCompiler) {
+    if !compiler.is_null() {
+        drop(Box::from_raw(compiler));
+    }
+}
+
+/// Get the message of the last error on the current thread, `NULL` if the last call succeeded
+///
+/// The message is owned by the library and stays valid until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn p2r_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Release a string returned by the library
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by the library, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn p2r_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Run `f`, turning errors and panics into the last error and `fallback`
+unsafe fn guard<T>(out_err: *mut *mut c_char, fallback: T, f: impl FnOnce() -> Result<T>) -> T {
+    if !out_err.is_null() {
+        *out_err = ptr::null_mut();
+    }
+    let result = catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown error".to_owned());
+        Err(anyhow!("panicked: {message}"))
+    });
+    match result {
+        Ok(value) => {
+            LAST_ERROR.with(|last| last.borrow_mut().take());
+            value
+        }
+        Err(err) => {
+            let message = CString::new(err.to_string().replace('\0', ""))
+                .expect("nul bytes have been removed");
+            if !out_err.is_null() {
+                *out_err = message.clone().into_raw();
+            }
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+            fallback
+        }
+    }
+}
+
+#[inline]
+fn to_c_string(s: String) -> Result<CString> {
+    CString::new(s).map_err(|err| anyhow!("Unexpected nul byte at {}", err.nul_position()))
+}
+
+#[inline]
+unsafe fn to_str<'a>(s: *const c_char, name: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(anyhow!("`{name}` is null"));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|err| anyhow!("`{name}` is not valid UTF-8: {err}"))
+}
+
+#[inline]
+unsafe fn to_option_string(s: *const c_char, name: &str) -> Result<Option<String>> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    to_str(s, name).map(|s| Some(s.to_owned()))
+}
+
+unsafe fn matcher_options(options: *const P2rMatcherOptions) -> Result<MatcherOptions> {
+    let mut result = MatcherOptions::default();
+    if let Some(options) = options.as_ref() {
+        if let Some(delimiter) = to_option_string(options.delimiter, "delimiter")? {
+            result.delimiter = delimiter.into();
+        }
+        if let Some(prefixes) = to_option_string(options.prefixes, "prefixes")? {
+            result.prefixes = prefixes;
+        }
+        if let Some(ends_with) = to_option_string(options.ends_with, "ends_with")? {
+            result.ends_with = ends_with;
+        }
+        result.sensitive = options.sensitive;
+        result.strict = options.strict;
+        result.end = options.end;
+        result.start = options.start;
+    }
+    Ok(result)
+}
+
+unsafe fn compiler_options(options: *const P2rCompilerOptions) -> Result<CompilerOptions> {
+    let mut result = CompilerOptions::default();
+    if let Some(options) = options.as_ref() {
+        if let Some(delimiter) = to_option_string(options.delimiter, "delimiter")? {
+            result.delimiter = delimiter.into();
+        }
+        if let Some(prefixes) = to_option_string(options.prefixes, "prefixes")? {
+            result.prefixes = prefixes;
+        }
+        result.sensitive = options.sensitive;
+        result.validate = options.validate;
+    }
+    Ok(result)
+}
@@ -1,26 +1,80 @@
 //! The Builder of the [`Compiler`](struct.Compiler.html)
 
-use anyhow::Result;
+use std::collections::HashMap;
 
+use anyhow::{anyhow, Context, Result};
+
+use super::resolve_value;
 use crate::{
-    internal::{type_of, FnStrWithKey},
+    internal::{
+        repeat_separator_of, type_of, DataValue, FnNumberWithKey, FnStrWithKey, FnStringifyWithKey,
+        FnTryStrWithKey,
+    },
+    re::preflight::preflight,
     try_into_with::TryIntoWith,
-    Compiler, Key, ParserOptions, Token,
+    Compiler, Delimiters, Key, ParserOptions, Token,
 };
 
+#[cfg(feature = "match")]
+use crate::MatcherOptions;
+
 /// The Configuration of the [`Compiler`](struct.Compiler.html)
 #[derive(Clone)]
 pub struct CompilerOptions {
-    /// Set the default delimiter for repeat parameters. (default: `'/'`)
-    pub delimiter: String,
+    /// The delimiters of a path segment, the first one separates repeated values. (default: `"/#?"`)
+    pub delimiter: Delimiters,
     /// List of characters to automatically consider prefixes when parsing.
     pub prefixes: String,
+    /// Characters that start a path segment besides the delimiter, like `:` in `urn:isbn:12345`.
+    /// They are literal text in the path and are considered prefixes. (default: `None`)
+    pub start_delimiter: Option<String>,
+    /// When `true` a `?` right before a parameter name starts the parameter like `:`,
+    /// like `/users?page`. (default: `false`)
+    pub query_params: bool,
     /// When `true` the regexp will be case sensitive. (default: `false`)
     pub sensitive: bool,
     /// Function for encoding input strings for output.
     pub encode: FnStrWithKey,
+    /// Function for encoding input strings for output, failing the render with an
+    /// [`EncodeError`](struct.EncodeError.html). It is used instead of `encode` when set. (default: `None`)
+    pub try_encode: Option<FnTryStrWithKey>,
+    /// Function for encoding the strings of a parameter by name, used instead of `encode`
+    /// for each of its values. (default: empty)
+    pub key_encoders: HashMap<String, Encoder>,
+    /// What to do with the delimiters in the values of the parameters, which would split
+    /// them into more segments, like `/` in `a/b`. (default: `DelimiterPolicy::Allow`)
+    pub delimiter_in_value: DelimiterPolicy,
     /// When `false` the function can produce an invalid (unmatched) path. (default: `true`)
     pub validate: bool,
+    /// When `true` the data not used by the parameters is appended as a query string,
+    /// like `?tab=posts&page=2`. (default: `false`)
+    pub append_query: bool,
+    /// Fail to render a path longer than this many bytes, with a
+    /// [`RenderError::PathTooLong`](enum.RenderError.html#variant.PathTooLong). (default: `None`)
+    pub max_rendered_length: Option<usize>,
+    /// The values of the parameters absent from the data, validated and encoded like the
+    /// data. A parameter given `null` is still left out. (default: empty)
+    pub defaults: HashMap<String, DataValue>,
+    /// Join the values of the repeated parameters with this separator, like `,` in `/tags/a,b,c`,
+    /// writing their prefix and suffix once around the values. (default: `None`)
+    pub repeat_separator: Option<String>,
+    /// The separator of a repeated parameter by name, overriding `repeat_separator`. (default: empty)
+    pub repeat_separators: HashMap<String, String>,
+    /// When `true` rendering fails on the data not used by any parameter, unless it is
+    /// appended as a query string with `append_query`. (default: `false`)
+    pub deny_unknown_keys: bool,
+    /// Function turning the values that are not strings, numbers or arrays into strings,
+    /// like [`stringify_bool`](fn.stringify_bool.html). Its strings are encoded and validated,
+    /// and the values it leaves out are missing. (default: `None`)
+    pub stringify: Option<FnStringifyWithKey>,
+    /// Function turning the numbers into strings, like
+    /// [`number_formats::integer_lossless`](number_formats/fn.integer_lossless.html).
+    /// (default: `None`, for [`number_formats::shortest`](number_formats/fn.shortest.html))
+    pub number_format: Option<FnNumberWithKey>,
+    /// When `true` a parameter named with dots, like `:"user.id"`, takes its value by walking
+    /// the data, like `data["user"]["id"]`, where a segment like `0` also indexes an array.
+    /// (default: `false`)
+    pub nested_lookup: bool,
 }
 
 impl Default for CompilerOptions {
@@ -28,20 +82,125 @@ impl Default for CompilerOptions {
         let ParserOptions {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
         } = ParserOptions::default();
         Self {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
             sensitive: false,
             encode: |x, _| x.to_owned(),
+            try_encode: None,
+            key_encoders: HashMap::new(),
+            delimiter_in_value: DelimiterPolicy::Allow,
             validate: true,
+            append_query: false,
+            max_rendered_length: None,
+            defaults: HashMap::new(),
+            repeat_separator: None,
+            repeat_separators: HashMap::new(),
+            deny_unknown_keys: false,
+            stringify: None,
+            number_format: None,
+            nested_lookup: false,
+        }
+    }
+}
+
+impl CompilerOptions {
+    /// The function encoding the strings of a key
+    pub(crate) fn encode_of(&self, key: &Key, overrides: &RenderOverrides) -> Encoder {
+        if let Some(encoder) = self.key_encoders.get(&key.name) {
+            return *encoder;
+        }
+        match (overrides.encode, self.try_encode) {
+            (Some(encode), _) => Encoder::Infallible(encode),
+            (None, Some(try_encode)) => Encoder::Fallible(try_encode),
+            (None, None) => Encoder::Infallible(self.encode),
+        }
+    }
+
+    /// The separator of the values of a repeated key, `None` to repeat its prefix and suffix
+    pub(crate) fn repeat_separator_of(&self, key: &Key) -> Option<&str> {
+        repeat_separator_of(&self.repeat_separators, &self.repeat_separator, key)
+    }
+}
+
+#[cfg(feature = "match")]
+impl From<MatcherOptions> for CompilerOptions {
+    #[inline]
+    fn from(options: MatcherOptions) -> Self {
+        let MatcherOptions {
+            delimiter,
+            prefixes,
+            start_delimiter,
+            query_params,
+            sensitive,
+            repeat_separator,
+            repeat_separators,
+            ..
+        } = options;
+        Self {
+            delimiter,
+            prefixes,
+            start_delimiter,
+            query_params,
+            sensitive,
+            repeat_separator,
+            repeat_separators,
+            ..Default::default()
         }
     }
 }
 
 impl std::fmt::Display for CompilerOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
+        write!(
+            f,
+            "delimiter={:?}, prefixes={:?}, sensitive={}, validate={}",
+            self.delimiter, self.prefixes, self.sensitive, self.validate
+        )?;
+        if let Some(start_delimiter) = &self.start_delimiter {
+            write!(f, ", start_delimiter={start_delimiter:?}")?;
+        }
+        if self.query_params {
+            write!(f, ", query_params=true")?;
+        }
+        if self.delimiter_in_value != DelimiterPolicy::Allow {
+            write!(f, ", delimiter_in_value={:?}", self.delimiter_in_value)?;
+        }
+        if self.append_query {
+            write!(f, ", append_query=true")?;
+        }
+        if let Some(max_rendered_length) = self.max_rendered_length {
+            write!(f, ", max_rendered_length={max_rendered_length}")?;
+        }
+        if !self.defaults.is_empty() {
+            let defaults = self
+                .defaults
+                .iter()
+                .collect::<std::collections::BTreeMap<_, _>>();
+            write!(f, ", defaults={defaults:?}")?;
+        }
+        if let Some(repeat_separator) = &self.repeat_separator {
+            write!(f, ", repeat_separator={repeat_separator:?}")?;
+        }
+        if !self.repeat_separators.is_empty() {
+            let separators = self
+                .repeat_separators
+                .iter()
+                .collect::<std::collections::BTreeMap<_, _>>();
+            write!(f, ", repeat_separators={separators:?}")?;
+        }
+        if self.deny_unknown_keys {
+            write!(f, ", deny_unknown_keys=true")?;
+        }
+        if self.nested_lookup {
+            write!(f, ", nested_lookup=true")?;
+        }
+        Ok(())
     }
 }
 
@@ -50,9 +209,147 @@ impl std::fmt::Debug for CompilerOptions {
         f.debug_struct("CompilerOptions")
             .field("delimiter", &self.delimiter)
             .field("prefixes", &self.prefixes)
+            .field("start_delimiter", &self.start_delimiter)
+            .field("query_params", &self.query_params)
             .field("sensitive", &self.sensitive)
             .field("encode", &type_of(self.encode))
+            .field("try_encode", &self.try_encode.map(type_of))
+            .field(
+                "key_encoders",
+                &self
+                    .key_encoders
+                    .iter()
+                    .collect::<std::collections::BTreeMap<_, _>>(),
+            )
+            .field("delimiter_in_value", &self.delimiter_in_value)
             .field("validate", &self.validate)
+            .field("append_query", &self.append_query)
+            .field("max_rendered_length", &self.max_rendered_length)
+            .field(
+                "defaults",
+                &self
+                    .defaults
+                    .iter()
+                    .collect::<std::collections::BTreeMap<_, _>>(),
+            )
+            .field("repeat_separator", &self.repeat_separator)
+            .field(
+                "repeat_separators",
+                &self
+                    .repeat_separators
+                    .iter()
+                    .collect::<std::collections::BTreeMap<_, _>>(),
+            )
+            .field("deny_unknown_keys", &self.deny_unknown_keys)
+            .field("stringify", &self.stringify.map(type_of))
+            .field("number_format", &self.number_format.map(type_of))
+            .field("nested_lookup", &self.nested_lookup)
+            .finish()
+    }
+}
+
+/// A function encoding the strings of a parameter, which may fail
+#[derive(Clone, Copy)]
+pub enum Encoder {
+    /// Encode every string
+    Infallible(FnStrWithKey),
+    /// Encode a string or fail the render
+    Fallible(FnTryStrWithKey),
+}
+
+impl Encoder {
+    /// Encode the string of the key
+    pub fn encode(&self, value: &str, key: &Key) -> Result<String, EncodeError> {
+        match self {
+            Encoder::Infallible(encode) => Ok(encode(value, key)),
+            Encoder::Fallible(try_encode) => try_encode(value, key),
+        }
+    }
+}
+
+impl std::fmt::Debug for Encoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoder::Infallible(encode) => f
+                .debug_tuple("Infallible")
+                .field(&type_of(*encode))
+                .finish(),
+            Encoder::Fallible(try_encode) => f
+                .debug_tuple("Fallible")
+                .field(&type_of(*try_encode))
+                .finish(),
+        }
+    }
+}
+
+/// The failure of a [`try_encode`](struct.CompilerOptions.html#structfield.try_encode) hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeError {
+    /// Why the value can't be encoded
+    pub message: String,
+}
+
+impl EncodeError {
+    /// Create the failure of an encoder
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+/// What the [`Compiler`](struct.Compiler.html) does with the delimiters in the values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelimiterPolicy {
+    /// Render the delimiters as they are
+    Allow,
+    /// Fail to render a value with a delimiter, except for the repeated parameters
+    Reject,
+    /// Percent-encode the delimiters and `%`, like `a%2Fb` for `a/b` and `100%25` for `100%`
+    Encode,
+}
+
+impl Default for DelimiterPolicy {
+    #[inline]
+    fn default() -> Self {
+        DelimiterPolicy::Allow
+    }
+}
+
+/// The case of the rendered parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderCase {
+    /// Lowercase the parameters
+    Lower,
+    /// Uppercase the parameters
+    Upper,
+}
+
+/// The options of the [`Compiler`](struct.Compiler.html) overridden for a single render
+#[derive(Clone, Copy, Default)]
+pub struct RenderOverrides {
+    /// Override `validate` of the options
+    pub validate: Option<bool>,
+    /// Override `encode` of the options
+    pub encode: Option<FnStrWithKey>,
+    /// Change the case of the parameters, after encoding them and before validating them
+    pub case: Option<RenderCase>,
+}
+
+impl std::fmt::Debug for RenderOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderOverrides")
+            .field("validate", &self.validate)
+            .field("encode", &self.encode.map(type_of))
+            .field("case", &self.case)
             .finish()
     }
 }
@@ -62,6 +359,7 @@ impl std::fmt::Debug for CompilerOptions {
 pub struct CompilerBuilder<I> {
     source: I,
     options: CompilerOptions,
+    prefix_segment: String,
 }
 
 impl<I> CompilerBuilder<I>
@@ -70,49 +368,144 @@ where
 {
     /// Create a builder of the [`Compiler`](struct.Compiler.html)
     pub fn new(source: I) -> Self {
-        Self {
-            source,
-            options: Default::default(),
-        }
+        Self::new_with_options(source, Default::default())
     }
 
     /// Create a builder of the [`Compiler`](struct.Compiler.html) with the options
     pub fn new_with_options(source: I, options: CompilerOptions) -> Self {
-        Self { source, options }
+        Self {
+            source,
+            options,
+            prefix_segment: String::new(),
+        }
     }
 
     /// build a builder of the [`Compiler`](struct.Compiler.html)
     pub fn build(&self) -> Result<Compiler> {
-        let tokens = self
+        let mut tokens = self
             .source
             .clone()
             .try_into_with(&ParserOptions::from(self.options.clone()))?;
+        if !self.prefix_segment.is_empty() {
+            match tokens.first_mut() {
+                Some(Token::Static(first)) => first.insert_str(0, &self.prefix_segment),
+                _ => tokens.insert(0, Token::Static(self.prefix_segment.clone())),
+            }
+        }
         let matches = tokens
             .iter()
             .map(|token| match token {
-                Token::Static(_) => None,
-                Token::Key(Key { pattern, .. }) => {
-                    let pattern = &format!("^(?:{pattern})$");
-                    let re = regex::RegexBuilder::new(pattern)
-                        .case_insensitive(self.options.sensitive)
-                        .build();
-                    re.ok()
+                Token::Static(_) => Ok(None),
+                Token::Key(Key { name, pattern, .. }) => {
+                    let context = || format!("Invalid pattern \"{pattern}\" of \"{name}\"");
+                    preflight(pattern, None).with_context(context)?;
+                    regex::RegexBuilder::new(&format!("^(?:{pattern})$"))
+                        .case_insensitive(!self.options.sensitive)
+                        .build()
+                        .map(Some)
+                        .with_context(context)
                 }
             })
-            .collect::<Vec<_>>();
-        Ok(Compiler {
+            .collect::<Result<Vec<_>>>()?;
+        let static_path = tokens
+            .iter()
+            .map(|token| match token {
+                Token::Static(token) => Some(token.as_str()),
+                Token::Key(_) => None,
+            })
+            .collect::<Option<String>>()
+            .map(String::into_boxed_str);
+        let compiler = Compiler {
             tokens,
             matches,
+            static_path,
             options: self.options.clone(),
-        })
+        };
+
+        let mut encoders = compiler.options.key_encoders.keys().collect::<Vec<_>>();
+        encoders.sort();
+        for name in encoders {
+            let known = compiler
+                .tokens
+                .iter()
+                .any(|token| matches!(token, Token::Key(key) if &key.name == name));
+            if !known {
+                return Err(anyhow!(
+                    "Expected \"{name}\" to be a parameter of the path to have an encoder"
+                ));
+            }
+        }
+
+        let mut defaults = compiler.options.defaults.iter().collect::<Vec<_>>();
+        defaults.sort_by_key(|(name, _)| *name);
+        for (name, value) in defaults {
+            let (i, key) = compiler
+                .tokens
+                .iter()
+                .enumerate()
+                .find_map(|(i, token)| match token {
+                    Token::Key(key) if &key.name == name => Some((i, key)),
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    anyhow!("Expected \"{name}\" to be a parameter of the path to have a default")
+                })?;
+            compiler
+                .render_key(
+                    i,
+                    key,
+                    resolve_value(Some(value), key, &compiler.options),
+                    &mut String::new(),
+                    RenderOverrides::default(),
+                    None,
+                )
+                .with_context(|| format!("Invalid default of \"{name}\""))?;
+        }
+        Ok(compiler)
     }
 
-    /// Set the default delimiter for repeat parameters. (default: `'/'`)
-    pub fn set_delimiter<S>(&mut self, delimiter: S) -> &mut Self
-    where
-        S: AsRef<str>,
-    {
-        self.options.delimiter = delimiter.as_ref().to_owned();
+    /// build a [`Compiler`](struct.Compiler.html) and render parameters into a path at once
+    pub fn build_and_render(self, data: &DataValue) -> Result<String> {
+        self.build()?.render(data)
+    }
+
+    /// Prepend a static segment to the path, like `/api/v2`, in front of every rendered path
+    ///
+    /// The segment is literal text, so `:` or `{` in it are not read as parameters.
+    /// Calling it again appends to the segments already given.
+    pub fn with_prefix_segment(&mut self, segment: &str) -> &mut Self {
+        self.prefix_segment.push_str(segment);
+        self
+    }
+
+    /// Set the options of the parser, overwriting `delimiter`, `prefixes`, `start_delimiter` and `query_params`
+    pub fn set_parser_options(&mut self, options: ParserOptions) -> &mut Self {
+        let ParserOptions {
+            delimiter,
+            prefixes,
+            start_delimiter,
+            query_params,
+        } = options;
+        self.options.delimiter = delimiter;
+        self.options.prefixes = prefixes;
+        self.options.start_delimiter = start_delimiter;
+        self.options.query_params = query_params;
+        self
+    }
+
+    /// Get the options of the parser
+    pub fn parser_options(&self) -> ParserOptions {
+        ParserOptions {
+            delimiter: self.options.delimiter.clone(),
+            prefixes: self.options.prefixes.clone(),
+            start_delimiter: self.options.start_delimiter.clone(),
+            query_params: self.options.query_params,
+        }
+    }
+
+    /// The delimiters of a path segment, the first one separates repeated values. (default: `"/#?"`)
+    pub fn set_delimiter(&mut self, delimiter: impl Into<Delimiters>) -> &mut Self {
+        self.options.delimiter = delimiter.into();
         self
     }
 
@@ -125,6 +518,22 @@ where
         self
     }
 
+    /// Characters that start a path segment besides the delimiter, like `:` in `urn:isbn:12345`.
+    /// They are literal text in the path and are considered prefixes. (default: `None`)
+    pub fn set_start_delimiter<S>(&mut self, start_delimiter: S) -> &mut Self
+    where
+        S: AsRef<str>,
+    {
+        self.options.start_delimiter = Some(start_delimiter.as_ref().to_owned());
+        self
+    }
+
+    /// When `true` a `?` right before a parameter name starts the parameter like `:`.
+    pub fn set_query_params(&mut self, yes: bool) -> &mut Self {
+        self.options.query_params = yes;
+        self
+    }
+
     /// When `true` the regexp will be case sensitive. (default: `false`)
     pub fn set_sensitive(&mut self, yes: bool) -> &mut Self {
         self.options.sensitive = yes;
@@ -137,9 +546,107 @@ where
         self
     }
 
-    ///
+    /// Function for encoding input strings for output, which may fail the render.
+    pub fn set_try_encode(&mut self, try_encode: FnTryStrWithKey) -> &mut Self {
+        self.options.try_encode = Some(try_encode);
+        self
+    }
+
+    /// Function for encoding the strings of a parameter by name, used instead of `encode`.
+    pub fn set_key_encoder(&mut self, name: impl Into<String>, encode: FnStrWithKey) -> &mut Self {
+        self.options
+            .key_encoders
+            .insert(name.into(), Encoder::Infallible(encode));
+        self
+    }
+
+    /// Function for encoding the strings of a parameter by name, which may fail the render.
+    pub fn set_key_try_encoder(
+        &mut self,
+        name: impl Into<String>,
+        try_encode: FnTryStrWithKey,
+    ) -> &mut Self {
+        self.options
+            .key_encoders
+            .insert(name.into(), Encoder::Fallible(try_encode));
+        self
+    }
+
+    /// What to do with the delimiters in the values of the parameters. (default: `DelimiterPolicy::Allow`)
+    pub fn set_delimiter_in_value(&mut self, policy: DelimiterPolicy) -> &mut Self {
+        self.options.delimiter_in_value = policy;
+        self
+    }
+
+    /// When `false` the function can produce an invalid (unmatched) path. (default: `true`)
     pub fn set_validate(&mut self, validate: bool) -> &mut Self {
         self.options.validate = validate;
         self
     }
+
+    /// When `true` the data not used by the parameters is appended as a query string,
+    /// like `?tab=posts&page=2`. (default: `false`)
+    pub fn set_append_query(&mut self, yes: bool) -> &mut Self {
+        self.options.append_query = yes;
+        self
+    }
+
+    /// Fail to render a path longer than this many bytes, with a
+    /// [`RenderError::PathTooLong`](enum.RenderError.html#variant.PathTooLong). (default: `None`)
+    pub fn set_max_rendered_length(&mut self, limit: usize) -> &mut Self {
+        self.options.max_rendered_length = Some(limit);
+        self
+    }
+
+    /// Function turning the values that are not strings, numbers or arrays into strings.
+    pub fn set_stringify(&mut self, stringify: FnStringifyWithKey) -> &mut Self {
+        self.options.stringify = Some(stringify);
+        self
+    }
+
+    /// Function turning the numbers into strings. (default: `None`, trimming the `.0` of the whole floats)
+    pub fn set_number_format(&mut self, number_format: FnNumberWithKey) -> &mut Self {
+        self.options.number_format = Some(number_format);
+        self
+    }
+
+    /// When `true` a parameter named with dots, like `:"user.id"`, is looked up in nested data.
+    pub fn set_nested_lookup(&mut self, yes: bool) -> &mut Self {
+        self.options.nested_lookup = yes;
+        self
+    }
+
+    /// When `true` rendering fails on the data not used by any parameter. (default: `false`)
+    pub fn set_deny_unknown_keys(&mut self, yes: bool) -> &mut Self {
+        self.options.deny_unknown_keys = yes;
+        self
+    }
+
+    /// Set the value of a parameter absent from the data, like `1` for `"/list/:page?"`
+    pub fn set_default(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<DataValue>,
+    ) -> &mut Self {
+        self.options.defaults.insert(name.into(), value.into());
+        self
+    }
+
+    /// The separator of the values of the repeated parameters, like `,` in `/tags/a,b,c`.
+    pub fn set_repeat_separator(&mut self, separator: impl Into<String>) -> &mut Self {
+        self.options.repeat_separator = Some(separator.into());
+        self
+    }
+
+    /// The separator of the values of a repeated parameter, overriding `repeat_separator`.
+    pub fn set_key_repeat_separator(
+        &mut self,
+        name: impl Into<String>,
+        separator: impl Into<String>,
+    ) -> &mut Self {
+        self.options
+            .repeat_separators
+            .insert(name.into(), separator.into());
+        self
+    }
 }
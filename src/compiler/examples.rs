@@ -0,0 +1,160 @@
+//! Generate example paths of a [`Compiler`](struct.Compiler.html)
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use crate::{internal::DataValue, Compiler, Key, RenderOverrides, Token};
+
+/// The characters tried in order when synthesizing a character of a class
+const CANDIDATES: &str = "abcdefghijklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-_.~";
+
+/// An example path of a [`Compiler`](struct.Compiler.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathExample {
+    /// The example path
+    pub path: String,
+    /// The parameters rendered as the placeholder `{name}`, because no value of their pattern
+    /// could be synthesized. The path may not match the route when it is not empty.
+    pub placeholders: Vec<String>,
+}
+
+impl Compiler {
+    /// Generate up to `n` distinct example paths
+    ///
+    /// The parameters are filled with values synthesized from their patterns, the optional
+    /// ones are toggled on and off, and the repeated ones have 1 to 3 values. A parameter
+    /// whose pattern is too complex is rendered as the placeholder `{name}` and listed in
+    /// the [`placeholders`](struct.PathExample.html#structfield.placeholders) of the example.
+    pub fn examples(&self, n: usize) -> Vec<PathExample> {
+        let optional_count = self
+            .keys()
+            .filter(|key| matches!(key.modifier.as_str(), "?" | "*"))
+            .count()
+            .min(16);
+        let attempts = (1usize << optional_count).max(3).saturating_mul(2).max(n);
+
+        let mut examples: Vec<PathExample> = vec![];
+        for variant in 0..attempts {
+            if examples.len() >= n {
+                break;
+            }
+            if let Some(example) = self.example(variant) {
+                if examples.iter().all(|other| other.path != example.path) {
+                    examples.push(example);
+                }
+            }
+        }
+        examples
+    }
+
+    /// Generate the example of the variant, toggling the optional keys on its bits
+    fn example(&self, variant: usize) -> Option<PathExample> {
+        let mut data = serde_json::Map::new();
+        let mut placeholders = vec![];
+        let mut optional_index = 0;
+        for (i, token) in self.tokens.iter().enumerate() {
+            let key = match token {
+                Token::Key(key) => key,
+                Token::Static(_) => continue,
+            };
+            let Key { name, modifier, .. } = key;
+            if matches!(modifier.as_str(), "?" | "*") {
+                let present = (variant >> optional_index) & 1 == 0;
+                optional_index += 1;
+                if !present {
+                    continue;
+                }
+            }
+            // A group without a parameter, like `{.json}?`, is rendered with an empty value
+            if key.pattern.is_empty() {
+                data.insert(name.to_owned(), DataValue::String(String::new()));
+                continue;
+            }
+            let count = match modifier.as_str() {
+                "+" | "*" => 1 + variant % 3,
+                _ => 1,
+            };
+            let values = (0..count)
+                .map(|j| {
+                    let value = synthesize(&key.pattern, variant + j)?;
+                    let matches = self.matches[i].as_ref()?.is_match(&value);
+                    matches.then_some(value)
+                })
+                .collect::<Option<Vec<_>>>();
+            let values = values.unwrap_or_else(|| {
+                placeholders.push(name.to_owned());
+                vec![format!("{{{name}}}")]
+            });
+            let value = match modifier.as_str() {
+                "+" | "*" => DataValue::Array(values.into_iter().map(DataValue::String).collect()),
+                _ => DataValue::String(values.into_iter().next()?),
+            };
+            data.insert(name.to_owned(), value);
+        }
+
+        let data = DataValue::Object(data);
+        let path = if placeholders.is_empty() {
+            self.render(&data).ok()?
+        } else {
+            let overrides = RenderOverrides {
+                validate: Some(false),
+                ..Default::default()
+            };
+            self.render_with_options(&data, overrides).ok()?
+        };
+        Some(PathExample { path, placeholders })
+    }
+}
+
+/// Synthesize a value matching the pattern, varying with the seed
+fn synthesize(pattern: &str, seed: usize) -> Option<String> {
+    let hir = regex_syntax::Parser::new().parse(pattern).ok()?;
+    let mut value = String::new();
+    let mut seed = seed;
+    write_hir(&hir, &mut seed, &mut value)?;
+    Some(value)
+}
+
+fn write_hir(hir: &Hir, seed: &mut usize, out: &mut String) -> Option<()> {
+    match hir.kind() {
+        HirKind::Empty => {}
+        HirKind::Literal(literal) => out.push_str(std::str::from_utf8(&literal.0).ok()?),
+        HirKind::Class(class) => {
+            let c = CANDIDATES
+                .chars()
+                .cycle()
+                .skip(*seed % CANDIDATES.len())
+                .take(CANDIDATES.len())
+                .find(|c| match class {
+                    Class::Unicode(class) => class
+                        .iter()
+                        .any(|range| range.start() <= *c && *c <= range.end()),
+                    Class::Bytes(class) => class.iter().any(|range| {
+                        range.start() as u32 <= *c as u32 && *c as u32 <= range.end() as u32
+                    }),
+                })?;
+            *seed += 1;
+            out.push(c);
+        }
+        HirKind::Look(_) => return None,
+        HirKind::Repetition(repetition) => {
+            let target = 1 + *seed % 3;
+            let max = repetition.max.map_or(usize::MAX, |max| max as usize);
+            let count = target.max(repetition.min as usize).min(max);
+            for _ in 0..count {
+                write_hir(&repetition.sub, seed, out)?;
+            }
+        }
+        HirKind::Capture(capture) => write_hir(&capture.sub, seed, out)?,
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                write_hir(hir, seed, out)?;
+            }
+        }
+        HirKind::Alternation(hirs) => {
+            let hir = &hirs[*seed % hirs.len()];
+            *seed += 1;
+            write_hir(hir, seed, out)?;
+        }
+    }
+    Some(())
+}
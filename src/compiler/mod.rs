@@ -1,16 +1,27 @@
 //! Path compiler
 mod builder;
+mod examples;
 
-use anyhow::{anyhow, Result};
-pub use builder::{CompilerBuilder, CompilerOptions};
+use std::{borrow::Cow, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+pub use builder::{
+    CompilerBuilder, CompilerOptions, DelimiterPolicy, EncodeError, Encoder, RenderCase,
+    RenderOverrides,
+};
+pub use examples::PathExample;
 use regex::Regex;
 
-use crate::{internal::DataValue, try_into_with::TryIntoWith, Key, ParserOptions, Token};
+use crate::{
+    describe_tokens, encoders::percent_encode, internal::DataValue, number_formats,
+    try_into_with::TryIntoWith, Delimiters, Key, ParserOptions, PathDescription, ToParams, Token,
+};
 
 /// Path compiler
 pub struct Compiler {
     pub(crate) tokens: Vec<Token>,
     pub(crate) matches: Vec<Option<Regex>>,
+    pub(crate) static_path: Option<Box<str>>,
     pub(crate) options: CompilerOptions,
 }
 
@@ -24,6 +35,14 @@ impl Compiler {
         CompilerBuilder::new(path).build()
     }
 
+    /// Create a [`Compiler`](struct.Compiler.html), naming the path in the error
+    ///
+    /// The error has the context `Invalid route template '{path}'`, with the cause shown
+    /// by `{:#}`, which helps to find the bad route among many registered at startup.
+    pub fn new_checked(path: &str) -> Result<Compiler> {
+        Self::new(path).with_context(|| format!("Invalid route template '{path}'"))
+    }
+
     /// Create a [`Compiler`](struct.Compiler.html) with the options
     #[inline]
     pub fn new_with_options<I>(path: I, options: CompilerOptions) -> Result<Compiler>
@@ -33,111 +52,1197 @@ impl Compiler {
         CompilerBuilder::new_with_options(path, options).build()
     }
 
+    /// Get the parameters expected by [`render`](struct.Compiler.html#method.render)
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.tokens.iter().filter_map(|token| match token {
+            Token::Key(key) if !key.pattern.is_empty() => Some(key),
+            _ => None,
+        })
+    }
+
+    /// Get the parameters [`render`](struct.Compiler.html#method.render) fails without,
+    /// with the modifier `""` or `"+"`
+    pub fn required_keys(&self) -> Vec<&Key> {
+        self.keys()
+            .filter(|key| matches!(key.modifier.as_str(), "" | "+"))
+            .collect()
+    }
+
+    /// Get the parameters [`render`](struct.Compiler.html#method.render) can do without,
+    /// with the modifier `"?"` or `"*"`
+    pub fn optional_keys(&self) -> Vec<&Key> {
+        self.keys()
+            .filter(|key| matches!(key.modifier.as_str(), "?" | "*"))
+            .collect()
+    }
+
+    /// Get the path when it has no parameters
+    pub fn is_static(&self) -> Option<&str> {
+        self.static_path.as_deref()
+    }
+
+    /// Summarize the parameters of the path, like [`describe_tokens`](fn.describe_tokens.html)
+    #[inline]
+    pub fn describe(&self) -> PathDescription {
+        describe_tokens(&self.tokens)
+    }
+
+    /// Get a path template parsed back into the tokens of the compiler
+    ///
+    /// The template spells out every parameter, like `{/:id([^/\#\?]+?)}` for `/:id`.
+    pub fn template_string(&self) -> String {
+        self.tokens.iter().map(ToString::to_string).collect()
+    }
+
     /// render parameters into a path
+    #[inline]
     pub fn render(&self, data: &DataValue) -> Result<String> {
+        self.render_cow(data).map(Cow::into_owned)
+    }
+
+    /// render parameters into a path, reporting every missing or invalid parameter at once
+    ///
+    /// Unlike [`render`](struct.Compiler.html#method.render), which stops at the first problem,
+    /// a parameter that fails is left out of the path and the render goes on with the next one.
+    /// The problems of the whole path, like the unknown parameters, the query or the length
+    /// limit, are reported after the ones of the parameters.
+    pub fn try_render(&self, data: &DataValue) -> Result<String, RenderErrors> {
+        let mut problems = vec![];
+        let mut path = String::new();
+        for (i, token) in self.tokens.iter().enumerate() {
+            let key = match token {
+                Token::Static(token) => {
+                    path.push_str(token);
+                    continue;
+                }
+                Token::Key(key) => key,
+            };
+            let value = self.data_value(data, key);
+            let len = path.len();
+            let rendered = self.render_key(
+                i,
+                key,
+                resolve_value(value, key, &self.options),
+                &mut path,
+                RenderOverrides::default(),
+                None,
+            );
+            if let Err(err) = rendered {
+                // The failed parameter is left out of the path the next ones are rendered after
+                path.truncate(len);
+                let position = RenderPosition::new(i, key, &path, false);
+                problems.push(RenderProblem::of(key, value, err, position));
+            }
+        }
+        if let Err(err) = self.deny_unknown_keys(data) {
+            problems.push(RenderProblem::other(err));
+        }
+        if self.options.append_query {
+            let has_query = path.contains('?');
+            if let Err(err) = self.write_query(data, &mut path, has_query) {
+                problems.push(RenderProblem::other(err));
+            }
+        }
+        match self.options.max_rendered_length {
+            Some(limit) if path.len() > limit => {
+                let err = RenderError::PathTooLong {
+                    limit,
+                    actual: path.len(),
+                };
+                problems.push(RenderProblem::other(err.into()));
+            }
+            _ => {}
+        }
+        if problems.is_empty() {
+            Ok(path)
+        } else {
+            Err(RenderErrors { problems })
+        }
+    }
+
+    /// render typed parameters into a path
+    #[inline]
+    pub fn render_params<T>(&self, params: &T) -> Result<String>
+    where
+        T: ToParams + ?Sized,
+    {
+        self.render(&params.to_data())
+    }
+
+    /// render parameters into a path, borrowing the path when it has no parameters
+    pub fn render_cow(&self, data: &DataValue) -> Result<Cow<'_, str>> {
+        if let Some(path) = self.is_static() {
+            self.deny_unknown_keys(data)?;
+            let no_query =
+                !self.options.append_query || data.as_object().map_or(true, |data| data.is_empty());
+            if no_query {
+                return match self.options.max_rendered_length {
+                    Some(limit) if path.len() > limit => Err(RenderError::PathTooLong {
+                        limit,
+                        actual: path.len(),
+                    }
+                    .into()),
+                    _ => Ok(Cow::Borrowed(path)),
+                };
+            }
+        }
+        let capacity = self
+            .tokens
+            .iter()
+            .map(|token| match token {
+                Token::Static(token) => token.len(),
+                Token::Key(key) => key.prefix.len() + key.suffix.len(),
+            })
+            .sum();
+        let mut path = String::with_capacity(capacity);
+        self.render_to(data, &mut path)?;
+        Ok(Cow::Owned(path))
+    }
+
+    /// render the values given by a resolver into a path, instead of the values of a data object
+    ///
+    /// The resolver gives the value of a key, or `None` when it has no value: the key then
+    /// takes its default value, is left out when it is optional, or fails the render.
+    /// The values are encoded and validated like with [`render`](struct.Compiler.html#method.render).
+    pub fn render_with<'v, F>(&self, resolve: F) -> Result<String>
+    where
+        F: Fn(&Key) -> Option<ParamValue<'v>>,
+    {
         let mut path = String::new();
-        let CompilerOptions {
-            validate, encode, ..
-        } = self.options;
+        let mut resolve = |key: &Key| {
+            Ok(match resolve(key) {
+                Some(value) => Some(Resolved::from_param(value)),
+                None => resolve_value(self.options.defaults.get(&key.name), key, &self.options),
+            })
+        };
+        self.render_resolved(
+            &mut resolve,
+            None,
+            &mut path,
+            RenderOverrides::default(),
+            None,
+            &mut String::new(),
+        )?;
+        Ok(path)
+    }
 
-        let array_type_name = "an array containing only strings or numbers";
-        let item_type_name = "a string or a number";
+    /// render the parameters present in `data`, and keep the other keys as pattern text
+    ///
+    /// The keys found in `data` are encoded and validated like with
+    /// [`render`](struct.Compiler.html#method.render), while the absent keys are written back
+    /// with their prefix, pattern, suffix and modifier, so the result can be parsed again.
+    /// The query is never appended and the length limit is not checked.
+    pub fn render_partial(&self, data: &DataValue) -> Result<String> {
+        use std::fmt::Write;
 
+        let mut path = String::new();
         for (i, token) in self.tokens.iter().enumerate() {
             match token {
+                Token::Static(_) => write!(path, "{token}")?,
+                Token::Key(key) => {
+                    match resolve_value(self.data_value(data, key), key, &self.options) {
+                        Some(value) => {
+                            let mut segment = String::new();
+                            self.render_key(
+                                i,
+                                key,
+                                Some(value),
+                                &mut segment,
+                                RenderOverrides::default(),
+                                None,
+                            )?;
+                            write!(path, "{}", Token::Static(segment))?;
+                        }
+                        None => write!(path, "{key}")?,
+                    }
+                }
+            }
+        }
+        Ok(path)
+    }
+
+    /// render parameters into a path, overriding some options for this call
+    pub fn render_with_options(
+        &self,
+        data: &DataValue,
+        overrides: RenderOverrides,
+    ) -> Result<String> {
+        let mut path = String::new();
+        self.render_to_with_options(data, &mut path, overrides, None, &mut String::new())?;
+        Ok(path)
+    }
+
+    /// render parameters into a path, reporting the non-fatal issues met on the way
+    pub fn render_checked(&self, data: &DataValue) -> Result<(String, Vec<RenderWarning>)> {
+        let mut path = String::new();
+        let mut warnings = vec![];
+        self.render_to_with_options(
+            data,
+            &mut path,
+            RenderOverrides::default(),
+            Some(&mut warnings),
+            &mut String::new(),
+        )?;
+        if let Some(data) = data.as_object().filter(|_| !self.options.append_query) {
+            for name in data.keys() {
+                if !self.uses_data_name(name) {
+                    warnings.push(RenderWarning::UnusedParam {
+                        key_name: name.to_owned(),
+                    });
+                }
+            }
+        }
+        Ok((path, warnings))
+    }
+
+    /// render parameters into a path written to `out`
+    ///
+    /// On error, `out` may hold the part of the path rendered before the failure.
+    #[inline]
+    pub fn render_to<W>(&self, data: &DataValue, out: &mut W) -> Result<()>
+    where
+        W: std::fmt::Write,
+    {
+        self.render_to_with_options(
+            data,
+            out,
+            RenderOverrides::default(),
+            None,
+            &mut String::new(),
+        )
+    }
+
+    /// render the parameters of every row into a path, like [`render`](struct.Compiler.html#method.render)
+    ///
+    /// It stops at the first row failing to render, with the index of the row.
+    pub fn render_many<'a, I>(&self, rows: I) -> Result<Vec<String>, (usize, anyhow::Error)>
+    where
+        I: IntoIterator<Item = &'a DataValue>,
+    {
+        let rows = rows.into_iter();
+        let mut paths = Vec::with_capacity(rows.size_hint().0);
+        self.render_each(rows, |_, path| paths.push(path.to_owned()))?;
+        Ok(paths)
+    }
+
+    /// render the parameters of every row into a path given to `sink` with the index of the row
+    ///
+    /// The path and the context kept for the errors are rendered into buffers reused for every
+    /// row. It stops at the first row failing to render, with the index of the row.
+    pub fn render_each<'a, I, F>(&self, rows: I, mut sink: F) -> Result<(), (usize, anyhow::Error)>
+    where
+        I: IntoIterator<Item = &'a DataValue>,
+        F: FnMut(usize, &str),
+    {
+        let mut path = String::new();
+        let mut tail = String::new();
+        for (i, data) in rows.into_iter().enumerate() {
+            path.clear();
+            self.render_to_with_options(
+                data,
+                &mut path,
+                RenderOverrides::default(),
+                None,
+                &mut tail,
+            )
+            .map_err(|err| (i, err))?;
+            sink(i, &path);
+        }
+        Ok(())
+    }
+
+    /// render parameters into a path written to an `io::Write`, like a file or a buffer
+    ///
+    /// On error, `writer` may hold the part of the path rendered before the failure.
+    pub fn render_write<W>(&self, data: &DataValue, writer: &mut W) -> Result<()>
+    where
+        W: std::io::Write,
+    {
+        let mut out = IoWriter {
+            writer,
+            error: None,
+        };
+        let result = self.render_to(data, &mut out);
+        match out.error {
+            Some(error) => Err(error.into()),
+            None => result,
+        }
+    }
+
+    fn render_to_with_options<W>(
+        &self,
+        data: &DataValue,
+        out: &mut W,
+        overrides: RenderOverrides,
+        warnings: Option<&mut Vec<RenderWarning>>,
+        tail: &mut String,
+    ) -> Result<()>
+    where
+        W: std::fmt::Write,
+    {
+        self.deny_unknown_keys(data)?;
+        let mut resolve = |key: &Key| {
+            Ok(resolve_value(
+                self.data_value(data, key),
+                key,
+                &self.options,
+            ))
+        };
+        self.render_resolved(&mut resolve, Some(data), out, overrides, warnings, tail)
+    }
+
+    /// Fail on the data not used by any parameter, with `deny_unknown_keys`
+    fn deny_unknown_keys(&self, data: &DataValue) -> Result<()> {
+        let data = match data.as_object() {
+            Some(data) if self.options.deny_unknown_keys && !self.options.append_query => data,
+            _ => return Ok(()),
+        };
+        let unknown = data
+            .keys()
+            .filter(|name| !self.uses_data_name(name))
+            .map(|name| match self.closest_key(name) {
+                Some(key) => format!("\"{name}\" (did you mean \"{}\"?)", key.name),
+                None => format!("\"{name}\""),
+            })
+            .collect::<Vec<_>>();
+        match unknown.len() {
+            0 => Ok(()),
+            1 => Err(anyhow!("Unknown parameter {}", unknown[0])),
+            _ => Err(anyhow!("Unknown parameters {}", unknown.join(", "))),
+        }
+    }
+
+    /// Find the key with a name close enough to `name` to be a typo of it
+    fn closest_key(&self, name: &str) -> Option<&Key> {
+        let max_distance = (name.chars().count() / 3).max(1);
+        self.keys()
+            .map(|key| (edit_distance(name, &key.name), key))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, key)| key)
+    }
+
+    /// Get the value of the key in the data, or its default when absent
+    fn data_value<'a>(&'a self, data: &'a DataValue, key: &Key) -> Option<&'a DataValue> {
+        let value = if self.options.nested_lookup && key.name.contains('.') {
+            key.name
+                .split('.')
+                .try_fold(data, |data, segment| match data {
+                    DataValue::Array(values) => values.get(segment.parse::<usize>().ok()?),
+                    data => data.get(segment),
+                })
+        } else {
+            data.get(&key.name)
+        };
+        value.or_else(|| self.options.defaults.get(&key.name))
+    }
+
+    /// Whether a top-level name of the data is used by a parameter
+    fn uses_data_name(&self, name: &str) -> bool {
+        self.keys().any(|key| {
+            key.name == name
+                || (self.options.nested_lookup
+                    && key
+                        .name
+                        .split_once('.')
+                        .map_or(false, |(first, _)| first == name))
+        })
+    }
+
+    /// Render the values of the resolver, with the data of the query
+    fn render_resolved<'a, W>(
+        &'a self,
+        resolve: &mut dyn FnMut(&'a Key) -> Result<Option<Resolved<'a>>>,
+        data: Option<&DataValue>,
+        out: &mut W,
+        overrides: RenderOverrides,
+        warnings: Option<&mut Vec<RenderWarning>>,
+        tail: &mut String,
+    ) -> Result<()>
+    where
+        W: std::fmt::Write,
+    {
+        let limit = match self.options.max_rendered_length {
+            Some(limit) => limit,
+            None => return self.render_with_query(resolve, data, out, overrides, warnings, tail),
+        };
+        let mut out = LengthLimitWriter { out, limit, len: 0 };
+        self.render_with_query(resolve, data, &mut out, overrides, warnings, tail)?;
+        if out.len > limit {
+            return Err(RenderError::PathTooLong {
+                limit,
+                actual: out.len,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn render_with_query<'a, W>(
+        &'a self,
+        resolve: &mut dyn FnMut(&'a Key) -> Result<Option<Resolved<'a>>>,
+        data: Option<&DataValue>,
+        out: &mut W,
+        overrides: RenderOverrides,
+        warnings: Option<&mut Vec<RenderWarning>>,
+        tail: &mut String,
+    ) -> Result<()>
+    where
+        W: std::fmt::Write,
+    {
+        let data = match data {
+            Some(data) if self.options.append_query => data,
+            _ => return self.render_tokens(resolve, out, overrides, warnings, tail),
+        };
+        let mut out = QueryMarkWriter {
+            out,
+            has_query: false,
+        };
+        self.render_tokens(resolve, &mut out, overrides, warnings, tail)?;
+        self.write_query(data, out.out, out.has_query)
+    }
+
+    /// Write the data not used by the parameters as a query string
+    fn write_query<W>(&self, data: &DataValue, out: &mut W, has_query: bool) -> Result<()>
+    where
+        W: std::fmt::Write,
+    {
+        let data = match data.as_object() {
+            Some(data) => data,
+            None => return Ok(()),
+        };
+        let mut names = data
+            .keys()
+            .filter(|name| !self.uses_data_name(name))
+            .collect::<Vec<_>>();
+        names.sort();
+
+        let number_format = self
+            .options
+            .number_format
+            .unwrap_or(number_formats::shortest);
+        let mut separator = if has_query { '&' } else { '?' };
+        for name in names {
+            let values = match &data[name] {
+                DataValue::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                let value = match value {
+                    DataValue::Null => continue,
+                    DataValue::String(value) => Cow::Borrowed(value.as_str()),
+                    DataValue::Number(value) => {
+                        let key = Key::default().with_name(name.as_str());
+                        Cow::Owned(number_format(value, &key))
+                    }
+                    DataValue::Bool(value) => Cow::Owned(value.to_string()),
+                    DataValue::Array(_) | DataValue::Object(_) => {
+                        return Err(anyhow!(
+                            "Expected the query parameter \"{name}\" to be a string, a number, a boolean or an array of them"
+                        ))
+                    }
+                };
+                write!(
+                    out,
+                    "{separator}{}={}",
+                    encode_query_component(name),
+                    encode_query_component(&value)
+                )?;
+                separator = '&';
+            }
+        }
+        Ok(())
+    }
+
+    fn render_tokens<'a, W>(
+        &'a self,
+        resolve: &mut dyn FnMut(&'a Key) -> Result<Option<Resolved<'a>>>,
+        out: &mut W,
+        overrides: RenderOverrides,
+        mut warnings: Option<&mut Vec<RenderWarning>>,
+        tail: &mut String,
+    ) -> Result<()>
+    where
+        W: std::fmt::Write,
+    {
+        use std::fmt::Write;
+
+        tail.clear();
+        let mut out = TailWriter {
+            out,
+            tail,
+            cut: false,
+        };
+        for (i, token) in self.tokens.iter().enumerate() {
+            let key = match token {
                 Token::Static(token) => {
-                    path += token;
+                    out.write_str(token)?;
                     continue;
                 }
-                Token::Key(token) => {
-                    let Key {
-                        name,
-                        prefix,
-                        suffix,
-                        pattern,
-                        modifier,
-                    } = token;
-                    let value = data.get(name);
-                    let modifier = modifier.as_str();
-                    let optional = matches!(modifier, "?" | "*");
-                    let repeat = matches!(modifier, "+" | "*");
-
-                    let mut resolve_string = |value: &String| {
-                        let segment = encode(value, token);
-
-                        let validate = validate
-                            && self.matches[i]
-                                .as_ref()
-                                .map(|m| m.is_match(segment.as_str()))
-                                .unwrap_or_default();
-                        match validate{
-                            false => Err(anyhow!("Expected all \"{name}\" to match \"{pattern}\", but got \"{segment}\"")),
-                            true => {
-                                path = format!("{path}{prefix}{segment}{suffix}");
-                                Ok(())
-                            }
-                        }
-                    };
-
-                    if let Some(value) = value {
-                        match value {
-                            DataValue::Array(value) => {
-                                if !repeat {
-                                    return Err(anyhow!(
-                                        "Expected \"{name}\" to not repeat, but got an array",
-                                    ));
-                                }
-
-                                if value.is_empty() {
-                                    if optional {
-                                        continue;
-                                    }
-
-                                    return Err(anyhow!("Expected \"{name}\" to not be empty",));
-                                }
-
-                                for value in value.iter() {
-                                    match value {
-                                        DataValue::Number(value) => {
-                                            resolve_string(&value.to_string())?;
-                                        }
-                                        DataValue::String(value) => {
-                                            resolve_string(value)?;
-                                        }
-                                        _ => {
-                                            return Err(anyhow!(
-                                                "Expected \"{name}\" to be {array_type_name}"
-                                            ))
-                                        }
-                                    }
-                                }
-                                continue;
-                            }
-                            DataValue::Number(value) => {
-                                resolve_string(&value.to_string())?;
-                                continue;
-                            }
-                            DataValue::String(value) => {
-                                resolve_string(value)?;
-                                continue;
-                            }
-                            _ => (),
+                Token::Key(key) => key,
+            };
+            let value = resolve(key)?;
+            let err = match self.render_key(
+                i,
+                key,
+                value,
+                &mut out,
+                overrides,
+                warnings.as_deref_mut(),
+            ) {
+                Ok(()) => continue,
+                Err(err) => err,
+            };
+            // A failure of the writer is not a failure of the parameter
+            if err.is::<std::fmt::Error>() {
+                return Err(err);
+            }
+            let partial_path = self.render_partial_path(i, resolve, overrides)?;
+            return Err(RenderError::InvalidParam {
+                key_name: key.name.to_owned(),
+                position: RenderPosition::new(i, key, out.tail, out.cut),
+                partial_path,
+                error: Arc::new(err),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Render the tokens before the index again, for the path rendered before a failure
+    fn render_partial_path<'a>(
+        &'a self,
+        end: usize,
+        resolve: &mut dyn FnMut(&'a Key) -> Result<Option<Resolved<'a>>>,
+        overrides: RenderOverrides,
+    ) -> Result<String> {
+        let mut path = String::new();
+        for (i, token) in self.tokens[..end].iter().enumerate() {
+            match token {
+                Token::Static(token) => path.push_str(token),
+                Token::Key(key) => {
+                    let value = resolve(key)?;
+                    self.render_key(i, key, value, &mut path, overrides, None)?;
+                }
+            }
+        }
+        Ok(path)
+    }
+
+    /// Render the value of the key at the index of the tokens
+    fn render_key<W>(
+        &self,
+        i: usize,
+        token: &Key,
+        value: Option<Resolved<'_>>,
+        out: &mut W,
+        overrides: RenderOverrides,
+        mut warnings: Option<&mut Vec<RenderWarning>>,
+    ) -> Result<()>
+    where
+        W: std::fmt::Write,
+    {
+        let validate = overrides.validate.unwrap_or(self.options.validate);
+        let encode = self.options.encode_of(token, &overrides);
+        let Key {
+            name,
+            prefix,
+            suffix,
+            pattern,
+            modifier,
+            default,
+        } = token;
+        let modifier = modifier.as_str();
+        let optional = matches!(modifier, "?" | "*");
+        let repeat = matches!(modifier, "+" | "*");
+
+        let mut resolve_string = |value: &str| -> Result<String> {
+            let segment = encode
+                .encode(value, token)
+                .with_context(|| format!("Failed to encode \"{name}\""))?;
+            let segment = match overrides.case {
+                Some(RenderCase::Lower) => segment.to_lowercase(),
+                Some(RenderCase::Upper) => segment.to_uppercase(),
+                None => segment,
+            };
+            let delimiters = &self.options.delimiter;
+            let segment = match self.options.delimiter_in_value {
+                DelimiterPolicy::Allow => segment,
+                DelimiterPolicy::Reject => {
+                    match segment.chars().find(|&c| delimiters.contains(c)) {
+                        Some(c) if !repeat => {
+                            return Err(anyhow!(
+                            "Expected \"{name}\" to not contain the delimiter \"{c}\", but got \"{segment}\""
+                        ));
                         }
+                        _ => segment,
                     }
+                }
+                DelimiterPolicy::Encode => encode_delimiters(segment, delimiters),
+            };
 
+            let mismatch = self.matches[i]
+                .as_ref()
+                .map_or(true, |m| !m.is_match(segment.as_str()));
+            if mismatch {
+                if validate {
+                    return Err(anyhow!(
+                        "Expected all \"{name}\" to match \"{pattern}\", but got \"{segment}\""
+                    ));
+                }
+                if let Some(warnings) = warnings.as_deref_mut() {
+                    warnings.push(RenderWarning::ValueNotMatched {
+                        key_name: name.to_owned(),
+                        value: segment.clone(),
+                    });
+                }
+            }
+            Ok(segment)
+        };
+        let mut write_string = |value: &str, out: &mut W| -> Result<()> {
+            let segment = resolve_string(value)?;
+            out.write_str(prefix)?;
+            out.write_str(&segment)?;
+            out.write_str(suffix)?;
+            Ok(())
+        };
+
+        match value {
+            Some(Resolved::One(value)) => return write_string(&value, out),
+            Some(Resolved::Many(values)) => {
+                if !repeat {
+                    return Err(anyhow!(
+                        "Expected \"{name}\" to not repeat, but got an array",
+                    ));
+                }
+
+                let mut values = values.peekable();
+                if values.peek().is_none() {
                     if optional {
-                        continue;
+                        skip_optional(&mut warnings, token);
+                        return Ok(());
                     }
 
-                    let type_of_message = if repeat {
-                        array_type_name
-                    } else {
-                        item_type_name
-                    };
-                    return Err(anyhow!("Expected \"{name}\" to be {type_of_message}"));
+                    return Err(anyhow!("Expected \"{name}\" to not be empty",));
                 }
+
+                let separator = self.options.repeat_separator_of(token);
+                let separator = match separator {
+                    Some(separator) => separator,
+                    None => {
+                        for value in values {
+                            write_string(&value?, out)?;
+                        }
+                        return Ok(());
+                    }
+                };
+                // Validate every value before writing the joined run
+                let segments = values
+                    .map(|value| resolve_string(&value?))
+                    .collect::<Result<Vec<_>>>()?;
+                out.write_str(prefix)?;
+                out.write_str(&segments.join(separator))?;
+                out.write_str(suffix)?;
+                return Ok(());
             }
+            None => {}
         }
-        Ok(path)
+
+        if let Some(default) = default {
+            return write_string(default, out);
+        }
+
+        if optional {
+            skip_optional(&mut warnings, token);
+            return Ok(());
+        }
+
+        let type_of_message = if repeat {
+            ARRAY_TYPE_NAME
+        } else {
+            ITEM_TYPE_NAME
+        };
+        Err(anyhow!("Expected \"{name}\" to be {type_of_message}"))
+    }
+}
+
+const ARRAY_TYPE_NAME: &str = "an array containing only strings or numbers";
+const ITEM_TYPE_NAME: &str = "a string or a number";
+
+/// A value of a parameter, given by the resolver of
+/// [`render_with`](struct.Compiler.html#method.render_with)
+pub enum ParamValue<'a> {
+    /// A string
+    Str(&'a str),
+    /// A value rendered with its `Display`
+    Display(&'a dyn std::fmt::Display),
+    /// The values of a repeated parameter
+    Many(Box<dyn Iterator<Item = &'a str> + 'a>),
+}
+
+impl std::fmt::Debug for ParamValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamValue::Str(value) => f.debug_tuple("Str").field(value).finish(),
+            ParamValue::Display(value) => {
+                f.debug_tuple("Display").field(&value.to_string()).finish()
+            }
+            ParamValue::Many(_) => f.debug_tuple("Many").finish(),
+        }
+    }
+}
+
+/// A value of a parameter ready to render
+enum Resolved<'a> {
+    One(Cow<'a, str>),
+    Many(Box<dyn Iterator<Item = Result<Cow<'a, str>>> + 'a>),
+}
+
+impl<'a> Resolved<'a> {
+    fn from_param<'v: 'a>(value: ParamValue<'v>) -> Self {
+        match value {
+            ParamValue::Str(value) => Resolved::One(Cow::Borrowed(value)),
+            ParamValue::Display(value) => Resolved::One(Cow::Owned(value.to_string())),
+            ParamValue::Many(values) => {
+                Resolved::Many(Box::new(values.map(|value| Ok(Cow::Borrowed(value)))))
+            }
+        }
+    }
+}
+
+/// The number of characters to insert, delete or substitute to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Resolve the value of a key, a value of another type is missing
+///
+/// The other types go through `stringify` when it is set, the values it leaves out are missing.
+fn resolve_value<'a>(
+    value: Option<&'a DataValue>,
+    key: &Key,
+    options: &CompilerOptions,
+) -> Option<Resolved<'a>> {
+    let stringify = options.stringify;
+    let number_format = options.number_format.unwrap_or(number_formats::shortest);
+    let value = match value {
+        Some(DataValue::String(value)) => Resolved::One(Cow::Borrowed(value)),
+        Some(DataValue::Number(value)) => Resolved::One(Cow::Owned(number_format(value, key))),
+        Some(DataValue::Array(values)) => {
+            let key = key.clone();
+            Resolved::Many(Box::new(values.iter().map(move |value| {
+                match value {
+                    DataValue::String(value) => Ok(Cow::Borrowed(value.as_str())),
+                    DataValue::Number(value) => Ok(Cow::Owned(number_format(value, &key))),
+                    _ => stringify
+                        .and_then(|stringify| stringify(value, &key))
+                        .map(Cow::Owned)
+                        .ok_or_else(|| {
+                            anyhow!("Expected \"{}\" to be {ARRAY_TYPE_NAME}", key.name)
+                        }),
+                }
+            })))
+        }
+        Some(value) => Resolved::One(Cow::Owned(stringify?(value, key)?)),
+        None => return None,
+    };
+    Some(value)
+}
+
+/// Stringify a boolean as `"true"` or `"false"`, for the `stringify` of
+/// [`CompilerOptions`](struct.CompilerOptions.html)
+///
+/// `null` and the objects are left out, so `null` is missing like without `stringify`.
+pub fn stringify_bool(value: &DataValue, _: &Key) -> Option<String> {
+    value.as_bool().map(|value| value.to_string())
+}
+
+/// Write to an `io::Write` through `fmt::Write`, keeping the error of the writer
+struct IoWriter<'a, W> {
+    writer: &'a mut W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: std::io::Write> std::fmt::Write for IoWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            std::fmt::Error
+        })
+    }
+}
+
+/// Keep the end of the path written, for the position of a failure
+struct TailWriter<'a, W> {
+    out: &'a mut W,
+    tail: &'a mut String,
+    /// Whether the start of the path was dropped from the tail
+    cut: bool,
+}
+
+impl<W: std::fmt::Write> std::fmt::Write for TailWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.tail.push_str(s);
+        // Drop the start of the tail once in a while, instead of on every write
+        if self.tail.len() > 4 * RENDERED_CONTEXT_LEN {
+            let start = self.tail.len() - RENDERED_CONTEXT_LEN;
+            let start = (start..self.tail.len())
+                .find(|&i| self.tail.is_char_boundary(i))
+                .unwrap_or(start);
+            self.tail.replace_range(..start, "");
+            self.cut = true;
+        }
+        self.out.write_str(s)
+    }
+}
+
+/// Count the length of the path, writing only up to the limit
+struct LengthLimitWriter<'a, W> {
+    out: &'a mut W,
+    limit: usize,
+    len: usize,
+}
+
+impl<W: std::fmt::Write> std::fmt::Write for LengthLimitWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.len += s.len();
+        if self.len > self.limit {
+            return Ok(());
+        }
+        self.out.write_str(s)
+    }
+}
+
+/// Remember whether the path written so far has a query
+struct QueryMarkWriter<'a, W> {
+    out: &'a mut W,
+    has_query: bool,
+}
+
+impl<W: std::fmt::Write> std::fmt::Write for QueryMarkWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.has_query |= s.contains('?');
+        self.out.write_str(s)
     }
 }
+
+/// Percent-encode the delimiters in the value, and `%` so the value decodes back as it was
+fn encode_delimiters(value: String, delimiters: &Delimiters) -> String {
+    let encoded_char = |c: char| c == '%' || delimiters.contains(c);
+    if !value.chars().any(encoded_char) {
+        return value;
+    }
+    let mut encoded = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        if encoded_char(c) {
+            percent_encode(&mut encoded, c.encode_utf8(&mut [0; 4]), |_| false);
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// Percent-encode everything but the unreserved characters of a URI
+fn encode_query_component(value: &str) -> Cow<'_, str> {
+    let unreserved = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~');
+    if value.bytes().all(unreserved) {
+        return Cow::Borrowed(value);
+    }
+    let mut encoded = String::with_capacity(value.len() * 3);
+    percent_encode(&mut encoded, value, unreserved);
+    Cow::Owned(encoded)
+}
+
+/// Report an optional parameter left out of the path
+fn skip_optional(warnings: &mut Option<&mut Vec<RenderWarning>>, key: &Key) {
+    if let Some(warnings) = warnings.as_deref_mut() {
+        if !key.pattern.is_empty() {
+            warnings.push(RenderWarning::OptionalParamSkipped {
+                key_name: key.name.to_owned(),
+            });
+        }
+    }
+}
+
+/// A non-fatal issue met by [`render_checked`](struct.Compiler.html#method.render_checked)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderWarning {
+    /// An optional parameter had no value and was left out of the path
+    OptionalParamSkipped {
+        /// The name of the parameter
+        key_name: String,
+    },
+    /// A value didn't match the pattern of its parameter, with `validate` off
+    ValueNotMatched {
+        /// The name of the parameter
+        key_name: String,
+        /// The encoded value
+        value: String,
+    },
+    /// A value of the data isn't a parameter of the path
+    UnusedParam {
+        /// The name of the value
+        key_name: String,
+    },
+}
+
+/// The error of a render failing on a limit of the [`CompilerOptions`](struct.CompilerOptions.html)
+/// or on a parameter
+#[derive(Debug, Clone)]
+pub enum RenderError {
+    /// The path is longer than `max_rendered_length`
+    PathTooLong {
+        /// The maximum length of the path
+        limit: usize,
+        /// The length of the whole path
+        actual: usize,
+    },
+    /// A parameter is missing or invalid, displayed as its `error`, whose causes are the
+    /// sources of this error
+    InvalidParam {
+        /// The name of the parameter
+        key_name: String,
+        /// Where the parameter is in the path
+        position: RenderPosition,
+        /// The whole path rendered before the parameter, like `/user` for `:id` of
+        /// `/user/:id`, whose prefix `/` is not rendered
+        partial_path: String,
+        /// The failure of the parameter
+        error: Arc<anyhow::Error>,
+    },
+}
+
+/// The parameter errors are equal when their messages are
+impl PartialEq for RenderError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                RenderError::PathTooLong { limit, actual },
+                RenderError::PathTooLong {
+                    limit: other_limit,
+                    actual: other_actual,
+                },
+            ) => limit == other_limit && actual == other_actual,
+            (
+                RenderError::InvalidParam {
+                    key_name,
+                    position,
+                    partial_path,
+                    error,
+                },
+                RenderError::InvalidParam {
+                    key_name: other_key_name,
+                    position: other_position,
+                    partial_path: other_partial_path,
+                    error: other_error,
+                },
+            ) => {
+                key_name == other_key_name
+                    && position == other_position
+                    && partial_path == other_partial_path
+                    && error.to_string() == other_error.to_string()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for RenderError {}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::PathTooLong { limit, actual } => write!(
+                f,
+                "Expected the path to be at most {limit} bytes long, but got {actual} bytes"
+            ),
+            RenderError::InvalidParam { error, .. } => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::PathTooLong { .. } => None,
+            // The error itself is displayed, its causes follow
+            RenderError::InvalidParam { error, .. } => error.source(),
+        }
+    }
+}
+
+/// The number of characters of the path rendered before a failure kept in a [`RenderPosition`]
+const RENDERED_CONTEXT_LEN: usize = 64;
+
+/// Where a parameter failed to render
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderPosition {
+    /// The index of the token of the parameter
+    pub index: usize,
+    /// The prefix of the parameter
+    pub prefix: String,
+    /// The suffix of the parameter
+    pub suffix: String,
+    /// The end of the path rendered before the failure, its last 64 characters
+    /// after `...` when it is longer
+    pub rendered: String,
+}
+
+impl RenderPosition {
+    fn new(index: usize, key: &Key, rendered: &str, cut: bool) -> Self {
+        let len = rendered.chars().count();
+        let rendered = if cut || len > RENDERED_CONTEXT_LEN {
+            let tail = rendered
+                .chars()
+                .skip(len.saturating_sub(RENDERED_CONTEXT_LEN))
+                .collect::<String>();
+            format!("...{tail}")
+        } else {
+            rendered.to_owned()
+        };
+        RenderPosition {
+            index,
+            prefix: key.prefix.to_owned(),
+            suffix: key.suffix.to_owned(),
+            rendered,
+        }
+    }
+}
+
+impl std::fmt::Display for RenderPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "at token {} between \"{}\" and \"{}\", after \"{}\"",
+            self.index, self.prefix, self.suffix, self.rendered
+        )
+    }
+}
+
+/// The problems met by [`try_render`](struct.Compiler.html#method.try_render), one per line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderErrors {
+    /// The problems in the order of the parameters in the path, then the problems of the whole path
+    pub problems: Vec<RenderProblem>,
+}
+
+impl std::fmt::Display for RenderErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, problem) in self.problems.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RenderErrors {}
+
+/// A parameter [`try_render`](struct.Compiler.html#method.try_render) could not render
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderProblem {
+    /// The name of the parameter, empty for [`RenderProblemKind::Other`](enum.RenderProblemKind.html#variant.Other)
+    pub key_name: String,
+    /// What is wrong with the parameter
+    pub kind: RenderProblemKind,
+    /// The offending value, `None` when the value is missing
+    pub value: Option<String>,
+    /// The message [`render`](struct.Compiler.html#method.render) fails with for this problem
+    pub message: String,
+    /// Where the parameter is in the path, `None` for [`RenderProblemKind::Other`](enum.RenderProblemKind.html#variant.Other)
+    pub position: Option<RenderPosition>,
+}
+
+impl RenderProblem {
+    fn of(
+        key: &Key,
+        value: Option<&DataValue>,
+        err: anyhow::Error,
+        position: RenderPosition,
+    ) -> Self {
+        let (kind, value) = match value {
+            None | Some(DataValue::Null) => (RenderProblemKind::Missing, None),
+            Some(DataValue::Array(_)) if !matches!(key.modifier.as_str(), "+" | "*") => (
+                RenderProblemKind::UnexpectedArray,
+                value.map(DataValue::to_string),
+            ),
+            Some(DataValue::Array(values)) => {
+                match values
+                    .iter()
+                    .find(|value| !(value.is_string() || value.is_number()))
+                {
+                    Some(value) => (RenderProblemKind::WrongType, Some(value.to_string())),
+                    None if values.is_empty() => (RenderProblemKind::Missing, None),
+                    None => (
+                        RenderProblemKind::PatternMismatch,
+                        value.map(DataValue::to_string),
+                    ),
+                }
+            }
+            Some(DataValue::String(value)) => {
+                (RenderProblemKind::PatternMismatch, Some(value.to_owned()))
+            }
+            Some(DataValue::Number(value)) => {
+                (RenderProblemKind::PatternMismatch, Some(value.to_string()))
+            }
+            Some(value) => (RenderProblemKind::WrongType, Some(value.to_string())),
+        };
+        RenderProblem {
+            key_name: key.name.to_owned(),
+            kind,
+            value,
+            message: err.to_string(),
+            position: Some(position),
+        }
+    }
+
+    /// A problem of the whole path
+    fn other(err: anyhow::Error) -> Self {
+        RenderProblem {
+            key_name: String::new(),
+            kind: RenderProblemKind::Other,
+            value: None,
+            message: err.to_string(),
+            position: None,
+        }
+    }
+}
+
+impl std::fmt::Display for RenderProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(position) = &self.position {
+            write!(f, " ({position})")?;
+        }
+        Ok(())
+    }
+}
+
+/// What is wrong with a parameter reported by [`try_render`](struct.Compiler.html#method.try_render)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderProblemKind {
+    /// A required parameter has no value, or an empty array
+    Missing,
+    /// The value is neither a string nor a number, like a boolean or an object
+    WrongType,
+    /// The encoded value doesn't match the pattern of the parameter
+    PatternMismatch,
+    /// An array was given to a parameter that doesn't repeat
+    UnexpectedArray,
+    /// The path failed for a reason other than a parameter, like the length limit
+    Other,
+}
@@ -0,0 +1,124 @@
+//! The delimiters of a path
+
+use crate::internal::escape_string;
+
+/// An ordered set of delimiter characters, the first one is the primary delimiter
+///
+/// The primary delimiter separates the values of repeated parameters without prefix and suffix.
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct Delimiters(String);
+
+impl Delimiters {
+    /// Create the set from the characters of `chars`, ignoring duplicates
+    pub fn new(chars: impl AsRef<str>) -> Self {
+        let mut set = String::new();
+        for c in chars.as_ref().chars() {
+            if !set.contains(c) {
+                set.push(c);
+            }
+        }
+        Self(set)
+    }
+
+    /// The primary delimiter
+    pub fn primary(&self) -> Option<char> {
+        self.0.chars().next()
+    }
+
+    /// Whether `c` is a delimiter
+    pub fn contains(&self, c: char) -> bool {
+        self.0.contains(c)
+    }
+
+    /// Whether there is no delimiter
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The delimiters in order
+    pub fn chars(&self) -> std::str::Chars<'_> {
+        self.0.chars()
+    }
+
+    /// The delimiters as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// A regex class matching a delimiter, empty when there is no delimiter
+    pub(crate) fn to_class(&self) -> String {
+        if self.is_empty() {
+            String::new()
+        } else {
+            format!("[{}]", escape_string(&self.0))
+        }
+    }
+
+    /// A regex class matching anything but a delimiter
+    pub(crate) fn to_negated_class(&self) -> String {
+        if self.is_empty() {
+            "(?s:.)".to_owned()
+        } else {
+            format!("[^{}]", escape_string(&self.0))
+        }
+    }
+}
+
+impl From<&str> for Delimiters {
+    #[inline]
+    fn from(chars: &str) -> Self {
+        Self::new(chars)
+    }
+}
+
+impl From<&String> for Delimiters {
+    #[inline]
+    fn from(chars: &String) -> Self {
+        Self::new(chars)
+    }
+}
+
+impl From<String> for Delimiters {
+    #[inline]
+    fn from(chars: String) -> Self {
+        Self::new(chars)
+    }
+}
+
+impl From<char> for Delimiters {
+    #[inline]
+    fn from(c: char) -> Self {
+        Self(c.to_string())
+    }
+}
+
+impl AsRef<str> for Delimiters {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Delimiters {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Delimiters {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl std::fmt::Display for Delimiters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::fmt::Debug for Delimiters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.0, f)
+    }
+}
@@ -0,0 +1,136 @@
+//! The summary of the parameters of a path, for docs and help texts
+
+use crate::{Key, Token};
+
+/// The summary of a path, made by [`describe_tokens`](fn.describe_tokens.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathDescription {
+    /// The static text of the path with a `{name}` placeholder for each parameter,
+    /// like `/users/{id}/{tab}`, and the optional text in brackets, like `/files[/{path}][.json]`
+    pub skeleton: String,
+    /// The parameters in the order of the path
+    pub params: Vec<ParamDescription>,
+}
+
+impl PathDescription {
+    /// Get the names of the parameters a path must have
+    pub fn required_param_names(&self) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|param| param.required)
+            .map(|param| param.name.as_str())
+            .collect()
+    }
+
+    /// Get the names of the parameters a path can do without
+    pub fn optional_param_names(&self) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|param| !param.required)
+            .map(|param| param.name.as_str())
+            .collect()
+    }
+}
+
+/// A parameter of a [`PathDescription`](struct.PathDescription.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamDescription {
+    /// The name of the parameter, like `0` for an unnamed pattern
+    pub name: String,
+    /// Whether the path must have the parameter, with the modifier `""` or `"+"`
+    pub required: bool,
+    /// Whether the parameter takes many values, with the modifier `"+"` or `"*"`
+    pub repeatable: bool,
+    /// The pattern of the parameter
+    pub pattern: String,
+    /// The prefix of the parameter
+    pub prefix: String,
+    /// The suffix of the parameter
+    pub suffix: String,
+    /// The value of the parameter missing from the path
+    pub default: Option<String>,
+}
+
+impl From<&Key> for ParamDescription {
+    fn from(key: &Key) -> Self {
+        let modifier = key.modifier.as_str();
+        Self {
+            name: key.name.to_owned(),
+            required: matches!(modifier, "" | "+"),
+            repeatable: matches!(modifier, "+" | "*"),
+            pattern: key.pattern.to_owned(),
+            prefix: key.prefix.to_owned(),
+            suffix: key.suffix.to_owned(),
+            default: key.default.clone(),
+        }
+    }
+}
+
+/// Summarize the parameters of the tokens of a path
+///
+/// A group without a parameter is static text of the skeleton. An optional group is in
+/// brackets with its prefix and suffix, like `[.json]` for `{.json}?` and `[/{tab}]` for `/:tab?`.
+pub fn describe_tokens(tokens: &[Token]) -> PathDescription {
+    let mut skeleton = String::new();
+    let mut params = vec![];
+    for token in tokens {
+        let key = match token {
+            Token::Static(text) => {
+                skeleton.push_str(text);
+                continue;
+            }
+            Token::Key(key) => key,
+        };
+        let optional = matches!(key.modifier.as_str(), "?" | "*");
+        if optional {
+            skeleton.push('[');
+        }
+        skeleton.push_str(&key.prefix);
+        if !key.pattern.is_empty() {
+            skeleton.push('{');
+            skeleton.push_str(&key.name);
+            skeleton.push('}');
+            params.push(ParamDescription::from(key));
+        }
+        skeleton.push_str(&key.suffix);
+        if optional {
+            skeleton.push(']');
+        }
+    }
+    PathDescription { skeleton, params }
+}
+
+#[cfg(feature = "serde")]
+impl serde_core::Serialize for PathDescription {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde_core::Serializer,
+    {
+        use serde_core::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("PathDescription", 2)?;
+        state.serialize_field("skeleton", &self.skeleton)?;
+        state.serialize_field("params", &self.params)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde_core::Serialize for ParamDescription {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde_core::Serializer,
+    {
+        use serde_core::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ParamDescription", 7)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("required", &self.required)?;
+        state.serialize_field("repeatable", &self.repeatable)?;
+        state.serialize_field("pattern", &self.pattern)?;
+        state.serialize_field("prefix", &self.prefix)?;
+        state.serialize_field("suffix", &self.suffix)?;
+        state.serialize_field("default", &self.default)?;
+        state.end()
+    }
+}
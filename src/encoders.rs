@@ -0,0 +1,53 @@
+//! Encoders of the parameters, for the `encode` of [`CompilerOptions`](../struct.CompilerOptions.html)
+//! or the `encode_value` of [`MatcherOptions`](../struct.MatcherOptions.html)
+//!
+//! The value is encoded before it is validated, so a value containing `/` encoded by
+//! [`uri_component`](fn.uri_component.html) passes a pattern like `[^/]+`.
+
+use std::fmt::Write;
+
+use crate::Key;
+
+/// Encode the value like `encodeURIComponent` of JavaScript
+///
+/// Everything but the letters, the digits and `-_.!~*'()` is percent-encoded, `%` included.
+pub fn uri_component(value: &str, _: &Key) -> String {
+    encode(value, |_| false)
+}
+
+/// Encode the value like [`uri_component`](fn.uri_component.html), leaving `/` alone
+///
+/// It keeps the segments of a value of a splat parameter, like `a/b` of `/files/:path*`.
+pub fn uri_path_segment(value: &str, _: &Key) -> String {
+    encode(value, |b| b == b'/')
+}
+
+/// Leave the value as it is, the default `encode`
+pub fn noop(value: &str, _: &Key) -> String {
+    value.to_owned()
+}
+
+fn encode(value: &str, keep: impl Fn(u8) -> bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    percent_encode(&mut encoded, value, |b| {
+        b.is_ascii_alphanumeric()
+            || matches!(
+                b,
+                b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')'
+            )
+            || keep(b)
+    });
+    encoded
+}
+
+/// Write the value percent-encoded, but the ASCII bytes to keep
+pub(crate) fn percent_encode(out: &mut String, value: &str, keep: impl Fn(u8) -> bool) {
+    for b in value.bytes() {
+        if b.is_ascii() && keep(b) {
+            out.push(b as char);
+        } else {
+            // Writing to a String can't fail
+            let _ = write!(out, "%{b:02X}");
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Build a [`PathRegex`](struct.PathRegex.html), a [`Matcher`](struct.Matcher.html) or a
+//! [`Compiler`](struct.Compiler.html) once, on first use
+
+/// Build a [`PathRegex`](struct.PathRegex.html) on first use, returning a `&'static PathRegex`
+///
+/// Panics on first use when the path is invalid.
+///
+/// ```
+/// use path2regex::{lazy_path_regex, PathRegex};
+///
+/// fn user_re() -> &'static PathRegex {
+///     lazy_path_regex!("/user/:id")
+/// }
+///
+/// assert!(user_re().is_match("/user/123"));
+/// ```
+#[macro_export]
+macro_rules! lazy_path_regex {
+    ($path:expr) => {
+        $crate::lazy_path_regex!($path, $crate::PathRegexOptions::default())
+    };
+    ($path:expr, $options:expr) => {{
+        $crate::__lazy_static! {
+            static ref PATH_REGEX: $crate::PathRegex =
+                $crate::PathRegexBuilder::new_with_options($path, $options)
+                    .build()
+                    .expect("Invalid path of `lazy_path_regex!`");
+        }
+        &*PATH_REGEX
+    }};
+}
+
+/// Build a [`Matcher`](struct.Matcher.html) on first use, returning a `&'static Matcher`
+///
+/// Panics on first use when the path is invalid.
+#[cfg(feature = "match")]
+#[cfg_attr(docsrs, doc(cfg(feature = "match")))]
+#[macro_export]
+macro_rules! lazy_matcher {
+    ($path:expr) => {
+        $crate::lazy_matcher!($path, $crate::MatcherOptions::default())
+    };
+    ($path:expr, $options:expr) => {{
+        $crate::__lazy_static! {
+            static ref MATCHER: $crate::Matcher =
+                $crate::Matcher::new_with_options($path, $options)
+                    .expect("Invalid path of `lazy_matcher!`");
+        }
+        &*MATCHER
+    }};
+}
+
+/// Build a [`Compiler`](struct.Compiler.html) on first use, returning a `&'static Compiler`
+///
+/// Panics on first use when the path is invalid.
+#[cfg(feature = "compile")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compile")))]
+#[macro_export]
+macro_rules! lazy_compiler {
+    ($path:expr) => {
+        $crate::lazy_compiler!($path, $crate::CompilerOptions::default())
+    };
+    ($path:expr, $options:expr) => {{
+        $crate::__lazy_static! {
+            static ref COMPILER: $crate::Compiler =
+                $crate::Compiler::new_with_options($path, $options)
+                    .expect("Invalid path of `lazy_compiler!`");
+        }
+        &*COMPILER
+    }};
+}
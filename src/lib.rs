@@ -1,31 +1,87 @@
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "capi"), forbid(unsafe_code))]
+#![cfg_attr(feature = "capi", deny(unsafe_code))]
 #![cfg_attr(docsrs, feature(doc_cfg))]
-#![deny(private_in_public, unreachable_pub, missing_docs, rust_2018_idioms)]
+#![deny(unreachable_pub, missing_docs, rust_2018_idioms)]
 #![doc = include_str!("../README.md")]
 
 mod ast;
+#[cfg(feature = "capi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "capi")))]
+pub mod capi;
 #[cfg(feature = "compile")]
 #[cfg_attr(docsrs, doc(cfg(feature = "compile")))]
 mod compiler;
+mod delimiters;
+mod describe;
+#[cfg(any(feature = "compile", feature = "match"))]
+pub mod encoders;
+#[cfg(feature = "cache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+mod lazy;
 #[cfg(feature = "match")]
 #[cfg_attr(docsrs, doc(cfg(feature = "match")))]
 mod matcher;
+#[cfg(feature = "compile")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compile")))]
+pub mod number_formats;
+#[cfg(any(feature = "compile", feature = "match"))]
+mod params;
 mod parser;
 mod re;
+#[cfg(all(feature = "compile", feature = "match"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "compile", feature = "match"))))]
+mod routes;
+#[cfg(feature = "compile-simple")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compile-simple")))]
+mod simple;
 mod try_into_with;
+#[cfg(feature = "url")]
+#[cfg_attr(docsrs, doc(cfg(feature = "url")))]
+mod url;
 
 pub use ast::{Key, Token};
-pub use parser::{Parser, ParserBuilder, ParserOptions};
-pub use re::{PathRegex, PathRegexBuilder, PathRegexOptions};
+pub use delimiters::Delimiters;
+pub use describe::{describe_tokens, ParamDescription, PathDescription};
+pub use parser::{ParseWarning, ParseWarningKind, Parser, ParserBuilder, ParserOptions};
+pub use re::{PathRegex, PathRegexBuilder, PathRegexOptions, UnsupportedPattern};
 pub use try_into_with::TryIntoWith;
 
 #[cfg(feature = "compile")]
-pub use compiler::{Compiler, CompilerBuilder, CompilerOptions};
+pub use compiler::{
+    stringify_bool, Compiler, CompilerBuilder, CompilerOptions, DelimiterPolicy, EncodeError,
+    Encoder, ParamValue, PathExample, RenderCase, RenderError, RenderErrors, RenderOverrides,
+    RenderPosition, RenderProblem, RenderProblemKind, RenderWarning,
+};
+#[cfg(feature = "cache")]
+#[doc(hidden)]
+pub use lazy_static::lazy_static as __lazy_static;
+#[cfg(feature = "match")]
+pub use matcher::{
+    DecodeError, FragmentMode, MatchResult, MatchTimeout, Matcher, MatcherBuilder, MatcherOptions,
+    RawMatchResult,
+};
 #[cfg(feature = "match")]
-pub use matcher::{MatchResult, Matcher, MatcherBuilder, MatcherOptions};
+pub use params::FromParams;
+#[cfg(feature = "compile")]
+pub use params::ToParams;
+#[cfg(all(feature = "compile", feature = "match"))]
+pub use routes::{Route, RouteDescription, RouteTrace, Routes, RoutesDescription};
+#[cfg(feature = "compile-simple")]
+pub use simple::compile_path;
+#[cfg(feature = "url")]
+pub use url::UrlMatcher;
 /// The matching trailing character is used for 'end' and 'ends_with' configuration item filtering
 pub const DEFAULT_DELIMITER: &str = "/#?";
 
+/// The support of the code generated by `path2regex-macros`
+#[cfg(any(feature = "compile", feature = "match"))]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::params::{from_str, optional, repeated, required};
+    pub use anyhow::{Error, Result};
+    pub use serde_json::{Map, Value};
+}
+
 mod internal {
     pub(crate) use regex::escape as escape_string;
     #[cfg(any(feature = "compile", feature = "match"))]
@@ -36,9 +92,38 @@ mod internal {
         std::any::type_name::<T>().to_string()
     }
 
+    /// The separator of the values of a repeated key, by the name of the key or for every key
+    pub(crate) fn repeat_separator_of<'a>(
+        separators: &'a std::collections::HashMap<String, String>,
+        separator: &'a Option<String>,
+        key: &crate::Key,
+    ) -> Option<&'a str> {
+        separators
+            .get(&key.name)
+            .or(separator.as_ref())
+            .map(String::as_str)
+    }
+
     pub(crate) type FnStr = for<'a> fn(&'a str) -> String;
     #[cfg(any(feature = "compile", feature = "match"))]
     pub(crate) type FnStrWithKey = for<'a> fn(&'a str, &'a crate::Key) -> String;
+    #[cfg(feature = "compile")]
+    pub(crate) type FnTryStrWithKey =
+        for<'a> fn(&'a str, &'a crate::Key) -> Result<String, crate::EncodeError>;
+    #[cfg(feature = "compile")]
+    pub(crate) type FnNumberWithKey = for<'a> fn(&'a serde_json::Number, &'a crate::Key) -> String;
+    #[cfg(feature = "compile")]
+    pub(crate) type FnStringifyWithKey =
+        for<'a> fn(&'a DataValue, &'a crate::Key) -> Option<String>;
+    #[cfg(feature = "match")]
+    pub(crate) type FnCowWithKey =
+        for<'a, 'k> fn(&'a str, &'k crate::Key) -> std::borrow::Cow<'a, str>;
 
     pub(crate) const END_WITH_DELIMITER: &str = "END_WITH_DELIMITER";
+    pub(crate) const TRAILING_DELIMITER: &str = "TRAILING_DELIMITER";
+
+    /// Whether a capture group is added for the delimiters, after the groups of the keys
+    pub(crate) fn is_internal_group(name: Option<&str>) -> bool {
+        matches!(name, Some(END_WITH_DELIMITER | TRAILING_DELIMITER))
+    }
 }
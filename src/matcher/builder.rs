@@ -1,19 +1,29 @@
 //! The Builder of the [`Matcher`](struct.Matcher.html)
-use anyhow::Result;
+use std::{borrow::Cow, collections::HashMap, sync::Mutex};
+
+use anyhow::{anyhow, Result};
 
 use crate::{
-    internal::{type_of, FnStr, FnStrWithKey},
+    internal::{repeat_separator_of, type_of, FnCowWithKey, FnStr, FnStrWithKey},
     try_into_with::TryIntoWith,
-    Matcher, PathRegex, PathRegexOptions,
+    Delimiters, Key, Matcher, ParserOptions, PathRegex, PathRegexOptions,
 };
 
+use super::cache::MatchCache;
+
 /// The Configuration of the [`Matcher`](struct.Matcher.html)
 #[derive(Clone)]
 pub struct MatcherOptions {
-    /// Set the default delimiter for repeat parameters. (default: `'/#?'`)
-    pub delimiter: String,
+    /// The delimiters of a path segment, the first one separates repeated values. (default: `"/#?"`)
+    pub delimiter: Delimiters,
     /// List of characters to automatically consider prefixes when parsing.
     pub prefixes: String,
+    /// Characters that start a path segment besides the delimiter, like `:` in `urn:isbn:12345`.
+    /// They are literal text in the path and are considered prefixes. (default: `None`)
+    pub start_delimiter: Option<String>,
+    /// When `true` a `?` right before a parameter name starts the parameter like `:`,
+    /// like `/users?page`. (default: `false`)
+    pub query_params: bool,
     /// When `true` the regexp will be case sensitive. (default: `false`)
     pub sensitive: bool,
     /// When `true` the regexp won't allow an optional trailing delimiter to match. (default: `false`)
@@ -24,10 +34,56 @@ pub struct MatcherOptions {
     pub start: bool,
     /// List of characters that can also be "end" characters.
     pub ends_with: String,
-    /// Encode path tokens for use in the `Regex`.
-    pub encode: FnStr,
-    /// Function for decoding strings for params.
-    pub decode: FnStrWithKey,
+    /// When `true` the parameters can't match a delimiter, except the repeated ones. (default: `false`)
+    ///
+    /// The delimiters are removed from every literal and class of the patterns.
+    pub confine_to_segment: bool,
+    /// Encode the static text of the path for use in the `Regex`.
+    pub encode_static: FnStr,
+    /// Encode the prefixes and suffixes of the keys for use in the `Regex`.
+    pub encode_affix: FnStr,
+    /// Function for decoding strings for params, borrowing the string when it is unchanged.
+    pub decode: FnCowWithKey,
+    /// Function for encoding the values spliced into a path by
+    /// [`replace_params`](struct.Matcher.html#method.replace_params).
+    pub encode_value: FnStrWithKey,
+    /// Cache the results of up to this many recent paths. (default: `None`)
+    ///
+    /// The cache assumes `decode` is deterministic: leave it disabled when it is not.
+    pub cache_size: Option<usize>,
+    /// Only put the first `max_captures` parameters in the params, dropping the others. (default: `None`)
+    pub max_captures: Option<usize>,
+    /// When `true` a parameter that is empty once decoded is treated as missing. (default: `false`)
+    ///
+    /// It takes its default value or is left out of the params,
+    /// and the empty values of a repeated parameter are dropped.
+    pub blank_as_missing: bool,
+    /// When `true` the matrix parameters of the segments, like `;color=red` in `/items;color=red/42`,
+    /// are left out of the path to match and put in the `matrix` object of the params. (default: `false`)
+    ///
+    /// The parameters of a segment are under the name of the key matching it, or the index of the
+    /// segment, and repeated parameters become arrays. It is only used by [`find`](struct.Matcher.html#method.find).
+    /// A path with a parameter named `matrix` fails to build.
+    pub matrix_params: bool,
+    /// How [`find`](struct.Matcher.html#method.find) handles the fragment of the path, from the first `#`.
+    /// When `None` the fragment is matched with the rest of the path. (default: `None`)
+    pub fragment: Option<FragmentMode>,
+    /// Split the values of the repeated parameters on this separator, like `,` in `/tags/a,b,c`,
+    /// instead of their suffix and prefix. (default: `None`)
+    pub repeat_separator: Option<String>,
+    /// The separator of a repeated parameter by name, overriding `repeat_separator`. (default: empty)
+    pub repeat_separators: HashMap<String, String>,
+}
+
+/// How the fragment of the path is handled by the [`Matcher`](struct.Matcher.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FragmentMode {
+    /// Leave the fragment out of the path to match
+    Ignore,
+    /// Leave the fragment out of the path to match and put it in the `fragment` of the result
+    Capture,
+    /// Don't match a path with a fragment
+    Reject,
 }
 
 impl Default for MatcherOptions {
@@ -35,30 +91,112 @@ impl Default for MatcherOptions {
         let PathRegexOptions {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
             sensitive,
             strict,
             end,
             start,
             ends_with,
-            encode,
+            confine_to_segment,
+            encode_static,
+            encode_affix,
+            repeat_separator,
+            repeat_separators,
         } = PathRegexOptions::default();
         Self {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
             sensitive,
             strict,
             end,
             start,
             ends_with,
-            encode,
-            decode: |x, _| x.to_owned(),
+            confine_to_segment,
+            encode_static,
+            encode_affix,
+            decode: |x, _| Cow::Borrowed(x),
+            encode_value: |x, _| x.to_owned(),
+            cache_size: None,
+            max_captures: None,
+            blank_as_missing: false,
+            matrix_params: false,
+            fragment: None,
+            repeat_separator,
+            repeat_separators,
+        }
+    }
+}
+
+impl MatcherOptions {
+    /// The default options with a `decode` function that percent-decodes the params
+    ///
+    /// Values that are not valid UTF-8 once decoded are kept as they are.
+    #[cfg(feature = "percent-encoding")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "percent-encoding")))]
+    pub fn decode_percent_encoded() -> Self {
+        Self {
+            decode: |x, _| urlencoding::decode(x).unwrap_or(Cow::Borrowed(x)),
+            ..Default::default()
         }
     }
+
+    /// The separator of the values of a repeated key, `None` to use its suffix and prefix
+    pub(crate) fn repeat_separator_of(&self, key: &Key) -> Option<&str> {
+        repeat_separator_of(&self.repeat_separators, &self.repeat_separator, key)
+    }
 }
 
 impl std::fmt::Display for MatcherOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
+        write!(
+            f,
+            "delimiter={:?}, prefixes={:?}, sensitive={}, strict={}, end={}, start={}, ends_with={:?}",
+            self.delimiter,
+            self.prefixes,
+            self.sensitive,
+            self.strict,
+            self.end,
+            self.start,
+            self.ends_with
+        )?;
+        if let Some(start_delimiter) = &self.start_delimiter {
+            write!(f, ", start_delimiter={start_delimiter:?}")?;
+        }
+        if self.query_params {
+            write!(f, ", query_params=true")?;
+        }
+        if self.confine_to_segment {
+            write!(f, ", confine_to_segment=true")?;
+        }
+        if let Some(cache_size) = self.cache_size {
+            write!(f, ", cache_size={cache_size}")?;
+        }
+        if let Some(max_captures) = self.max_captures {
+            write!(f, ", max_captures={max_captures}")?;
+        }
+        if self.blank_as_missing {
+            write!(f, ", blank_as_missing=true")?;
+        }
+        if self.matrix_params {
+            write!(f, ", matrix_params=true")?;
+        }
+        if let Some(fragment) = self.fragment {
+            write!(f, ", fragment={fragment:?}")?;
+        }
+        if let Some(repeat_separator) = &self.repeat_separator {
+            write!(f, ", repeat_separator={repeat_separator:?}")?;
+        }
+        if !self.repeat_separators.is_empty() {
+            let separators = self
+                .repeat_separators
+                .iter()
+                .collect::<std::collections::BTreeMap<_, _>>();
+            write!(f, ", repeat_separators={separators:?}")?;
+        }
+        Ok(())
     }
 }
 
@@ -67,18 +205,37 @@ impl std::fmt::Debug for MatcherOptions {
         f.debug_struct("MatcherOptions")
             .field("delimiter", &self.delimiter)
             .field("prefixes", &self.prefixes)
+            .field("start_delimiter", &self.start_delimiter)
+            .field("query_params", &self.query_params)
             .field("sensitive", &self.sensitive)
             .field("strict", &self.strict)
             .field("end", &self.end)
             .field("start", &self.start)
             .field("ends_with", &self.ends_with)
-            .field("encode", &type_of(self.encode))
+            .field("confine_to_segment", &self.confine_to_segment)
+            .field("encode_static", &type_of(self.encode_static))
+            .field("encode_affix", &type_of(self.encode_affix))
             .field("decode", &type_of(self.decode))
+            .field("encode_value", &type_of(self.encode_value))
+            .field("cache_size", &self.cache_size)
+            .field("max_captures", &self.max_captures)
+            .field("blank_as_missing", &self.blank_as_missing)
+            .field("matrix_params", &self.matrix_params)
+            .field("fragment", &self.fragment)
+            .field("repeat_separator", &self.repeat_separator)
+            .field(
+                "repeat_separators",
+                &self
+                    .repeat_separators
+                    .iter()
+                    .collect::<std::collections::BTreeMap<_, _>>(),
+            )
             .finish()
     }
 }
 
 /// The Builder of the [`Matcher`](struct.Matcher.html)
+#[derive(Clone)]
 pub struct MatcherBuilder<I> {
     source: I,
     options: MatcherOptions,
@@ -108,21 +265,72 @@ where
             .clone()
             .try_into_with(&PathRegexOptions::from(self.options.clone()))?;
 
+        if self.options.matrix_params && re.keys.iter().any(|key| key.name == "matrix") {
+            return Err(anyhow!(
+                "The parameter \"matrix\" is taken by the matrix parameters"
+            ));
+        }
+        let anchored = super::anchored_regex(&re, &self.options)?;
+        let matches = super::key_regexes(&re.keys, &self.options)?;
         Ok(Matcher {
+            anchored,
+            matches,
             re: re.clone(),
             keys: re.keys,
             options: self.options.clone(),
+            cache: self
+                .options
+                .cache_size
+                .map(|size| Mutex::new(MatchCache::new(size))),
         })
     }
 }
 
 impl<I> MatcherBuilder<I> {
+    /// Set the options of the parser, overwriting `delimiter`, `prefixes`, `start_delimiter` and `query_params`
+    pub fn set_parser_options(&mut self, options: ParserOptions) -> &mut Self {
+        let ParserOptions {
+            delimiter,
+            prefixes,
+            start_delimiter,
+            query_params,
+        } = options;
+        self.options.delimiter = delimiter;
+        self.options.prefixes = prefixes;
+        self.options.start_delimiter = start_delimiter;
+        self.options.query_params = query_params;
+        self
+    }
+
+    /// Get the options of the parser
+    pub fn parser_options(&self) -> ParserOptions {
+        ParserOptions {
+            delimiter: self.options.delimiter.clone(),
+            prefixes: self.options.prefixes.clone(),
+            start_delimiter: self.options.start_delimiter.clone(),
+            query_params: self.options.query_params,
+        }
+    }
+
     /// List of characters to automatically consider prefixes when parsing.
     pub fn set_prefixes(&mut self, prefixes: impl AsRef<str>) -> &mut Self {
         self.options.prefixes = prefixes.as_ref().to_owned();
         self
     }
 
+    /// Characters that start a path segment besides the delimiter, like `:` in `urn:isbn:12345`.
+    /// They are literal text in the path and are considered prefixes. (default: `None`)
+    pub fn set_start_delimiter(&mut self, start_delimiter: impl AsRef<str>) -> &mut Self {
+        self.options.start_delimiter = Some(start_delimiter.as_ref().to_owned());
+        self
+    }
+
+    /// When `true` a `?` right before a parameter name starts the parameter like `:`.
+    pub fn set_query_params(&mut self, yes: bool) -> &mut Self {
+        self.options.query_params = yes;
+        self
+    }
+
     /// When `true` the regexp will be case sensitive. (default: `false`)
     pub fn set_sensitive(&mut self, yes: bool) -> &mut Self {
         self.options.sensitive = yes;
@@ -147,9 +355,15 @@ impl<I> MatcherBuilder<I> {
         self
     }
 
-    /// Set the default delimiter for repeat parameters. (default: `'/'`)
-    pub fn set_delimiter(&mut self, de: impl AsRef<str>) -> &mut Self {
-        self.options.delimiter = de.as_ref().to_owned();
+    /// The delimiters of a path segment, the first one separates repeated values. (default: `"/#?"`)
+    pub fn set_delimiter(&mut self, delimiter: impl Into<Delimiters>) -> &mut Self {
+        self.options.delimiter = delimiter.into();
+        self
+    }
+
+    /// When `true` the parameters can't match a delimiter, except the repeated ones. (default: `false`)
+    pub fn set_confine_to_segment(&mut self, yes: bool) -> &mut Self {
+        self.options.confine_to_segment = yes;
         self
     }
 
@@ -159,15 +373,84 @@ impl<I> MatcherBuilder<I> {
         self
     }
 
-    /// Function for encoding input strings for output.
+    /// Function for encoding input strings for output, used for both the static text and the affixes.
     pub fn set_encode(&mut self, encode: FnStr) -> &mut Self {
-        self.options.encode = encode;
+        self.options.encode_static = encode;
+        self.options.encode_affix = encode;
+        self
+    }
+
+    /// Function for encoding the static text of the path.
+    pub fn set_encode_static(&mut self, encode: FnStr) -> &mut Self {
+        self.options.encode_static = encode;
+        self
+    }
+
+    /// Function for encoding the prefixes and suffixes of the keys.
+    pub fn set_encode_affix(&mut self, encode: FnStr) -> &mut Self {
+        self.options.encode_affix = encode;
         self
     }
 
-    /// Function for decoding strings for params.
-    pub fn set_decode(&mut self, decode: FnStrWithKey) -> &mut Self {
+    /// Function for decoding strings for params, borrowing the string when it is unchanged.
+    pub fn set_decode(&mut self, decode: FnCowWithKey) -> &mut Self {
         self.options.decode = decode;
         self
     }
+
+    /// Function for encoding the values spliced into a path by `replace_params`.
+    pub fn set_encode_value(&mut self, encode: FnStrWithKey) -> &mut Self {
+        self.options.encode_value = encode;
+        self
+    }
+
+    /// Cache the results of up to this many recent paths.
+    ///
+    /// The cache assumes `decode` is deterministic: leave it disabled when it is not.
+    pub fn set_cache_size(&mut self, size: usize) -> &mut Self {
+        self.options.cache_size = Some(size);
+        self
+    }
+
+    /// Only put the first `max_captures` parameters in the params, dropping the others. (default: `None`)
+    pub fn set_max_captures(&mut self, max_captures: usize) -> &mut Self {
+        self.options.max_captures = Some(max_captures);
+        self
+    }
+
+    /// When `true` a parameter that is empty once decoded is treated as missing. (default: `false`)
+    pub fn set_blank_as_missing(&mut self, yes: bool) -> &mut Self {
+        self.options.blank_as_missing = yes;
+        self
+    }
+
+    /// When `true` the matrix parameters of the segments are put in the `matrix` object of the params. (default: `false`)
+    pub fn set_matrix_params(&mut self, yes: bool) -> &mut Self {
+        self.options.matrix_params = yes;
+        self
+    }
+
+    /// How the fragment of the path is handled, from the first `#`.
+    pub fn set_fragment(&mut self, mode: FragmentMode) -> &mut Self {
+        self.options.fragment = Some(mode);
+        self
+    }
+
+    /// The separator of the values of the repeated parameters, like `,` in `/tags/a,b,c`.
+    pub fn set_repeat_separator(&mut self, separator: impl Into<String>) -> &mut Self {
+        self.options.repeat_separator = Some(separator.into());
+        self
+    }
+
+    /// The separator of the values of a repeated parameter, overriding `repeat_separator`.
+    pub fn set_key_repeat_separator(
+        &mut self,
+        name: impl Into<String>,
+        separator: impl Into<String>,
+    ) -> &mut Self {
+        self.options
+            .repeat_separators
+            .insert(name.into(), separator.into());
+        self
+    }
 }
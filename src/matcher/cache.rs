@@ -0,0 +1,165 @@
+//! The cache of the recent results of a [`Matcher`](struct.Matcher.html)
+
+use std::collections::HashMap;
+
+use crate::MatchResult;
+
+/// Paths longer than this are never cached
+pub(crate) const MAX_CACHED_PATH_LEN: usize = 256;
+
+/// The index of no entry in the recency list
+const NONE: usize = usize::MAX;
+
+/// A least recently used cache of the results keyed on the path
+///
+/// The entries are linked from the most to the least recently used by their index,
+/// so that using or evicting one doesn't scan the others.
+#[derive(Debug)]
+pub(crate) struct MatchCache {
+    capacity: usize,
+    indexes: HashMap<String, usize>,
+    entries: Vec<Entry>,
+    /// The most recently used entry
+    newest: usize,
+    /// The least recently used entry, evicted first
+    oldest: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    path: String,
+    result: Option<MatchResult>,
+    newer: usize,
+    older: usize,
+}
+
+impl MatchCache {
+    /// Create an empty cache, growing up to the capacity as paths are inserted
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            indexes: HashMap::new(),
+            entries: Vec::new(),
+            newest: NONE,
+            oldest: NONE,
+        }
+    }
+
+    pub(crate) fn get(&mut self, path: &str) -> Option<Option<MatchResult>> {
+        let index = *self.indexes.get(path)?;
+        self.unlink(index);
+        self.push_newest(index);
+        Some(self.entries[index].result.clone())
+    }
+
+    pub(crate) fn insert(&mut self, path: &str, result: Option<MatchResult>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let index = match self.indexes.get(path) {
+            Some(&index) => {
+                self.entries[index].result = result;
+                self.unlink(index);
+                index
+            }
+            None if self.entries.len() < self.capacity => {
+                self.entries.push(Entry {
+                    path: path.to_owned(),
+                    result,
+                    newer: NONE,
+                    older: NONE,
+                });
+                self.indexes.insert(path.to_owned(), self.entries.len() - 1);
+                self.entries.len() - 1
+            }
+            None => {
+                let index = self.oldest;
+                self.unlink(index);
+                let entry = &mut self.entries[index];
+                self.indexes.remove(&entry.path);
+                entry.path.clear();
+                entry.path.push_str(path);
+                entry.result = result;
+                self.indexes.insert(path.to_owned(), index);
+                index
+            }
+        };
+        self.push_newest(index);
+    }
+
+    /// Take the entry out of the recency list
+    fn unlink(&mut self, index: usize) {
+        let Entry { newer, older, .. } = self.entries[index];
+        match newer {
+            NONE => self.newest = older,
+            newer => self.entries[newer].older = older,
+        }
+        match older {
+            NONE => self.oldest = newer,
+            older => self.entries[older].newer = newer,
+        }
+    }
+
+    /// Put an unlinked entry at the front of the recency list
+    fn push_newest(&mut self, index: usize) {
+        let entry = &mut self.entries[index];
+        entry.newer = NONE;
+        entry.older = self.newest;
+        match self.newest {
+            NONE => self.oldest = index,
+            newest => self.entries[newest].newer = index,
+        }
+        self.newest = index;
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.indexes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let mut cache = MatchCache::new(2);
+        cache.insert("/a", None);
+        cache.insert("/b", None);
+        assert_eq!(cache.get("/a"), Some(None));
+        cache.insert("/c", None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("/b"), None);
+        assert_eq!(cache.get("/a"), Some(None));
+        assert_eq!(cache.get("/c"), Some(None));
+    }
+
+    #[test]
+    fn test_refresh_a_cached_path() {
+        let mut cache = MatchCache::new(3);
+        for path in ["/a", "/b", "/c"] {
+            cache.insert(path, None);
+        }
+        cache.insert("/a", None);
+        cache.insert("/d", None);
+        cache.insert("/e", None);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.get("/b"), None);
+        assert_eq!(cache.get("/c"), None);
+        for path in ["/a", "/d", "/e"] {
+            assert_eq!(cache.get(path), Some(None), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_keep_one_path() {
+        let mut cache = MatchCache::new(1);
+        cache.insert("/a", None);
+        cache.insert("/b", None);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("/a"), None);
+        assert_eq!(cache.get("/b"), Some(None));
+        assert_eq!(MatchCache::new(0).get("/a"), None);
+    }
+}
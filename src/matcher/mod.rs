@@ -1,14 +1,19 @@
 //! Path matcher
 mod builder;
+mod cache;
 
-use anyhow::Result;
+use std::{sync::Mutex, time::Duration};
+
+use anyhow::{anyhow, Result};
+use regex::{CaptureLocations, Captures, Regex, RegexBuilder};
 
 use crate::{
-    internal::{DataValue, END_WITH_DELIMITER},
-    Key, PathRegex, PathRegexOptions, TryIntoWith,
+    internal::{DataValue, END_WITH_DELIMITER, TRAILING_DELIMITER},
+    Delimiters, FromParams, Key, PathRegex, PathRegexOptions, TryIntoWith,
 };
 
-pub use builder::{MatcherBuilder, MatcherOptions};
+pub use builder::{FragmentMode, MatcherBuilder, MatcherOptions};
+use cache::{MatchCache, MAX_CACHED_PATH_LEN};
 
 /// Path matcher
 #[derive(Debug)]
@@ -16,6 +21,11 @@ pub struct Matcher {
     pub(crate) re: PathRegex,
     pub(crate) keys: Vec<Key>,
     pub(crate) options: MatcherOptions,
+    pub(crate) cache: Option<Mutex<MatchCache>>,
+    /// The regex anchored at the start of the path, when `start` is `false`
+    pub(crate) anchored: Option<Regex>,
+    /// The regexes validating the values of the keys in `replace_params`
+    pub(crate) matches: Vec<Regex>,
 }
 
 impl Matcher {
@@ -37,59 +47,619 @@ impl Matcher {
         MatcherBuilder::new_with_options(path, options).build()
     }
 
+    /// Get the parameters captured by the matcher, like [`PathRegex::keys`](struct.PathRegex.html#method.keys)
+    #[inline]
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+
+    /// Take the [`PathRegex`](struct.PathRegex.html) and the options the matcher was built with
+    #[inline]
+    pub fn into_router_entry(self) -> (PathRegex, MatcherOptions) {
+        (self.re, self.options)
+    }
+
     /// matching parameters in the path
     pub fn find<S>(&self, path: S) -> Option<MatchResult>
     where
         S: AsRef<str>,
     {
         let path = path.as_ref();
-        let MatcherOptions { decode, .. } = &self.options;
+        match &self.cache {
+            Some(cache) if path.len() <= MAX_CACHED_PATH_LEN => {
+                let lock = || cache.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(result) = lock().get(path) {
+                    return result;
+                }
+                let result = self.find_uncached(path);
+                lock().insert(path, result.clone());
+                result
+            }
+            _ => self.find_uncached(path),
+        }
+    }
 
-        let captures = self.re.captures(path)?;
-        let m = captures.get(0)?;
+    /// matching parameters in the path, borrowing their raw values from the path
+    ///
+    /// Unlike [`find`](struct.Matcher.html#method.find) it doesn't own the values, so they are
+    /// not decoded, and the options `fragment`, `matrix_params`, `max_captures` and
+    /// `blank_as_missing`, the defaults and the cache are left out.
+    pub fn find_raw<'t>(&'t self, path: &'t str) -> Option<RawMatchResult<'t>> {
+        let mut locs = self.re.capture_locations();
+        let m = self.re.captures_read(&mut locs, path)?;
+        let mut end = m.end();
+        let end_with = self
+            .re
+            .capture_names()
+            .position(|name| name == Some(END_WITH_DELIMITER))
+            .and_then(|i| locs.get(i));
+        if let Some((start, _)) = end_with {
+            end = end.min(start);
+        }
+        Some(RawMatchResult {
+            path: &path[m.start()..end],
+            index: m.start(),
+            input: path,
+            keys: &self.keys,
+            locs,
+        })
+    }
 
-        let params = captures
+    /// Split the path of a match on the primary delimiter of the matcher, leaving out the
+    /// empty segments
+    pub fn path_segments<'r>(&self, result: &'r MatchResult) -> Vec<&'r str> {
+        match self.options.delimiter.primary() {
+            Some(delimiter) => result.path_segments_on(delimiter),
+            None => vec![result.path.as_str()],
+        }
+    }
+
+    /// matching parameters in a leading part of the path, with the rest of the path
+    ///
+    /// The matcher is usually built with `end` set to `false`. The rest of the path starts
+    /// after the matched `path` of the result, at the delimiter ending the match.
+    pub fn match_prefix<'a>(&self, path: &'a str) -> Option<(&'a str, MatchResult)> {
+        let result = self.find(path)?;
+        // The match is on the path without its matrix parameters and its fragment
+        let mut end = result.index + result.path.len();
+        if self.options.matrix_params {
+            let searched = match (self.options.fragment, path.split_once('#')) {
+                (Some(_), Some((searched, _))) => searched,
+                _ => path,
+            };
+            end = strip_matrix(searched, &self.options.delimiter).original_offset(end);
+        }
+        Some((&path[end..], result))
+    }
+
+    /// Replace the values of some parameters in a matching path
+    ///
+    /// The values are encoded with `encode_value` and must match the patterns of their keys.
+    /// The rest of the path, including the values of the other parameters, the matrix
+    /// parameters and the fragment, is kept as it is.
+    pub fn replace_params(&self, path: &str, replacements: &DataValue) -> Result<String> {
+        let replacements = replacements
+            .as_object()
+            .ok_or_else(|| anyhow!("Expected the replacements to be an object"))?;
+        if let Some(name) = replacements
+            .keys()
+            .find(|name| !self.keys.iter().any(|key| &&key.name == name))
+        {
+            return Err(anyhow!("Unknown parameter \"{name}\""));
+        }
+        let mismatch = || anyhow!("Expected \"{path}\" to match \"{}\"", self.re.as_str());
+        // The path is matched like in `find`, the fragment and the matrix parameters are kept
+        let (path, fragment) = match (self.options.fragment, path.split_once('#')) {
+            (Some(FragmentMode::Reject), Some(_)) => return Err(mismatch()),
+            (Some(_), Some((path, fragment))) => (path, Some(fragment)),
+            _ => (path, None),
+        };
+        let matrix = self
+            .options
+            .matrix_params
+            .then(|| strip_matrix(path, &self.options.delimiter));
+        let haystack = matrix
+            .as_ref()
+            .map_or(path, |matrix| matrix.stripped.as_str());
+        let captures = self.re.captures(haystack).ok_or_else(mismatch)?;
+        let offset = |offset: usize| {
+            matrix
+                .as_ref()
+                .map_or(offset, |matrix| matrix.offset_in_segment(offset))
+        };
+
+        let mut result = String::with_capacity(path.len());
+        let mut last = 0;
+        for ((capture, key), re) in captures
             .iter()
             .skip(1)
-            .map(|x| x.map_or("", |x| x.as_str()))
             .zip(self.keys.iter())
-            .map(|(value, key)| {
-                let Key {
-                    name,
-                    prefix,
-                    suffix,
-                    ..
-                } = key;
-
-                match name.as_str() {
-                    "*" | "+" => {
-                        let sp = if prefix.is_empty() { suffix } else { prefix };
-                        let value = value
-                            .split(sp)
-                            .map(|x| DataValue::String(decode(x, key)))
-                            .collect();
-                        (name.to_owned(), DataValue::Array(value))
+            .zip(self.matches.iter())
+        {
+            let value = match replacements.get(&key.name) {
+                Some(value) => value,
+                None => continue,
+            };
+            let name = &key.name;
+            let capture = capture
+                .ok_or_else(|| anyhow!("Expected \"{name}\" to be in \"{path}\" to replace it"))?;
+            let segment = |value: &DataValue| {
+                let value = match value {
+                    DataValue::String(value) => value.to_owned(),
+                    DataValue::Number(value) => value.to_string(),
+                    _ => return Err(anyhow!("Expected \"{name}\" to be a string or a number")),
+                };
+                let segment = (self.options.encode_value)(&value, key);
+                if !re.is_match(&segment) {
+                    return Err(anyhow!(
+                        "Expected all \"{name}\" to match \"{}\", but got \"{segment}\"",
+                        key.pattern
+                    ));
+                }
+                Ok(segment)
+            };
+            let replacement = match (value, key.modifier.as_str()) {
+                (DataValue::Array(values), "+" | "*") if !values.is_empty() => {
+                    let separator = repeat_separator(key, &self.options);
+                    values
+                        .iter()
+                        .map(segment)
+                        .collect::<Result<Vec<_>>>()?
+                        .join(&separator)
+                }
+                (DataValue::Array(_), "+" | "*") => {
+                    return Err(anyhow!("Expected \"{name}\" to not be empty"))
+                }
+                (DataValue::Array(_), _) => {
+                    return Err(anyhow!(
+                        "Expected \"{name}\" to not repeat, but got an array"
+                    ))
+                }
+                (value, _) => segment(value)?,
+            };
+            result += &path[last..offset(capture.start())];
+            result += &replacement;
+            last = offset(capture.end());
+        }
+        result += &path[last..];
+        if let Some(fragment) = fragment {
+            result.push('#');
+            result += fragment;
+        }
+        Ok(result)
+    }
+
+    /// matching parameters in the path and extract them as `T`
+    ///
+    /// It is `Ok(None)` when the path doesn't match, and an error when a parameter can't be extracted.
+    pub fn find_as<T, S>(&self, path: S) -> Result<Option<T>>
+    where
+        T: FromParams,
+        S: AsRef<str>,
+    {
+        self.find(path)
+            .map(|result| T::from_params(&result.params))
+            .transpose()
+    }
+
+    /// matching parameters in the path, at exactly `offset` bytes into it
+    ///
+    /// The rest of the path is matched like with [`find`](struct.Matcher.html#method.find),
+    /// fragment and matrix parameters included, but only from its start.
+    /// The `index` of the result is an offset into the whole path.
+    pub fn find_anchored_at(&self, path: &str, offset: usize) -> Option<MatchResult> {
+        // `^` only matches at the start of the haystack, search the rest of the path instead
+        let rest = path.get(offset..)?;
+        let re = self.anchored.as_ref().unwrap_or(&self.re.re);
+        let mut result = self.find_with(rest, re)?;
+        result.index += offset;
+        Some(result)
+    }
+
+    /// matching parameters in the path, catching a panic of the `decode` function
+    ///
+    /// The panic is still reported by the panic hook, which prints it by default.
+    pub fn find_opt<S>(&self, path: S) -> Result<Option<MatchResult>, DecodeError>
+    where
+        S: AsRef<str>,
+    {
+        let path = path.as_ref();
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.find(path))).map_err(
+            |payload| {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                DecodeError::Panic(message)
+            },
+        )
+    }
+
+    /// matching parameters in the path, giving up after `timeout`
+    ///
+    /// Every call spawns a thread to run the match on, and copies the matcher, without its
+    /// cache, and the path to it. When the match times out, the thread is left running until the match is over,
+    /// so a caller retrying slow matches piles up threads. The regex itself matches in linear
+    /// time, the time is mostly spent in the `decode` function or on a very long path.
+    pub fn find_with_timeout(
+        &self,
+        path: &str,
+        timeout: Duration,
+    ) -> Result<Option<MatchResult>, MatchTimeout> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // The match doesn't use the cache
+        let matcher = Matcher {
+            re: self.re.clone(),
+            keys: self.keys.clone(),
+            options: self.options.clone(),
+            cache: None,
+            anchored: None,
+            matches: vec![],
+        };
+        let path = path.to_owned();
+        std::thread::spawn(move || sender.send(matcher.find_uncached(&path)));
+        receiver
+            .recv_timeout(timeout)
+            .map_err(|_| MatchTimeout { timeout })
+    }
+
+    #[inline]
+    fn find_uncached(&self, path: &str) -> Option<MatchResult> {
+        self.find_with(path, &self.re.re)
+    }
+
+    /// Match the path with the regex, or a variant of it with the same groups
+    fn find_with(&self, path: &str, re: &Regex) -> Option<MatchResult> {
+        let (path, fragment) = match (self.options.fragment, path.split_once('#')) {
+            (Some(FragmentMode::Reject), Some(_)) => return None,
+            (Some(FragmentMode::Ignore), Some((path, _))) => (path, None),
+            (Some(FragmentMode::Capture), Some((path, fragment))) => (path, Some(fragment)),
+            _ => (path, None),
+        };
+        let mut result = if self.options.matrix_params {
+            self.find_with_matrix(path, re)?
+        } else {
+            self.to_result(&re.captures(path)?)?
+        };
+        result.fragment = fragment.map(str::to_owned);
+        Some(result)
+    }
+
+    /// Match the path without its matrix parameters, then add them to the params
+    fn find_with_matrix(&self, path: &str, re: &Regex) -> Option<MatchResult> {
+        let MatrixPath { stripped, segments } = strip_matrix(path, &self.options.delimiter);
+
+        let captures = re.captures(&stripped)?;
+        let mut result = self.to_result(&captures)?;
+
+        let mut matrix = serde_json::Map::new();
+        for (index, segment) in segments.into_iter().enumerate() {
+            let MatrixSegment {
+                start,
+                end,
+                matrix: params,
+                ..
+            } = segment;
+            if params.is_empty() {
+                continue;
+            }
+            let name = captures
+                .iter()
+                .skip(1)
+                .zip(self.keys.iter())
+                .find(|(capture, _)| {
+                    capture.map_or(false, |c| start <= c.start() && c.start() < end)
+                })
+                .map_or_else(|| index.to_string(), |(_, key)| key.name.to_owned());
+            let mut values = serde_json::Map::new();
+            for param in params.split(';').filter(|param| !param.is_empty()) {
+                let (name, value) = param.split_once('=').unwrap_or((param, ""));
+                let key = Key {
+                    name: name.to_owned(),
+                    ..Default::default()
+                };
+                let value = DataValue::String((self.options.decode)(value, &key).into_owned());
+                match values.get_mut(name) {
+                    Some(DataValue::Array(previous)) => previous.push(value),
+                    Some(previous) => *previous = DataValue::Array(vec![previous.take(), value]),
+                    None => {
+                        values.insert(name.to_owned(), value);
                     }
-                    _ => (name.to_owned(), DataValue::String(decode(value, key))),
                 }
+            }
+            matrix.insert(name, DataValue::Object(values));
+        }
+        if let (false, DataValue::Object(params)) = (matrix.is_empty(), &mut result.params) {
+            params.insert("matrix".to_owned(), DataValue::Object(matrix));
+        }
+        Some(result)
+    }
+
+    fn to_result(&self, captures: &Captures<'_>) -> Option<MatchResult> {
+        let MatcherOptions {
+            decode,
+            max_captures,
+            blank_as_missing,
+            ..
+        } = &self.options;
+
+        let m = captures.get(0)?;
+
+        let param = |capture: Option<&str>, key: &Key| {
+            let repeat = matches!(key.modifier.as_str(), "*" | "+");
+            // Blank values are checked before being owned, they are dropped when missing
+            let blank = |value: &str| *blank_as_missing && value.is_empty();
+            let value = capture.and_then(|value| match repeat {
+                true => {
+                    let values = split_repeated(value, key, &self.options)
+                        .map(|x| decode(x, key))
+                        .filter(|x| !blank(x))
+                        .map(|x| DataValue::String(x.into_owned()))
+                        .collect::<Vec<_>>();
+                    (!(*blank_as_missing && values.is_empty())).then_some(DataValue::Array(values))
+                }
+                false => {
+                    let value = decode(value, key);
+                    (!blank(&value)).then(|| DataValue::String(value.into_owned()))
+                }
+            });
+            if value.is_some() {
+                return value;
+            }
+            // The default value of a missing parameter is used as is
+            match &key.default {
+                Some(default) if repeat => Some(DataValue::Array(
+                    split_repeated(default, key, &self.options)
+                        .map(|x| DataValue::String(x.to_owned()))
+                        .collect(),
+                )),
+                Some(default) => Some(DataValue::String(default.to_owned())),
+                None if *blank_as_missing => None,
+                None if repeat => Some(DataValue::Array(vec![])),
+                None => Some(DataValue::String(String::new())),
+            }
+        };
+
+        let params = captures
+            .iter()
+            .skip(1)
+            .zip(self.keys.iter())
+            .take(max_captures.unwrap_or(usize::MAX))
+            .filter_map(|(capture, key)| {
+                let value = param(capture.map(|x| x.as_str()), key)?;
+                Some((key.name.to_owned(), value))
             })
             .collect::<DataValue>();
 
         let mut path = m.as_str();
-        if captures.name(END_WITH_DELIMITER).is_some() {
-            path = &path[..path.len() - 1];
-        }
+        let end_with = captures
+            .name(END_WITH_DELIMITER)
+            .filter(|end_with| !end_with.as_str().is_empty());
+        let trailing_delimiter = match end_with {
+            Some(end_with) => {
+                path = &path[..path.len() - end_with.as_str().len()];
+                end_with.as_str().chars().next()
+            }
+            None => captures
+                .name(TRAILING_DELIMITER)
+                .and_then(|delimiter| delimiter.as_str().chars().next()),
+        };
 
         Some(MatchResult {
             index: m.start(),
             path: path.to_owned(),
             params,
+            trailing_delimiter,
+            fragment: None,
         })
     }
 }
 
+impl Clone for Matcher {
+    /// The clone starts with an empty cache of the same size
+    fn clone(&self) -> Self {
+        Self {
+            re: self.re.clone(),
+            keys: self.keys.clone(),
+            options: self.options.clone(),
+            cache: self
+                .options
+                .cache_size
+                .map(|size| Mutex::new(MatchCache::new(size))),
+            anchored: self.anchored.clone(),
+            matches: self.matches.clone(),
+        }
+    }
+}
+
+/// The regexes validating the values of the keys
+pub(crate) fn key_regexes(keys: &[Key], options: &MatcherOptions) -> Result<Vec<Regex>> {
+    keys.iter()
+        .map(|key| {
+            RegexBuilder::new(&format!("^(?:{})$", key.pattern))
+                .case_insensitive(!options.sensitive)
+                .build()
+                .map_err(Into::into)
+        })
+        .collect()
+}
+
+/// The regex anchored at the start of the path, when it isn't with `start`
+pub(crate) fn anchored_regex(re: &PathRegex, options: &MatcherOptions) -> Result<Option<Regex>> {
+    if options.start {
+        return Ok(None);
+    }
+    // The group keeps the flags of the pattern and doesn't capture
+    Ok(Some(Regex::new(&format!("^(?:{})", re.as_str()))?))
+}
+
+/// The match of [`find_raw`](struct.Matcher.html#method.find_raw), borrowing from the path
+#[derive(Debug, Clone)]
+pub struct RawMatchResult<'t> {
+    /// The path of the match
+    pub path: &'t str,
+    /// The index of the match
+    pub index: usize,
+    input: &'t str,
+    keys: &'t [Key],
+    locs: CaptureLocations,
+}
+
+impl<'t> RawMatchResult<'t> {
+    /// Get the raw value of a parameter, `None` when the parameter is unknown or not in the path
+    ///
+    /// The value of a repeated parameter is all its values with their separators.
+    pub fn get(&self, name: &str) -> Option<&'t str> {
+        let i = self.keys.iter().position(|key| key.name == name)?;
+        self.value(i)
+    }
+
+    /// Iterate over the names of the parameters with their raw values
+    pub fn iter(&self) -> impl Iterator<Item = (&'t str, Option<&'t str>)> + '_ {
+        let keys = self.keys;
+        keys.iter()
+            .enumerate()
+            .map(move |(i, key)| (key.name.as_str(), self.value(i)))
+    }
+
+    fn value(&self, i: usize) -> Option<&'t str> {
+        let input = self.input;
+        self.locs.get(i + 1).map(|(start, end)| &input[start..end])
+    }
+}
+
+/// The error of [`find_with_timeout`](struct.Matcher.html#method.find_with_timeout)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchTimeout {
+    /// The time the match was given
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for MatchTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Matching took longer than {:?}", self.timeout)
+    }
+}
+
+impl std::error::Error for MatchTimeout {}
+
+/// The error of [`find_opt`](struct.Matcher.html#method.find_opt)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The `decode` function panicked, with the message of the panic
+    Panic(String),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Panic(message) if message.is_empty() => f.write_str("Decoding panicked"),
+            DecodeError::Panic(message) => write!(f, "Decoding panicked: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A path without its matrix parameters
+struct MatrixPath<'a> {
+    stripped: String,
+    segments: Vec<MatrixSegment<'a>>,
+}
+
+/// The bounds of a segment in the stripped path and in the path, with its matrix parameters
+struct MatrixSegment<'a> {
+    start: usize,
+    end: usize,
+    original_start: usize,
+    original_end: usize,
+    matrix: &'a str,
+}
+
+impl MatrixPath<'_> {
+    /// The offset in the path of an offset in the stripped path, after the matrix
+    /// parameters at the end of a segment
+    fn original_offset(&self, offset: usize) -> usize {
+        self.segments
+            .iter()
+            .find(|segment| segment.start <= offset && offset <= segment.end)
+            .map_or(offset, |segment| match offset == segment.end {
+                true => segment.original_end,
+                false => segment.original_start + offset - segment.start,
+            })
+    }
+
+    /// The offset in the path of an offset in the stripped path, before the matrix
+    /// parameters at the end of a segment
+    fn offset_in_segment(&self, offset: usize) -> usize {
+        self.segments
+            .iter()
+            .find(|segment| segment.start <= offset && offset <= segment.end)
+            .map_or(offset, |segment| {
+                segment.original_start + offset - segment.start
+            })
+    }
+}
+
+/// Strip the matrix parameters, like `;color=red`, from the segments of the path
+fn strip_matrix<'a>(path: &'a str, delimiter: &Delimiters) -> MatrixPath<'a> {
+    let mut stripped = String::with_capacity(path.len());
+    let mut segments = vec![];
+    let mut rest = path;
+    loop {
+        let end = rest.find(|c| delimiter.contains(c)).unwrap_or(rest.len());
+        let (segment, matrix) = rest[..end].split_once(';').unwrap_or((&rest[..end], ""));
+        let start = stripped.len();
+        let original_start = path.len() - rest.len();
+        stripped += segment;
+        segments.push(MatrixSegment {
+            start,
+            end: stripped.len(),
+            original_start,
+            original_end: original_start + end,
+            matrix,
+        });
+        let delimiter = match rest[end..].chars().next() {
+            Some(delimiter) => delimiter,
+            None => break,
+        };
+        stripped.push(delimiter);
+        rest = &rest[end + delimiter.len_utf8()..];
+    }
+    MatrixPath { stripped, segments }
+}
+
+/// The separator of the values of a repeated key: the `repeat_separator` option,
+/// or its suffix and prefix, or the primary delimiter
+fn repeat_separator(key: &Key, options: &MatcherOptions) -> String {
+    if let Some(separator) = options.repeat_separator_of(key) {
+        return separator.to_owned();
+    }
+    let mut separator = format!("{}{}", key.suffix, key.prefix);
+    if separator.is_empty() {
+        separator.extend(options.delimiter.primary());
+    }
+    separator
+}
+
+/// Split the value of a repeated key on its separator
+fn split_repeated<'a>(
+    value: &'a str,
+    key: &Key,
+    options: &MatcherOptions,
+) -> impl Iterator<Item = &'a str> {
+    let separator = repeat_separator(key, options);
+    let values: Vec<&str> = match (value.is_empty(), separator.is_empty()) {
+        (true, _) => vec![],
+        (false, true) => vec![value],
+        (false, false) => value.split(separator.as_str()).collect(),
+    };
+    values.into_iter()
+}
+
 /// Regular matching results
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub struct MatchResult {
     /// The path of the match
     pub path: String,
@@ -97,6 +667,114 @@ pub struct MatchResult {
     pub index: usize,
     /// Matching parameters
     pub params: DataValue,
+    /// The optional delimiter at the end of the match, not taken by a parameter,
+    /// or the delimiter that ended the match when `end` is `false`
+    pub trailing_delimiter: Option<char>,
+    /// The fragment of the path, without the `#`, when it is captured
+    pub fragment: Option<String>,
+}
+
+impl MatchResult {
+    /// Get the path of the match as bytes
+    #[inline]
+    pub fn path_bytes(&self) -> &[u8] {
+        self.path.as_bytes()
+    }
+
+    /// Split the path of the match on `/`, leaving out the empty segments
+    ///
+    /// Use [`Matcher::path_segments`](struct.Matcher.html#method.path_segments) to split on
+    /// the delimiter of the matcher.
+    #[inline]
+    pub fn path_segments(&self) -> Vec<&str> {
+        self.path_segments_on('/')
+    }
+
+    /// Split the path of the match on `delimiter`, leaving out the empty segments
+    pub fn path_segments_on(&self, delimiter: char) -> Vec<&str> {
+        self.path
+            .split(delimiter)
+            .filter(|segment| !segment.is_empty())
+            .collect()
+    }
+
+    /// Get the params in the shape expected by [`Compiler::render`](struct.Compiler.html#method.render)
+    ///
+    /// Empty values are left out, they are what a missing optional parameter matches.
+    /// Rendering the data gives back a path matching the same params, except for values that
+    /// only match after being decoded and required parameters matching an empty value.
+    pub fn to_data(&self) -> DataValue {
+        let params = match &self.params {
+            DataValue::Object(params) => params,
+            _ => return DataValue::Object(Default::default()),
+        };
+        params
+            .iter()
+            .filter(|(_, value)| match value {
+                DataValue::String(value) => !value.is_empty(),
+                DataValue::Array(value) => !value.is_empty(),
+                _ => true,
+            })
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+}
+
+/// Parse a result logged as `path|index|params`, like `/user/42|0|{"id":"42"}`
+///
+/// The path can't contain a `|`, the params are a JSON object.
+/// `trailing_delimiter` and `fragment` are left unset.
+impl std::str::FromStr for MatchResult {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.splitn(3, '|');
+        let (path, index, params) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(path), Some(index), Some(params)) => (path, index, params),
+            _ => return Err(anyhow!("Expected \"{s}\" to be \"path|index|params\"")),
+        };
+        let index = index
+            .parse()
+            .map_err(|_| anyhow!("Expected the index to be an integer, but got \"{index}\""))?;
+        let params: DataValue = serde_json::from_str(params)?;
+        if !params.is_object() {
+            return Err(anyhow!(
+                "Expected the params to be an object, but got {params}"
+            ));
+        }
+        Ok(MatchResult {
+            path: path.to_owned(),
+            index,
+            params,
+            ..Default::default()
+        })
+    }
+}
+
+impl std::fmt::Debug for MatchResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        /// Print the params as JSON, pretty when `{:#?}` is used
+        struct Params<'a>(&'a DataValue);
+
+        impl std::fmt::Debug for Params<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let json = if f.alternate() {
+                    serde_json::to_string_pretty(self.0)
+                } else {
+                    serde_json::to_string(self.0)
+                };
+                f.write_str(&json.map_err(|_| std::fmt::Error)?)
+            }
+        }
+
+        f.debug_struct("MatchResult")
+            .field("path", &self.path)
+            .field("index", &self.index)
+            .field("params", &Params(&self.params))
+            .field("trailing_delimiter", &self.trailing_delimiter)
+            .field("fragment", &self.fragment)
+            .finish()
+    }
 }
 
 // impl MatchResult {
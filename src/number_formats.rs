@@ -0,0 +1,41 @@
+//! Formats of the numbers, for the `number_format` of [`CompilerOptions`](../struct.CompilerOptions.html)
+//!
+//! The integers are always written with all their digits, like `18446744073709551615`,
+//! the formats only differ on the floats.
+
+use crate::Key;
+
+/// Write the number like `serde_json`, without the `.0` of a whole float
+///
+/// It is the format used when `number_format` is not set, so `1.0` is rendered as `1`,
+/// while `1.5` and `1e20` are kept as they are.
+pub fn shortest(value: &serde_json::Number, _: &Key) -> String {
+    let text = value.to_string();
+    match text.strip_suffix(".0") {
+        Some(int) => int.to_owned(),
+        None => text,
+    }
+}
+
+/// Write a whole number as plain digits, for patterns like `\d+`
+///
+/// A whole float is written without its fraction or exponent, like `100000000000000000000`
+/// for `1e20`. A float with a fraction is written like [`shortest`](fn.shortest.html),
+/// so it fails to validate against an integer pattern.
+pub fn integer_lossless(value: &serde_json::Number, key: &Key) -> String {
+    let text = value.to_string();
+    let digits = text.strip_prefix('-').unwrap_or(&text);
+    if digits.bytes().all(|b| b.is_ascii_digit()) {
+        return text;
+    }
+    // The digits of an arbitrary precision number are kept as they are
+    if let Some((int, fraction)) = text.split_once('.') {
+        if !fraction.is_empty() && fraction.bytes().all(|b| b == b'0') {
+            return int.to_owned();
+        }
+    }
+    match value.as_f64() {
+        Some(float) if float.is_finite() && float.fract() == 0.0 => format!("{float:.0}"),
+        _ => shortest(value, key),
+    }
+}
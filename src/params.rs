@@ -0,0 +1,92 @@
+//! Typed parameters, usually implemented with `#[derive(PathParams)]` of `path2regex-macros`
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use crate::internal::DataValue;
+
+/// Extract a value from the params of a [`MatchResult`](struct.MatchResult.html)
+#[cfg(feature = "match")]
+#[cfg_attr(docsrs, doc(cfg(feature = "match")))]
+pub trait FromParams: Sized {
+    /// Extract the value from the params, failing on a missing or invalid parameter
+    fn from_params(params: &DataValue) -> Result<Self>;
+}
+
+/// Turn a value into the data of [`Compiler::render`](struct.Compiler.html#method.render)
+#[cfg(feature = "compile")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compile")))]
+pub trait ToParams {
+    /// Get the data of the value
+    fn to_data(&self) -> DataValue;
+}
+
+/// Parse a required parameter
+#[doc(hidden)]
+pub fn required<T, E>(
+    params: &DataValue,
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<T>
+where
+    E: std::fmt::Display,
+{
+    optional(params, name, parse)?.ok_or_else(|| anyhow!("Expected \"{name}\" in the params"))
+}
+
+/// Parse an optional parameter, missing when it is absent or empty
+#[doc(hidden)]
+pub fn optional<T, E>(
+    params: &DataValue,
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<Option<T>>
+where
+    E: std::fmt::Display,
+{
+    match params.get(name) {
+        None | Some(DataValue::Null) => Ok(None),
+        Some(DataValue::String(value)) if value.is_empty() => Ok(None),
+        Some(DataValue::String(value)) => parse_value(name, value, &parse).map(Some),
+        Some(_) => Err(anyhow!("Expected \"{name}\" to be a string")),
+    }
+}
+
+/// Parse a repeated parameter, empty when it is absent
+#[doc(hidden)]
+pub fn repeated<T, E>(
+    params: &DataValue,
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<Vec<T>>
+where
+    E: std::fmt::Display,
+{
+    match params.get(name) {
+        None | Some(DataValue::Null) => Ok(vec![]),
+        Some(DataValue::String(value)) => parse_value(name, value, &parse).map(|value| vec![value]),
+        Some(DataValue::Array(values)) => values
+            .iter()
+            .map(|value| match value {
+                DataValue::String(value) => parse_value(name, value, &parse),
+                _ => Err(anyhow!("Expected \"{name}\" to contain only strings")),
+            })
+            .collect(),
+        Some(_) => Err(anyhow!("Expected \"{name}\" to be an array")),
+    }
+}
+
+/// Parse a parameter with `FromStr`
+#[doc(hidden)]
+#[inline]
+pub fn from_str<T: FromStr>(value: &str) -> Result<T, T::Err> {
+    value.parse()
+}
+
+fn parse_value<T, E>(name: &str, value: &str, parse: impl Fn(&str) -> Result<T, E>) -> Result<T>
+where
+    E: std::fmt::Display,
+{
+    parse(value).map_err(|err| anyhow!("Failed to parse \"{name}\" from \"{value}\": {err}"))
+}
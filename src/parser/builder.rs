@@ -2,22 +2,51 @@
 
 #[cfg(feature = "compile")]
 use crate::CompilerOptions;
-use crate::{Parser, PathRegexOptions, DEFAULT_DELIMITER};
+use crate::{Delimiters, Parser, PathRegexOptions, DEFAULT_DELIMITER};
 
 /// The Configuration of the [`Parser`](struct.Parser.html)
 #[derive(Clone)]
 pub struct ParserOptions {
-    /// Set the default delimiter for repeat parameters. (default: `'/'`)
-    pub delimiter: String,
+    /// The delimiters of a path segment, the first one separates repeated values. (default: `"/#?"`)
+    pub delimiter: Delimiters,
     /// List of characters to automatically consider prefixes when parsing.
     pub prefixes: String,
+    /// Characters that start a path segment besides the delimiter, like `:` in `urn:isbn:12345`.
+    /// They are literal text in the path and are considered prefixes. (default: `None`)
+    pub start_delimiter: Option<String>,
+    /// When `true` a `?` right before a parameter name starts the parameter like `:`,
+    /// so `/users?page` and `/users?:page` both have a `page` parameter. (default: `false`)
+    ///
+    /// The `?` is then not a modifier: write `{:id}?{?page}` to follow an optional parameter.
+    pub query_params: bool,
 }
 
 impl Default for ParserOptions {
     fn default() -> Self {
         Self {
-            delimiter: DEFAULT_DELIMITER.to_owned(),
+            delimiter: DEFAULT_DELIMITER.into(),
             prefixes: "./".to_owned(),
+            start_delimiter: None,
+            query_params: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Create the options with a single character delimiter and prefix
+    pub fn from_chars(delimiter: char, prefix: char) -> Self {
+        Self {
+            delimiter: delimiter.into(),
+            prefixes: prefix.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Create the default options without prefixes, so no character before a parameter is its prefix
+    pub fn with_no_prefixes() -> Self {
+        Self {
+            prefixes: String::new(),
+            ..Default::default()
         }
     }
 }
@@ -27,13 +56,26 @@ impl std::fmt::Debug for ParserOptions {
         f.debug_struct("ParserOptions")
             .field("delimiter", &self.delimiter)
             .field("prefixes", &self.prefixes)
+            .field("start_delimiter", &self.start_delimiter)
+            .field("query_params", &self.query_params)
             .finish()
     }
 }
 
 impl std::fmt::Display for ParserOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
+        write!(
+            f,
+            "delimiter={:?}, prefixes={:?}",
+            self.delimiter, self.prefixes
+        )?;
+        if let Some(start_delimiter) = &self.start_delimiter {
+            write!(f, ", start_delimiter={start_delimiter:?}")?;
+        }
+        if self.query_params {
+            write!(f, ", query_params=true")?;
+        }
+        Ok(())
     }
 }
 
@@ -43,11 +85,15 @@ impl From<PathRegexOptions> for ParserOptions {
         let PathRegexOptions {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
             ..
         } = options;
         Self {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
         }
     }
 }
@@ -59,11 +105,15 @@ impl From<CompilerOptions> for ParserOptions {
         let CompilerOptions {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
             ..
         } = options;
         Self {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
         }
     }
 }
@@ -83,12 +133,9 @@ impl ParserBuilder {
         Parser(self.0.clone())
     }
 
-    /// Set the default delimiter for repeat parameters. (default: `'/'`)
-    pub fn set_delimiter<S>(&mut self, delimiter: S) -> &mut Self
-    where
-        S: AsRef<str>,
-    {
-        self.0.delimiter = delimiter.as_ref().to_owned();
+    /// The delimiters of a path segment, the first one separates repeated values. (default: `"/#?"`)
+    pub fn set_delimiter(&mut self, delimiter: impl Into<Delimiters>) -> &mut Self {
+        self.0.delimiter = delimiter.into();
         self
     }
 
@@ -100,6 +147,22 @@ impl ParserBuilder {
         self.0.prefixes = prefixes.as_ref().to_owned();
         self
     }
+
+    /// Characters that start a path segment besides the delimiter, like `:` in `urn:isbn:12345`.
+    /// They are literal text in the path and are considered prefixes. (default: `None`)
+    pub fn set_start_delimiter<S>(&mut self, start_delimiter: S) -> &mut Self
+    where
+        S: AsRef<str>,
+    {
+        self.0.start_delimiter = Some(start_delimiter.as_ref().to_owned());
+        self
+    }
+
+    /// When `true` a `?` right before a parameter name starts the parameter like `:`.
+    pub fn set_query_params(&mut self, yes: bool) -> &mut Self {
+        self.0.query_params = yes;
+        self
+    }
 }
 
 impl Default for ParserBuilder {
@@ -1,17 +1,19 @@
 //! Path parser
 mod builder;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use std::cell::Cell;
 
 use crate::{
     ast::{LexToken, LexTokenKind},
-    internal::escape_string,
     Key, Token, TryIntoWith,
 };
 
 pub use builder::{ParserBuilder, ParserOptions};
 
+#[cfg(feature = "compile")]
+use crate::{Compiler, CompilerBuilder, CompilerOptions};
+
 /// Path parser
 #[derive(Debug, Clone)]
 pub struct Parser(pub(crate) ParserOptions);
@@ -33,6 +35,143 @@ impl Parser {
     pub fn parse_str(&self, input: impl AsRef<str>) -> Result<Vec<Token>> {
         input.as_ref().try_into_with(&self.0)
     }
+
+    /// Parse the path with the parser options of `options` and build a
+    /// [`Compiler`](struct.Compiler.html) from it
+    #[cfg(feature = "compile")]
+    #[inline]
+    pub fn parse_to_compiler(path: &str, options: CompilerOptions) -> Result<Compiler> {
+        CompilerBuilder::new_with_options(path, options).build()
+    }
+
+    /// Parse the path to the lexical, reading the characters of the syntax errors as text
+    ///
+    /// It gives the best-effort tokens and a warning for every character read as text,
+    /// like the `:` of `/a/:` or the `{` of `/a{b`, in the order of the input.
+    pub fn parse_with_recovery(&self, input: impl AsRef<str>) -> (Vec<Token>, Vec<ParseWarning>) {
+        let chars = input.as_ref().chars().collect::<Vec<_>>();
+        let mut escaped = vec![false; chars.len()];
+        let mut warnings: Vec<ParseWarning> = vec![];
+        loop {
+            // The input with the escaped characters, and the index of each character in `chars`
+            let mut text = String::new();
+            let mut origins = vec![];
+            for (i, &c) in chars.iter().enumerate() {
+                if escaped[i] {
+                    text.push('\\');
+                    origins.push(i);
+                }
+                text.push(c);
+                origins.push(i);
+            }
+
+            let err = match parse_str_with_options(&text, &self.0) {
+                Ok(tokens) => {
+                    warnings.sort_by_key(|warning| warning.offset);
+                    return (tokens, warnings);
+                }
+                Err(err) => err,
+            };
+            let err = match err.downcast_ref::<SyntaxError>() {
+                Some(err) => err,
+                None => return (vec![], warnings),
+            };
+            // An unexpected end is an unclosed group, read its `{` as text
+            let offset = match origins.get(err.index) {
+                Some(&offset) => Some(offset),
+                None => (0..chars.len())
+                    .rev()
+                    .find(|&i| chars[i] == '{' && !escaped[i] && !is_escaped(&chars, i)),
+            };
+            match offset.filter(|&offset| !escaped[offset]) {
+                Some(offset) => {
+                    escaped[offset] = true;
+                    // The message points into the escaped text, point it into the input
+                    let message = err.message.replacen(
+                        &format!(" at {}", err.index),
+                        &format!(" at {offset}"),
+                        1,
+                    );
+                    warnings.push(ParseWarning {
+                        offset,
+                        kind: err.kind,
+                        message,
+                    });
+                }
+                None => return (vec![], warnings),
+            }
+        }
+    }
+}
+
+/// Whether the character is escaped by a backslash of the input
+fn is_escaped(chars: &[char], i: usize) -> bool {
+    chars[..i].iter().rev().take_while(|&&c| c == '\\').count() % 2 == 1
+}
+
+/// A syntax error read as text by [`parse_with_recovery`](struct.Parser.html#method.parse_with_recovery)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// The index of the character read as text, in characters
+    pub offset: usize,
+    /// The kind of the syntax error
+    pub kind: ParseWarningKind,
+    /// The message of the syntax error
+    pub message: String,
+}
+
+/// The kind of a [`ParseWarning`](struct.ParseWarning.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseWarningKind {
+    /// A `:` without a parameter name
+    MissingParameterName,
+    /// A pattern starting with `?`
+    PatternStartsWithModifier,
+    /// A capturing group in a pattern
+    CapturingGroup,
+    /// A pattern without its closing `)`
+    UnbalancedPattern,
+    /// An empty pattern `()`
+    MissingPattern,
+    /// A `\` at the end of the path
+    MissingEscapedChar,
+    /// A token out of place, like a `}` without its `{`
+    UnexpectedToken,
+    /// A default value on a parameter that isn't optional, like `:page=1`, escape the `=`
+    /// for static text like `:page\=1`
+    DefaultNotAllowed,
+    /// A `=` without a default value
+    MissingDefault,
+    /// A modifier after the compact `{:name?}`
+    DuplicateModifier,
+    /// A quoted parameter name without its closing `"`, like `:"user.id`
+    UnterminatedQuote,
+}
+
+/// A syntax error of a path, at an index in characters
+#[derive(Debug)]
+struct SyntaxError {
+    index: usize,
+    kind: ParseWarningKind,
+    message: String,
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+#[inline]
+fn syntax_error(index: usize, kind: ParseWarningKind, message: String) -> anyhow::Error {
+    SyntaxError {
+        index,
+        kind,
+        message,
+    }
+    .into()
 }
 
 impl Default for Parser {
@@ -44,27 +183,76 @@ impl Default for Parser {
 
 /// lex word parser
 #[inline]
-fn lexer(input: &str) -> Result<Vec<LexToken<'_>>> {
+fn lexer<'a>(
+    input: &'a str,
+    start_delimiter: &str,
+    query_params: bool,
+) -> Result<Vec<LexToken<'a>>> {
     use LexTokenKind::*;
+    use ParseWarningKind::*;
 
     let mut tokens = vec![];
     let mut i = 0;
     let char_vec: Vec<_> = input.chars().collect();
+    // The indexes are in characters, the text between two of them is sliced at their byte offsets
+    let offsets = input
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(input.len()))
+        .collect::<Vec<_>>();
+    let text = |start: usize, end: usize| -> &'a str { &input[offsets[start]..offsets[end]] };
+    let starts_name = |i: usize| {
+        matches!(
+            char_vec.get(i),
+            Some('0'..='9' | 'A'..='Z' | 'a'..='z' | '_' | '"')
+        )
+    };
     while i < char_vec.len() {
         match char_vec[i] {
+            // A start delimiter is literal text, unless `:` starts a parameter name
+            c if start_delimiter.contains(c) && (c != ':' || !starts_name(i + 1)) => {
+                tokens.push(LexToken {
+                    kind: Char,
+                    index: i,
+                    value: text(i, i + 1),
+                });
+                i += 1;
+            }
+            // With `query_params`, the `?` of `?:page` is dropped, and `?page` is read like `:page`
+            '?' if query_params && char_vec.get(i + 1) == Some(&':') && starts_name(i + 2) => {
+                i += 1;
+            }
+            '?' if query_params && starts_name(i + 1) && char_vec[i + 1] != '"' => {
+                let j = (i + 1..char_vec.len())
+                    .find(|&j| !matches!(char_vec[j], '0'..='9' | 'A'..='Z' | 'a'..='z' | '_'))
+                    .unwrap_or(char_vec.len());
+                tokens.push(LexToken {
+                    kind: Name,
+                    index: i,
+                    value: text(i + 1, j),
+                });
+                i = j;
+            }
             '*' | '+' | '?' => {
                 tokens.push(LexToken {
                     kind: Modifier,
                     index: i,
-                    value: &input[i..i + 1],
+                    value: text(i, i + 1),
                 });
                 i += 1;
             }
             '\\' => {
+                if i + 1 >= char_vec.len() {
+                    return Err(syntax_error(
+                        i,
+                        MissingEscapedChar,
+                        format!("Missing escaped character at {i}"),
+                    ));
+                }
                 tokens.push(LexToken {
                     kind: EscapedChar,
                     index: i,
-                    value: &input[i + 1..i + 2],
+                    value: text(i + 1, i + 2),
                 });
                 i += 2;
             }
@@ -72,7 +260,7 @@ fn lexer(input: &str) -> Result<Vec<LexToken<'_>>> {
                 tokens.push(LexToken {
                     kind: Open,
                     index: i,
-                    value: &input[i..i + 1],
+                    value: text(i, i + 1),
                 });
                 i += 1;
             }
@@ -80,13 +268,40 @@ fn lexer(input: &str) -> Result<Vec<LexToken<'_>>> {
                 tokens.push(LexToken {
                     kind: Close,
                     index: i,
-                    value: &input[i..i + 1],
+                    value: text(i, i + 1),
                 });
                 i += 1;
             }
+            // A quoted name can hold any character but `"`, like `:"user.id"`
+            ':' if char_vec.get(i + 1) == Some(&'"') => {
+                let j = match char_vec[i + 2..].iter().position(|&c| c == '"') {
+                    Some(len) => i + 2 + len,
+                    None => {
+                        return Err(syntax_error(
+                            i,
+                            UnterminatedQuote,
+                            format!("Unterminated quote at {i}"),
+                        ))
+                    }
+                };
+                let name = text(i + 2, j);
+                if name.is_empty() {
+                    return Err(syntax_error(
+                        i,
+                        MissingParameterName,
+                        format!("Missing parameter name at {i}"),
+                    ));
+                }
+                tokens.push(LexToken {
+                    kind: Name,
+                    index: i,
+                    value: name,
+                });
+                i = j + 1;
+            }
             ':' => {
                 let mut j = i + 1;
-                while j < input.len() {
+                while j < char_vec.len() {
                     match char_vec[j] {
                         '0'..='9' | 'A'..='Z' | 'a'..='z' | '_' => {
                             j += 1;
@@ -96,10 +311,14 @@ fn lexer(input: &str) -> Result<Vec<LexToken<'_>>> {
                     }
                 }
 
-                let name = &input[i + 1..j];
+                let name = text(i + 1, j);
 
                 if name.is_empty() {
-                    return Err(anyhow!("Missing parameter name at {i}"));
+                    return Err(syntax_error(
+                        i,
+                        MissingParameterName,
+                        format!("Missing parameter name at {i}"),
+                    ));
                 }
                 tokens.push(LexToken {
                     kind: Name,
@@ -113,14 +332,19 @@ fn lexer(input: &str) -> Result<Vec<LexToken<'_>>> {
                 let mut pattern = "";
                 let mut j = i + 1;
 
-                if char_vec[j] == '?' {
-                    return Err(anyhow!("Pattern cannot start with \"?\" at {j}"));
+                if char_vec.get(j) == Some(&'?') {
+                    return Err(syntax_error(
+                        j,
+                        PatternStartsWithModifier,
+                        format!("Pattern cannot start with \"?\" at {j}"),
+                    ));
                 }
 
-                while j < input.len() {
+                while j < char_vec.len() {
                     match char_vec[j] {
                         '\\' => {
                             j += 2;
+                            pattern = text(i + 1, j.min(char_vec.len()));
                             continue;
                         }
                         ')' => {
@@ -134,21 +358,33 @@ fn lexer(input: &str) -> Result<Vec<LexToken<'_>>> {
                             count += 1;
                             let it = char_vec.get(j + 1);
                             if it.is_none() || matches!(it, Some(&x) if x != '?') {
-                                return Err(anyhow!("Capturing groups are not allowed at {j}"));
+                                return Err(syntax_error(
+                                    j,
+                                    CapturingGroup,
+                                    format!("Capturing groups are not allowed at {j}"),
+                                ));
                             }
                         }
                         _ => {}
                     };
 
-                    pattern = &input[i + 1..j + 1];
+                    pattern = text(i + 1, j + 1);
                     j += 1;
                 }
                 if count > 0 {
-                    return Err(anyhow!("Unbalanced pattern at {i}"));
+                    return Err(syntax_error(
+                        i,
+                        UnbalancedPattern,
+                        format!("Unbalanced pattern at {i}"),
+                    ));
                 }
 
                 if pattern.is_empty() {
-                    return Err(anyhow!("Missing pattern at {i}"));
+                    return Err(syntax_error(
+                        i,
+                        MissingPattern,
+                        format!("Missing pattern at {i}"),
+                    ));
                 }
 
                 tokens.push(LexToken {
@@ -162,7 +398,7 @@ fn lexer(input: &str) -> Result<Vec<LexToken<'_>>> {
                 tokens.push(LexToken {
                     kind: Char,
                     index: i,
-                    value: &input[i..i + 1],
+                    value: text(i, i + 1),
                 });
                 i += 1;
             }
@@ -187,13 +423,17 @@ pub(crate) fn parse_str_with_options(
     let ParserOptions {
         delimiter,
         prefixes,
+        start_delimiter,
+        query_params,
     } = options;
 
     use LexTokenKind::*;
+    use ParseWarningKind::*;
     let input = input.as_ref();
-    let tokens = lexer(input)?;
+    let start_delimiter = start_delimiter.as_deref().unwrap_or_default();
+    let tokens = lexer(input, start_delimiter, *query_params)?;
     let mut result = vec![];
-    let default_pattern = format!("[^{}]+?", escape_string(delimiter));
+    let default_pattern = format!("{}+?", delimiter.to_negated_class());
 
     let mut key: usize = 0;
     let i: Cell<usize> = Cell::new(0);
@@ -214,7 +454,11 @@ pub(crate) fn parse_str_with_options(
             Some(v) => Ok(v),
             None => {
                 let LexToken { kind, index, .. } = &tokens[i.get()];
-                Err(anyhow!("Unexpected {kind} at {index}, expected {ty}"))
+                Err(syntax_error(
+                    *index,
+                    UnexpectedToken,
+                    format!("Unexpected {kind} at {index}, expected {ty}"),
+                ))
             }
         }
     };
@@ -227,6 +471,38 @@ pub(crate) fn parse_str_with_options(
         result
     };
 
+    // The default value after the modifier, up to the next delimiter, like `=1` in `:page?=1`
+    let consume_default = |name: &str, modifier: &str| match tokens.get(i.get()) {
+        Some(t) if t.kind == Char && t.value == "=" => {
+            let index = t.index;
+            if !matches!(modifier, "?" | "*") {
+                return Err(syntax_error(
+                    index,
+                    DefaultNotAllowed,
+                    format!("Default value of \"{name}\" at {index} is only allowed on an optional parameter"),
+                ));
+            }
+            i.set(i.get() + 1);
+            let mut value = String::new();
+            while let Some(t) = tokens.get(i.get()).filter(|t| {
+                t.kind == EscapedChar
+                    || t.kind == Char && !t.value.chars().any(|c| delimiter.contains(c))
+            }) {
+                value += t.value;
+                i.set(i.get() + 1);
+            }
+            if value.is_empty() {
+                return Err(syntax_error(
+                    index,
+                    MissingDefault,
+                    format!("Missing default value of \"{name}\" at {index}"),
+                ));
+            }
+            Ok(Some(value))
+        }
+        _ => Ok(None),
+    };
+
     while i.get() < tokens.len() {
         let char = try_consume(Char);
         let name = try_consume(Name);
@@ -235,7 +511,7 @@ pub(crate) fn parse_str_with_options(
         if name.or(pattern).is_some() {
             let mut prefix = char.unwrap_or_default();
 
-            if !prefixes.contains(prefix) {
+            if !prefixes.contains(prefix) && !start_delimiter.contains(prefix) {
                 path += prefix;
                 prefix = ""
             }
@@ -245,19 +521,23 @@ pub(crate) fn parse_str_with_options(
                 path = String::new();
             }
 
+            let name = name.map_or_else(
+                || {
+                    let k = key;
+                    key += 1;
+                    k.to_string()
+                },
+                |x| x.to_owned(),
+            );
+            let modifier = try_consume(Modifier).unwrap_or_default();
+            let default = consume_default(&name, modifier)?;
             result.push(Token::Key(Key {
-                name: name.map_or_else(
-                    || {
-                        let k = key;
-                        key += 1;
-                        k.to_string()
-                    },
-                    |x| x.to_owned(),
-                ),
+                name,
                 prefix: prefix.to_owned(),
                 suffix: String::new(),
                 pattern: pattern.map_or_else(|| default_pattern.clone(), |x| x.to_owned()),
-                modifier: try_consume(Modifier).unwrap_or_default().to_owned(),
+                modifier: modifier.to_owned(),
+                default,
             }));
             continue;
         }
@@ -276,31 +556,61 @@ pub(crate) fn parse_str_with_options(
             let prefix = consume_text();
             let name = try_consume(Name);
             let pattern = try_consume(Pattern);
+            // The compact optional parameter `{:name?}` is the same as `{:name}?`
+            let inner_modifier = match (tokens.get(i.get()), tokens.get(i.get() + 1)) {
+                (Some(modifier), Some(close))
+                    if prefix.is_empty()
+                        && name.or(pattern).is_some()
+                        && modifier.kind == Modifier
+                        && modifier.value == "?"
+                        && close.kind == Close =>
+                {
+                    i.set(i.get() + 1);
+                    Some(modifier)
+                }
+                _ => None,
+            };
             let suffix = consume_text();
 
             must_consume(Close)?;
 
-            result.push(Token::Key(Key {
-                name: name.map_or_else(
-                    || {
-                        if pattern.is_some() {
-                            let k = key;
-                            key += 1;
-                            k.to_string()
-                        } else {
-                            Default::default()
-                        }
-                    },
-                    |x| x.to_owned(),
-                ),
-                pattern: if name.is_some() && pattern.is_none() {
-                    default_pattern.clone()
-                } else {
-                    pattern.unwrap_or_default().to_owned()
+            let pattern = if name.is_some() && pattern.is_none() {
+                default_pattern.clone()
+            } else {
+                pattern.unwrap_or_default().to_owned()
+            };
+            let name = name.map_or_else(
+                || {
+                    if pattern.is_empty() {
+                        Default::default()
+                    } else {
+                        let k = key;
+                        key += 1;
+                        k.to_string()
+                    }
                 },
+                |x| x.to_owned(),
+            );
+            let modifier = match (inner_modifier, try_consume(Modifier)) {
+                (Some(_), Some(_)) => {
+                    let LexToken { index, .. } = &tokens[i.get() - 1];
+                    return Err(syntax_error(
+                        *index,
+                        DuplicateModifier,
+                        format!("Duplicate modifier of \"{name}\" at {index}"),
+                    ));
+                }
+                (Some(inner), None) => inner.value,
+                (None, modifier) => modifier.unwrap_or_default(),
+            };
+            let default = consume_default(&name, modifier)?;
+            result.push(Token::Key(Key {
+                name,
+                pattern,
                 prefix,
                 suffix,
-                modifier: try_consume(Modifier).unwrap_or_default().to_owned(),
+                modifier: modifier.to_owned(),
+                default,
             }));
 
             continue;
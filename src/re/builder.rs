@@ -1,22 +1,35 @@
 //! The Builder of the [`PathRegex`](struct.PathRegex.html)
 
-use anyhow::Result;
+use std::{
+    collections::HashMap,
+    env::{self, VarError},
+};
+
+use anyhow::{anyhow, Result};
 
 use crate::{
-    internal::{type_of, FnStr},
-    ParserOptions, PathRegex, TryIntoWith,
+    internal::{repeat_separator_of, type_of, FnStr},
+    Delimiters, Key, ParserOptions, PathRegex, TryIntoWith,
 };
 
+#[cfg(feature = "compile")]
+use crate::CompilerOptions;
 #[cfg(feature = "match")]
 use crate::MatcherOptions;
 
 /// The Configuration of the [`PathRegex`](struct.PathRegex.html)
 #[derive(Clone)]
 pub struct PathRegexOptions {
-    /// Set the default delimiter for repeat parameters. (default: `'/#?'`)
-    pub delimiter: String,
+    /// The delimiters of a path segment, the first one separates repeated values. (default: `"/#?"`)
+    pub delimiter: Delimiters,
     /// List of characters to automatically consider prefixes when parsing.
     pub prefixes: String,
+    /// Characters that start a path segment besides the delimiter, like `:` in `urn:isbn:12345`.
+    /// They are literal text in the path and are considered prefixes. (default: `None`)
+    pub start_delimiter: Option<String>,
+    /// When `true` a `?` right before a parameter name starts the parameter like `:`,
+    /// like `/users?page`. (default: `false`)
+    pub query_params: bool,
     /// When `true` the regexp will be case sensitive. (default: `false`)
     pub sensitive: bool,
     /// When `true` the regexp won't allow an optional trailing delimiter to match. (default: `false`)
@@ -27,8 +40,19 @@ pub struct PathRegexOptions {
     pub start: bool,
     /// List of characters that can also be "end" characters.
     pub ends_with: String,
-    /// Encode path tokens for use in the `Regex`.
-    pub encode: FnStr,
+    /// When `true` the parameters can't match a delimiter, except the repeated ones. (default: `false`)
+    ///
+    /// The delimiters are removed from every literal and class of the patterns.
+    pub confine_to_segment: bool,
+    /// Encode the static text of the path for use in the `Regex`.
+    pub encode_static: FnStr,
+    /// Encode the prefixes and suffixes of the keys for use in the `Regex`.
+    pub encode_affix: FnStr,
+    /// Separate the values of the repeated parameters with this separator, like `,` in `/tags/a,b,c`,
+    /// instead of their suffix and prefix. (default: `None`)
+    pub repeat_separator: Option<String>,
+    /// The separator of a repeated parameter by name, overriding `repeat_separator`. (default: empty)
+    pub repeat_separators: HashMap<String, String>,
 }
 
 impl Default for PathRegexOptions {
@@ -36,16 +60,125 @@ impl Default for PathRegexOptions {
         let ParserOptions {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
         } = ParserOptions::default();
         Self {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
             sensitive: false,
             strict: false,
             end: true,
             start: true,
             ends_with: "".to_owned(),
-            encode: |x| x.to_owned(),
+            confine_to_segment: false,
+            encode_static: |x| x.to_owned(),
+            encode_affix: |x| x.to_owned(),
+            repeat_separator: None,
+            repeat_separators: HashMap::new(),
+        }
+    }
+}
+
+impl PathRegexOptions {
+    /// The separator of the values of a repeated key, `None` to use its suffix and prefix
+    pub(crate) fn repeat_separator_of(&self, key: &Key) -> Option<&str> {
+        repeat_separator_of(&self.repeat_separators, &self.repeat_separator, key)
+    }
+
+    /// The default options overridden by the environment variables
+    ///
+    /// | Variable | Option |
+    /// | --- | --- |
+    /// | `PATH_REGEX_DELIMITER` | `delimiter` |
+    /// | `PATH_REGEX_PREFIXES` | `prefixes` |
+    /// | `PATH_REGEX_START_DELIMITER` | `start_delimiter` |
+    /// | `PATH_REGEX_SENSITIVE` | `sensitive` |
+    /// | `PATH_REGEX_STRICT` | `strict` |
+    /// | `PATH_REGEX_END` | `end` |
+    /// | `PATH_REGEX_START` | `start` |
+    /// | `PATH_REGEX_ENDS_WITH` | `ends_with` |
+    /// | `PATH_REGEX_CONFINE_TO_SEGMENT` | `confine_to_segment` |
+    ///
+    /// The flags take `true` or `false`.
+    pub fn from_env() -> Result<Self> {
+        let mut options = Self::default();
+        if let Some(delimiter) = env_var("PATH_REGEX_DELIMITER")? {
+            options.delimiter = delimiter.into();
+        }
+        if let Some(prefixes) = env_var("PATH_REGEX_PREFIXES")? {
+            options.prefixes = prefixes;
+        }
+        if let Some(start_delimiter) = env_var("PATH_REGEX_START_DELIMITER")? {
+            options.start_delimiter = Some(start_delimiter);
+        }
+        if let Some(ends_with) = env_var("PATH_REGEX_ENDS_WITH")? {
+            options.ends_with = ends_with;
+        }
+        for (name, flag) in [
+            ("PATH_REGEX_SENSITIVE", &mut options.sensitive),
+            ("PATH_REGEX_STRICT", &mut options.strict),
+            ("PATH_REGEX_END", &mut options.end),
+            ("PATH_REGEX_START", &mut options.start),
+            (
+                "PATH_REGEX_CONFINE_TO_SEGMENT",
+                &mut options.confine_to_segment,
+            ),
+        ] {
+            if let Some(value) = env_var(name)? {
+                *flag = value.parse().map_err(|_| {
+                    anyhow!("Expected \"{name}\" to be \"true\" or \"false\", but got \"{value}\"")
+                })?;
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Get the value of an environment variable, when it is set
+fn env_var(name: &str) -> Result<Option<String>> {
+    match env::var(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(VarError::NotUnicode(_)) => Err(anyhow!("Expected \"{name}\" to be valid unicode")),
+    }
+}
+
+#[cfg(feature = "compile")]
+impl PathRegexOptions {
+    /// Take the options of the parser, `sensitive` and the repeat separators of the
+    /// [`Compiler`](struct.Compiler.html), to match the paths it renders
+    #[inline]
+    pub fn from_compiler_options(options: CompilerOptions) -> Self {
+        Self::from(options)
+    }
+}
+
+#[cfg(feature = "compile")]
+impl From<CompilerOptions> for PathRegexOptions {
+    #[inline]
+    fn from(options: CompilerOptions) -> Self {
+        let CompilerOptions {
+            delimiter,
+            prefixes,
+            start_delimiter,
+            query_params,
+            sensitive,
+            repeat_separator,
+            repeat_separators,
+            ..
+        } = options;
+        Self {
+            delimiter,
+            prefixes,
+            start_delimiter,
+            query_params,
+            sensitive,
+            repeat_separator,
+            repeat_separators,
+            ..Default::default()
         }
     }
 }
@@ -57,30 +190,72 @@ impl From<MatcherOptions> for PathRegexOptions {
         let MatcherOptions {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
             sensitive,
             strict,
             end,
             start,
             ends_with,
-            encode,
+            confine_to_segment,
+            encode_static,
+            encode_affix,
+            repeat_separator,
+            repeat_separators,
             ..
         } = options;
         Self {
             delimiter,
             prefixes,
+            start_delimiter,
+            query_params,
             sensitive,
             strict,
             end,
             start,
             ends_with,
-            encode,
+            confine_to_segment,
+            encode_static,
+            encode_affix,
+            repeat_separator,
+            repeat_separators,
         }
     }
 }
 
 impl std::fmt::Display for PathRegexOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
+        write!(
+            f,
+            "delimiter={:?}, prefixes={:?}, sensitive={}, strict={}, end={}, start={}, ends_with={:?}",
+            self.delimiter,
+            self.prefixes,
+            self.sensitive,
+            self.strict,
+            self.end,
+            self.start,
+            self.ends_with
+        )?;
+        if let Some(start_delimiter) = &self.start_delimiter {
+            write!(f, ", start_delimiter={start_delimiter:?}")?;
+        }
+        if self.query_params {
+            write!(f, ", query_params=true")?;
+        }
+        if self.confine_to_segment {
+            write!(f, ", confine_to_segment=true")?;
+        }
+        if let Some(repeat_separator) = &self.repeat_separator {
+            write!(f, ", repeat_separator={repeat_separator:?}")?;
+        }
+        if !self.repeat_separators.is_empty() {
+            let separators = self
+                .repeat_separators
+                .iter()
+                .collect::<std::collections::BTreeMap<_, _>>();
+            write!(f, ", repeat_separators={separators:?}")?;
+        }
+        Ok(())
     }
 }
 
@@ -89,20 +264,34 @@ impl std::fmt::Debug for PathRegexOptions {
         f.debug_struct("PathRegexOptions")
             .field("delimiter", &self.delimiter)
             .field("prefixes", &self.prefixes)
+            .field("start_delimiter", &self.start_delimiter)
+            .field("query_params", &self.query_params)
             .field("sensitive", &self.sensitive)
             .field("strict", &self.strict)
             .field("end", &self.end)
             .field("start", &self.start)
             .field("ends_with", &self.ends_with)
-            .field("encode", &type_of(self.encode))
+            .field("confine_to_segment", &self.confine_to_segment)
+            .field("encode_static", &type_of(self.encode_static))
+            .field("encode_affix", &type_of(self.encode_affix))
+            .field("repeat_separator", &self.repeat_separator)
+            .field(
+                "repeat_separators",
+                &self
+                    .repeat_separators
+                    .iter()
+                    .collect::<std::collections::BTreeMap<_, _>>(),
+            )
             .finish()
     }
 }
 
 /// The Builder of the [`PathRegex`](struct.PathRegex.html)
+#[derive(Clone)]
 pub struct PathRegexBuilder<S> {
     source: S,
     options: PathRegexOptions,
+    keys: Vec<Key>,
 }
 
 impl<S> PathRegexBuilder<S>
@@ -114,17 +303,55 @@ where
         Self {
             source,
             options: Default::default(),
+            keys: Vec::new(),
         }
     }
 
     /// Create a builder of the [`PathRegex`](struct.PathRegex.html) with the options
     pub fn new_with_options(source: S, options: PathRegexOptions) -> Self {
-        Self { source, options }
+        Self {
+            source,
+            options,
+            keys: Vec::new(),
+        }
     }
 
     /// build a builder of the [`PathRegex`](struct.PathRegex.html)
     pub fn build(&self) -> Result<PathRegex> {
-        self.source.clone().try_into_with(&self.options)
+        let mut re = self.source.clone().try_into_with(&self.options)?;
+        re.keys.extend(self.keys.iter().cloned());
+        Ok(re)
+    }
+
+    /// Add keys after the ones of the source, like the keys of the capture groups of a `Regex`
+    pub fn extend_keys(&mut self, keys: Vec<Key>) -> &mut Self {
+        self.keys.extend(keys);
+        self
+    }
+
+    /// Set the options of the parser, overwriting `delimiter`, `prefixes`, `start_delimiter` and `query_params`
+    pub fn set_parser_options(&mut self, options: ParserOptions) -> &mut Self {
+        let ParserOptions {
+            delimiter,
+            prefixes,
+            start_delimiter,
+            query_params,
+        } = options;
+        self.options.delimiter = delimiter;
+        self.options.prefixes = prefixes;
+        self.options.start_delimiter = start_delimiter;
+        self.options.query_params = query_params;
+        self
+    }
+
+    /// Get the options of the parser
+    pub fn parser_options(&self) -> ParserOptions {
+        ParserOptions {
+            delimiter: self.options.delimiter.clone(),
+            prefixes: self.options.prefixes.clone(),
+            start_delimiter: self.options.start_delimiter.clone(),
+            query_params: self.options.query_params,
+        }
     }
 
     /// List of characters to automatically consider prefixes when parsing.
@@ -133,6 +360,19 @@ where
         self
     }
 
+    /// Characters that start a path segment besides the delimiter, like `:` in `urn:isbn:12345`.
+    /// They are literal text in the path and are considered prefixes. (default: `None`)
+    pub fn set_start_delimiter(&mut self, start_delimiter: impl AsRef<str>) -> &mut Self {
+        self.options.start_delimiter = Some(start_delimiter.as_ref().to_owned());
+        self
+    }
+
+    /// When `true` a `?` right before a parameter name starts the parameter like `:`.
+    pub fn set_query_params(&mut self, yes: bool) -> &mut Self {
+        self.options.query_params = yes;
+        self
+    }
+
     /// When `true` the regexp will be case sensitive. (default: `false`)
     pub fn set_sensitive(&mut self, yes: bool) -> &mut Self {
         self.options.sensitive = yes;
@@ -157,9 +397,15 @@ where
         self
     }
 
-    /// Set the default delimiter for repeat parameters. (default: `'/#?'`)
-    pub fn set_delimiter(&mut self, de: impl AsRef<str>) -> &mut Self {
-        self.options.delimiter = de.as_ref().to_owned();
+    /// The delimiters of a path segment, the first one separates repeated values. (default: `"/#?"`)
+    pub fn set_delimiter(&mut self, delimiter: impl Into<Delimiters>) -> &mut Self {
+        self.options.delimiter = delimiter.into();
+        self
+    }
+
+    /// When `true` the parameters can't match a delimiter, except the repeated ones. (default: `false`)
+    pub fn set_confine_to_segment(&mut self, yes: bool) -> &mut Self {
+        self.options.confine_to_segment = yes;
         self
     }
 
@@ -169,9 +415,40 @@ where
         self
     }
 
-    /// Function for encoding input strings for output.
+    /// Function for encoding input strings for output, used for both the static text and the affixes.
     pub fn set_encode(&mut self, encode: FnStr) -> &mut Self {
-        self.options.encode = encode;
+        self.options.encode_static = encode;
+        self.options.encode_affix = encode;
+        self
+    }
+
+    /// Function for encoding the static text of the path.
+    pub fn set_encode_static(&mut self, encode: FnStr) -> &mut Self {
+        self.options.encode_static = encode;
+        self
+    }
+
+    /// Function for encoding the prefixes and suffixes of the keys.
+    pub fn set_encode_affix(&mut self, encode: FnStr) -> &mut Self {
+        self.options.encode_affix = encode;
+        self
+    }
+
+    /// The separator of the values of the repeated parameters, like `,` in `/tags/a,b,c`.
+    pub fn set_repeat_separator(&mut self, separator: impl Into<String>) -> &mut Self {
+        self.options.repeat_separator = Some(separator.into());
+        self
+    }
+
+    /// The separator of the values of a repeated parameter, overriding `repeat_separator`.
+    pub fn set_key_repeat_separator(
+        &mut self,
+        name: impl Into<String>,
+        separator: impl Into<String>,
+    ) -> &mut Self {
+        self.options
+            .repeat_separators
+            .insert(name.into(), separator.into());
         self
     }
 }
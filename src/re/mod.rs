@@ -1,15 +1,22 @@
 //! Path regex
 mod builder;
+pub(crate) mod preflight;
 
-use anyhow::Result;
+use std::{borrow::Cow, collections::HashMap};
+
+use anyhow::{anyhow, Result};
 
 use regex::{Regex, RegexBuilder};
+use regex_syntax::hir::{
+    Capture, Class, ClassBytes, ClassUnicode, Hir, HirKind, Literal, Repetition,
+};
 
 pub use builder::{PathRegexBuilder, PathRegexOptions};
+pub use preflight::UnsupportedPattern;
 
 use crate::{
-    internal::{escape_string, END_WITH_DELIMITER},
-    Key, Parser, ParserOptions, Token, TryIntoWith,
+    internal::{escape_string, is_internal_group, END_WITH_DELIMITER, TRAILING_DELIMITER},
+    Delimiters, Key, Parser, ParserOptions, Token, TryIntoWith,
 };
 
 /// Path regex
@@ -17,6 +24,8 @@ use crate::{
 pub struct PathRegex {
     pub(crate) re: Regex,
     pub(crate) keys: Vec<Key>,
+    /// The path and the options the regex was built from
+    pub(crate) source: Option<(String, PathRegexOptions)>,
 }
 
 impl PathRegex {
@@ -38,21 +47,208 @@ impl PathRegex {
         PathRegexBuilder::new_with_options(source, options).build()
     }
 
+    /// Create a [`PathRegex`](struct.PathRegex.html) matching only the leading portion of a path
+    ///
+    /// Like building with `set_end(false)`: `"/api/v1"` matches `/api/v1` and `/api/v1/users`,
+    /// the match ending at a delimiter or the end of the path, but not `/api/v10`.
+    #[inline]
+    pub fn new_prefix_only<S>(source: S) -> Result<Self>
+    where
+        S: TryIntoWith<PathRegex, PathRegexOptions>,
+    {
+        PathRegexBuilder::new(source).set_end(false).build()
+    }
+
+    /// Create a [`PathRegex`](struct.PathRegex.html) from a raw regex pattern, not a path
+    ///
+    /// Every capture group is mapped to a key, named after the group or numbered from `0`
+    /// when it has no name.
+    ///
+    /// It fails with an [`UnsupportedPattern`](struct.UnsupportedPattern.html) on a regex
+    /// feature the `regex` crate doesn't support, like a lookahead.
+    pub fn from_pattern(pattern: &str) -> Result<Self> {
+        preflight::preflight(pattern, None)?;
+        let re = Regex::new(pattern)?;
+        let mut index: usize = 0;
+        let keys = re
+            .capture_names()
+            .skip(1)
+            .map(|name| Key {
+                name: name.map_or_else(
+                    || {
+                        index += 1;
+                        format!("{}", index - 1)
+                    },
+                    str::to_owned,
+                ),
+                prefix: Default::default(),
+                suffix: Default::default(),
+                pattern: Default::default(),
+                modifier: Default::default(),
+                default: None,
+            })
+            .collect();
+        Ok(PathRegex {
+            re,
+            keys,
+            source: None,
+        })
+    }
+
+    /// Create a [`PathRegex`](struct.PathRegex.html) from the tokens of a path with the options
+    ///
+    /// It fails with an [`UnsupportedPattern`](struct.UnsupportedPattern.html) on a regex
+    /// feature the `regex` crate doesn't support in the pattern of a key, like a lookahead.
+    #[inline]
+    pub fn try_from_tokens_with_options(
+        tokens: Vec<Token>,
+        options: &PathRegexOptions,
+    ) -> Result<Self> {
+        Self::from_tokens(tokens, options, None)
+    }
+
+    fn from_tokens(
+        tokens: Vec<Token>,
+        options: &PathRegexOptions,
+        source: Option<&str>,
+    ) -> Result<Self> {
+        for token in tokens.iter() {
+            if let Token::Key(key) = token {
+                preflight::preflight(&key.pattern, source)?;
+            }
+        }
+        let mut keys = vec![];
+        let re = tokens_to_path_regex(tokens, &mut keys, options)?;
+        Ok(PathRegex {
+            re,
+            keys,
+            source: None,
+        })
+    }
+
     /// Get then parameter matches in the path
     pub fn keys(&self) -> &Vec<Key> {
         &self.keys
     }
+
+    /// Get the key of a capture group, the group `0` being the whole match
+    ///
+    /// The groups added for the delimiters at the end of a path have no key,
+    /// even with more keys given to [`extend_keys`](struct.PathRegexBuilder.html#method.extend_keys).
+    pub fn key_at(&self, group_index: usize) -> Option<&Key> {
+        if is_internal_group(self.re.capture_names().nth(group_index)?) {
+            return None;
+        }
+        group_index
+            .checked_sub(1)
+            .and_then(|index| self.keys.get(index))
+    }
+
+    /// Iterate over the successive matches in the path, with the values of their keys by name
+    ///
+    /// The keys missing from a match are left out, and the values borrow from the path.
+    /// The `captures_iter` of the `Regex` is still available with `as_ref`.
+    pub fn captures_iter<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> impl Iterator<Item = HashMap<&'a str, &'a str>> + 'a {
+        self.re.captures_iter(path).map(move |captures| {
+            captures
+                .iter()
+                .enumerate()
+                .skip(1)
+                .filter_map(|(i, value)| Some((self.key_at(i)?.name.as_str(), value?.as_str())))
+                .collect()
+        })
+    }
+
+    /// Get the path the regex was built from, if it was built from a path
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_ref().map(|(path, _)| path.as_str())
+    }
+
+    /// Get the pattern of the compiled regex, unlike [`source`](struct.PathRegex.html#method.source)
+    /// giving the path it was built from
+    #[inline]
+    pub fn pattern_string(&self) -> &str {
+        self.re.as_str()
+    }
 }
 
 impl std::fmt::Display for PathRegex {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&self, f)
+        f.write_str(self.re.as_str())
     }
 }
 
 impl std::fmt::Debug for PathRegex {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.re.as_str())
+        f.debug_struct("PathRegex")
+            .field("source", &self.source())
+            .field("pattern", &self.re.as_str())
+            .field("keys", &self.keys)
+            .finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde_core::Serialize for PathRegex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde_core::Serializer,
+    {
+        use serde_core::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("PathRegex", 2)?;
+        state.serialize_field("pattern", self.re.as_str())?;
+        state.serialize_field("keys", &self.keys)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde_core::Deserialize<'de> for PathRegex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde_core::Deserializer<'de>,
+    {
+        use serde_core::de::{Error, IgnoredAny, MapAccess, Visitor};
+
+        struct PathRegexVisitor;
+
+        impl<'de> Visitor<'de> for PathRegexVisitor {
+            type Value = PathRegex;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("struct PathRegex")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut pattern: Option<String> = None;
+                let mut keys = vec![];
+                while let Some(field) = map.next_key::<String>()? {
+                    match field.as_str() {
+                        "pattern" => pattern = Some(map.next_value()?),
+                        "keys" => keys = map.next_value()?,
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                let pattern = pattern.ok_or_else(|| A::Error::missing_field("pattern"))?;
+                let re = Regex::new(&pattern).map_err(A::Error::custom)?;
+                Ok(PathRegex {
+                    re,
+                    keys,
+                    source: None,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct("PathRegex", &["pattern", "keys"], PathRegexVisitor)
     }
 }
 
@@ -72,7 +268,29 @@ impl std::ops::Deref for PathRegex {
     }
 }
 
-///
+impl std::ops::Add<&str> for &PathRegex {
+    type Output = Result<PathRegex>;
+
+    /// Append a path to the path the regex was built from, with the same options
+    fn add(self, path: &str) -> Self::Output {
+        let (source, options) = self.source.as_ref().ok_or_else(|| {
+            anyhow!("Expected a PathRegex built from a path to append \"{path}\"")
+        })?;
+        PathRegex::new_with_options(format!("{source}{path}"), options.clone())
+    }
+}
+
+impl std::ops::Add<&str> for PathRegex {
+    type Output = Result<PathRegex>;
+
+    /// Append a path to the path the regex was built from, with the same options
+    #[inline]
+    fn add(self, path: &str) -> Self::Output {
+        &self + path
+    }
+}
+
+/// Collect the keys of the capture groups in a `Regex`
 #[inline]
 pub(crate) fn regex_to_path_regex(path: Regex, keys: &mut Vec<Key>) -> Result<Regex> {
     if keys.is_empty() {
@@ -96,13 +314,79 @@ pub(crate) fn regex_to_path_regex(path: Regex, keys: &mut Vec<Key>) -> Result<Re
             suffix: Default::default(),
             pattern: Default::default(),
             modifier: Default::default(),
+            default: None,
         });
     }
 
     Ok(path)
 }
 
+/// Remove the delimiters from the characters a pattern can match
 ///
+/// Every literal and class of the pattern is rewritten, so `.+` becomes `[^/#?\n]+`.
+/// A pattern the regex syntax can't parse is kept as is, for `Regex::new` to report the error.
+fn confine_pattern(pattern: &str, delimiter: &Delimiters) -> String {
+    use regex_syntax::hir::{ClassBytesRange, ClassUnicodeRange};
+
+    let chars = ClassUnicode::new(delimiter.chars().map(|c| ClassUnicodeRange::new(c, c)));
+    let bytes = ClassBytes::new(
+        delimiter
+            .chars()
+            .filter(char::is_ascii)
+            .map(|c| ClassBytesRange::new(c as u8, c as u8)),
+    );
+    match regex_syntax::Parser::new().parse(pattern) {
+        Ok(hir) => confine_hir(hir, delimiter, &chars, &bytes).to_string(),
+        Err(_) => pattern.to_owned(),
+    }
+}
+
+fn confine_hir(hir: Hir, delimiter: &Delimiters, chars: &ClassUnicode, bytes: &ClassBytes) -> Hir {
+    let confine = |hir: Box<Hir>| Box::new(confine_hir(*hir, delimiter, chars, bytes));
+    match hir.into_kind() {
+        HirKind::Literal(Literal(literal)) => {
+            let crosses = match std::str::from_utf8(&literal) {
+                Ok(literal) => literal.chars().any(|c| delimiter.contains(c)),
+                Err(_) => literal.iter().any(|&b| delimiter.contains(b as char)),
+            };
+            if crosses {
+                Hir::fail()
+            } else {
+                Hir::literal(literal)
+            }
+        }
+        HirKind::Class(Class::Unicode(mut class)) => {
+            class.difference(chars);
+            Hir::class(Class::Unicode(class))
+        }
+        HirKind::Class(Class::Bytes(mut class)) => {
+            class.difference(bytes);
+            Hir::class(Class::Bytes(class))
+        }
+        HirKind::Repetition(repetition) => Hir::repetition(Repetition {
+            sub: confine(repetition.sub),
+            ..repetition
+        }),
+        HirKind::Capture(capture) => Hir::capture(Capture {
+            sub: confine(capture.sub),
+            ..capture
+        }),
+        HirKind::Concat(subs) => Hir::concat(
+            subs.into_iter()
+                .map(|sub| confine_hir(sub, delimiter, chars, bytes))
+                .collect(),
+        ),
+        HirKind::Alternation(subs) => Hir::alternation(
+            subs.into_iter()
+                .map(|sub| confine_hir(sub, delimiter, chars, bytes))
+                .collect(),
+        ),
+        HirKind::Empty => Hir::empty(),
+        HirKind::Look(look) => Hir::look(look),
+    }
+}
+
+/// Build a `Regex` from the tokens of a path
 #[inline]
 fn tokens_to_path_regex(
     tokens: Vec<Token>,
@@ -116,21 +400,26 @@ fn tokens_to_path_regex(
         start,
         delimiter,
         ends_with,
-        encode,
+        encode_static,
+        encode_affix,
+        confine_to_segment,
         ..
     } = options;
-    let ends_with_re = (!ends_with.is_empty())
-        .then(|| format!("[{}]|$", escape_string(ends_with)))
-        .unwrap_or_else(|| "$".to_string());
-    let delimiter_re = (!delimiter.is_empty())
-        .then(|| format!("[{}]", escape_string(delimiter)))
-        .unwrap_or_default();
-    let route = if *start { "^" } else { "" };
-    let mut route = String::from(route);
+    let ends_with_re = if ends_with.is_empty() {
+        "$".to_string()
+    } else {
+        format!("[{}]|$", escape_string(ends_with))
+    };
+    let delimiter_re = delimiter.to_class();
+    // Keep the flag in the pattern so that the pattern string alone rebuilds the same regex
+    let mut route = String::from(if *sensitive { "" } else { "(?i)" });
+    if *start {
+        route += "^";
+    }
 
     for token in tokens.iter() {
         match token {
-            Token::Static(token) => route += &escape_string(&encode(token)),
+            Token::Static(token) => route += &escape_string(&encode_static(token)),
             Token::Key(token) => {
                 let Key {
                     prefix,
@@ -139,13 +428,28 @@ fn tokens_to_path_regex(
                     modifier,
                     ..
                 } = token;
-                let prefix = escape_string(&encode(prefix));
-                let suffix = escape_string(&encode(suffix));
+                let prefix = escape_string(&encode_affix(prefix));
+                let suffix = escape_string(&encode_affix(suffix));
 
                 if !pattern.is_empty() {
                     keys.push(token.clone());
+                    let pattern = if *confine_to_segment && !matches!(modifier.as_str(), "+" | "*")
+                    {
+                        Cow::Owned(confine_pattern(pattern, delimiter))
+                    } else {
+                        Cow::Borrowed(pattern.as_str())
+                    };
 
-                    if !prefix.is_empty() || !suffix.is_empty() {
+                    let separator = options
+                        .repeat_separator_of(token)
+                        .filter(|_| matches!(modifier.as_str(), "+" | "*"))
+                        .map(|separator| escape_string(&encode_affix(separator)));
+                    if let Some(separator) = separator {
+                        let mo = if modifier == "*" { "?" } else { "" };
+                        route += &format!(
+                            "(?:{prefix}((?:{pattern})(?:{separator}(?:{pattern}))*){suffix}){mo}"
+                        );
+                    } else if !prefix.is_empty() || !suffix.is_empty() {
                         let modifier = modifier.as_str();
                         if matches!(modifier, "+" | "*") {
                             let mo = if modifier == "*" { "?" } else { "" };
@@ -172,7 +476,7 @@ fn tokens_to_path_regex(
 
     if *end {
         if !strict {
-            route += &format!("{delimiter_re}?");
+            route += &format!("(?P<{TRAILING_DELIMITER}>{delimiter_re})?");
         }
         route += "$";
         if ends_with.is_empty() {
@@ -185,7 +489,7 @@ fn tokens_to_path_regex(
         let is_end_delimited = match end_token {
             Some(token) => match token {
                 Token::Static(end_token) if !end_token.is_empty() => {
-                    delimiter_re.contains(end_token.chars().last().unwrap())
+                    delimiter.contains(end_token.chars().last().unwrap())
                 }
                 _ => false,
             },
@@ -193,7 +497,7 @@ fn tokens_to_path_regex(
         };
 
         if !strict {
-            route += &format!("(?:{delimiter_re}{ends_with_re})?");
+            route += &format!("(?:(?P<{TRAILING_DELIMITER}>{delimiter_re}){ends_with_re})?");
         }
 
         if !is_end_delimited {
@@ -201,9 +505,7 @@ fn tokens_to_path_regex(
         }
     }
 
-    RegexBuilder::new(&route)
-        .case_insensitive(!sensitive)
-        .build()
+    Regex::new(&route)
 }
 
 #[inline]
@@ -211,11 +513,12 @@ pub(crate) fn string_to_path_regex<S>(path: S, options: &PathRegexOptions) -> Re
 where
     S: AsRef<str>,
 {
-    let mut keys = vec![];
+    let path = path.as_ref();
     let tokens = Parser::new_with_options(ParserOptions::from(options.clone())).parse_str(path)?;
 
-    let re = tokens_to_path_regex(tokens, &mut keys, options)?;
-    Ok(PathRegex { re, keys })
+    let mut re = PathRegex::from_tokens(tokens, options, Some(path))?;
+    re.source = Some((path.to_owned(), options.clone()));
+    Ok(re)
 }
 
 #[cfg(test)]
@@ -226,11 +529,14 @@ mod tests {
     #[test]
     fn test_compile_tokens_to_regexp() -> anyhow::Result<()> {
         let tokens = Parser::new().parse_str("/user/:id")?;
-        let re = tokens_to_path_regex(tokens, &mut vec![], &Default::default())?;
+        let mut keys = vec![];
+        let re = tokens_to_path_regex(tokens, &mut keys, &Default::default())?;
+        // The groups after the keys are internal, like the trailing delimiter
         let matches = re
             .captures("/user/123")
             .unwrap()
             .iter()
+            .take(keys.len() + 1)
             .map(|x| match x {
                 Some(x) => x.as_str(),
                 None => Default::default(),
@@ -0,0 +1,119 @@
+//! Detect the regex features the `regex` crate doesn't support, before building the `Regex`
+
+/// A regex feature of a pattern the `regex` crate doesn't support, like a lookahead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedPattern {
+    /// The unsupported construct, like `(?=`
+    pub construct: String,
+    /// The index of the construct in the pattern, in characters
+    pub position: usize,
+    /// The pattern containing the construct
+    pub pattern: String,
+    /// The path the pattern was found in, `None` for a raw regex pattern
+    pub source: Option<String>,
+    /// How to do without the construct
+    pub hint: &'static str,
+}
+
+impl std::fmt::Display for UnsupportedPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unsupported \"{}\" at {} in the pattern \"{}\"",
+            self.construct, self.position, self.pattern
+        )?;
+        if let Some(source) = &self.source {
+            write!(f, " of \"{source}\"")?;
+        }
+        write!(f, ": {}", self.hint)
+    }
+}
+
+impl std::error::Error for UnsupportedPattern {}
+
+/// Find the first construct of the pattern the `regex` crate doesn't support
+///
+/// Escaped characters and the content of classes are skipped, so `\(?=` is literal text.
+pub(crate) fn preflight(pattern: &str, source: Option<&str>) -> Result<(), UnsupportedPattern> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let unsupported = |construct: String, position: usize, hint: &'static str| {
+        Err(UnsupportedPattern {
+            construct,
+            position,
+            pattern: pattern.to_owned(),
+            source: source.map(str::to_owned),
+            hint,
+        })
+    };
+    let text =
+        |from: usize, len: usize| chars[from..(from + len).min(chars.len())].iter().collect();
+
+    let mut class_depth = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                match chars.get(i + 1) {
+                    Some('1'..='9') if class_depth == 0 => {
+                        return unsupported(
+                            text(i, 2),
+                            i,
+                            "the Rust regex engine does not support backreferences, consider checking the repeated text after matching",
+                        )
+                    }
+                    Some('k') if class_depth == 0 && chars.get(i + 2) == Some(&'<') => {
+                        return unsupported(
+                            text(i, 3),
+                            i,
+                            "the Rust regex engine does not support backreferences, consider checking the repeated text after matching",
+                        )
+                    }
+                    Some('c') if chars.get(i + 2).map_or(false, char::is_ascii_alphabetic) => {
+                        return unsupported(
+                            text(i, 3),
+                            i,
+                            "the Rust regex engine does not support control escapes, use a \\x escape instead",
+                        )
+                    }
+                    Some('0') => {
+                        return unsupported(
+                            text(i, 2),
+                            i,
+                            "the Rust regex engine does not support octal escapes, use \\x00 instead",
+                        )
+                    }
+                    _ => {}
+                }
+                i += 2;
+                continue;
+            }
+            '[' => class_depth += 1,
+            // A `]` right after the opening `[` or `[^` is literal
+            ']' if class_depth > 0 && !matches!(&chars[..i], [.., '['] | [.., '[', '^']) => {
+                class_depth -= 1
+            }
+            '(' if class_depth == 0 && chars.get(i + 1) == Some(&'?') => {
+                match (chars.get(i + 2), chars.get(i + 3)) {
+                    (Some('=' | '!'), _) => {
+                        return unsupported(
+                            text(i, 3),
+                            i,
+                            "the Rust regex engine does not support lookahead, consider restructuring with ends_with or a custom decode",
+                        )
+                    }
+                    (Some('<'), Some('=' | '!')) => {
+                        return unsupported(
+                            text(i, 4),
+                            i,
+                            "the Rust regex engine does not support lookbehind, consider matching the preceding text in the path",
+                        )
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(())
+}
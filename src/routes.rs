@@ -0,0 +1,723 @@
+//! Named routes
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{
+    internal::DataValue, Compiler, CompilerOptions, Delimiters, FragmentMode, MatchResult, Matcher,
+    MatcherOptions, Parser, ParserOptions, PathRegexOptions, Token,
+};
+
+/// A named route, matching paths and generating them
+pub struct Route {
+    name: String,
+    path: String,
+    matcher: Matcher,
+    compiler: Mutex<Option<Arc<Compiler>>>,
+    /// The static text every matching path starts with, empty when unknown
+    static_prefix: String,
+    specificity: usize,
+    priority: i32,
+    /// The insertion order in a [`Routes`](struct.Routes.html)
+    order: u64,
+}
+
+impl Route {
+    /// Create a [`Route`](struct.Route.html) with the options
+    pub fn new(
+        name: impl AsRef<str>,
+        path: impl AsRef<str>,
+        options: MatcherOptions,
+    ) -> Result<Self> {
+        let name = name.as_ref();
+        let path = path.as_ref();
+        let matcher = Matcher::new_with_options(path, options)
+            .with_context(|| format!("Invalid route \"{name}\""))?;
+        let options = PathRegexOptions::from(matcher.options.clone());
+        let tokens = Parser::new_with_options(ParserOptions::from(options))
+            .parse_str(path)
+            .unwrap_or_default();
+        let static_prefix = static_prefix(&tokens, &matcher.options);
+        Ok(Self {
+            name: name.to_owned(),
+            path: path.to_owned(),
+            matcher,
+            compiler: Mutex::new(None),
+            static_prefix,
+            specificity: specificity(&tokens),
+            priority: 0,
+            order: 0,
+        })
+    }
+
+    /// The name of the route
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The path of the route
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The priority of the route in a [`Routes`](struct.Routes.html)
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// The matcher of the route
+    pub fn matcher(&self) -> &Matcher {
+        &self.matcher
+    }
+
+    /// The static text every path matching the route starts with, used to skip the route
+    /// without running its regex. It is empty when the route doesn't start with static text.
+    pub fn static_prefix(&self) -> &str {
+        &self.static_prefix
+    }
+
+    /// The number of static characters every path matching the route has,
+    /// higher for a more specific route like `/users/me` than `/users/:id`
+    pub fn specificity(&self) -> usize {
+        self.specificity
+    }
+
+    /// Whether the path is excluded by the static prefix, so that it can't match the route
+    fn excludes(&self, path: &str) -> bool {
+        let prefix = self.static_prefix.as_str();
+        if self.matcher.options.sensitive {
+            return !path.starts_with(prefix);
+        }
+        let mut chars = path.chars();
+        for p in prefix.chars() {
+            match chars.next() {
+                None => return true,
+                // Leave the Unicode case folding, like `K` for `k`, to the regex
+                Some(c) if !c.is_ascii() => return false,
+                Some(c) if !c.eq_ignore_ascii_case(&p) => return true,
+                Some(_) => {}
+            }
+        }
+        false
+    }
+
+    /// The compiler of the route, built on first use
+    pub fn compiler(&self) -> Result<Arc<Compiler>> {
+        let mut compiler = self.compiler.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(compiler) = compiler.as_ref() {
+            return Ok(compiler.clone());
+        }
+        let options = CompilerOptions::from(self.matcher.options.clone());
+        let built = Arc::new(Compiler::new_with_options(self.path.as_str(), options)?);
+        *compiler = Some(built.clone());
+        Ok(built)
+    }
+
+    /// Render parameters into a path of the route
+    pub fn url_for(&self, params: &DataValue) -> Result<String> {
+        self.compiler()
+            .and_then(|compiler| compiler.render(params))
+            .with_context(|| format!("Failed to render route \"{}\"", self.name))
+    }
+
+    /// Render the params of a match back into a path of the route
+    #[inline]
+    pub fn recompile(&self, result: &MatchResult) -> Result<String> {
+        self.url_for(&result.to_data())
+    }
+}
+
+impl std::fmt::Debug for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Route")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("matcher", &self.matcher)
+            .field("static_prefix", &self.static_prefix)
+            .field("specificity", &self.specificity)
+            .field("priority", &self.priority)
+            .finish()
+    }
+}
+
+/// A registry of named routes
+///
+/// Routes are dispatched by descending priority, then in the order they were added. Changing the routes takes `&mut self`,
+/// wrap the registry in a lock to share it with readers.
+#[derive(Debug, Default)]
+pub struct Routes {
+    routes: Vec<Route>,
+    names: HashMap<String, usize>,
+    next_order: u64,
+}
+
+impl Routes {
+    /// Create an empty [`Routes`](struct.Routes.html)
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Register a route, the name must be unique
+    #[inline]
+    pub fn add(
+        &mut self,
+        name: impl AsRef<str>,
+        path: impl AsRef<str>,
+        options: MatcherOptions,
+    ) -> Result<()> {
+        self.add_with_priority(name, path, options, 0)
+    }
+
+    /// Register a route dispatched before the routes of lower priority, the name must be unique
+    pub fn add_with_priority(
+        &mut self,
+        name: impl AsRef<str>,
+        path: impl AsRef<str>,
+        options: MatcherOptions,
+        priority: i32,
+    ) -> Result<()> {
+        let name = name.as_ref();
+        if self.names.contains_key(name) {
+            return Err(anyhow!("Duplicate route name \"{name}\""));
+        }
+        let mut route = Route::new(name, path, options)?;
+        route.priority = priority;
+        route.order = self.next_order;
+        self.next_order += 1;
+        self.routes.push(route);
+        self.reorder();
+        Ok(())
+    }
+
+    /// Change the priority of a route
+    pub fn set_priority(&mut self, name: impl AsRef<str>, priority: i32) -> Result<()> {
+        let index = self.index_of(name.as_ref())?;
+        self.routes[index].priority = priority;
+        self.reorder();
+        Ok(())
+    }
+
+    /// Remove a route by its name
+    pub fn remove(&mut self, name: impl AsRef<str>) -> Option<Route> {
+        let index = self.names.remove(name.as_ref())?;
+        let route = self.routes.remove(index);
+        self.reorder();
+        Some(route)
+    }
+
+    /// Replace the path of a route, keeping its name, options and place in the dispatch order
+    pub fn replace(&mut self, name: impl AsRef<str>, path: impl AsRef<str>) -> Result<()> {
+        let name = name.as_ref();
+        let index = self.index_of(name)?;
+        let old = &self.routes[index];
+        let mut route = Route::new(name, path, old.matcher.options.clone())?;
+        route.priority = old.priority;
+        route.order = old.order;
+        self.routes[index] = route;
+        Ok(())
+    }
+
+    /// Remove all the routes
+    pub fn clear(&mut self) {
+        self.routes.clear();
+        self.names.clear();
+    }
+
+    /// Iterate over the routes in the dispatch order
+    pub fn iter(&self) -> std::slice::Iter<'_, Route> {
+        self.routes.iter()
+    }
+
+    /// Get a route by its name
+    pub fn route(&self, name: impl AsRef<str>) -> Option<&Route> {
+        self.names.get(name.as_ref()).map(|&i| &self.routes[i])
+    }
+
+    /// Get the matcher of a route by its name
+    pub fn matcher(&self, name: impl AsRef<str>) -> Option<&Matcher> {
+        self.route(name).map(Route::matcher)
+    }
+
+    /// Render parameters into a path of the named route
+    pub fn url_for(&self, name: impl AsRef<str>, params: &DataValue) -> Result<String> {
+        let index = self.index_of(name.as_ref())?;
+        self.routes[index].url_for(params)
+    }
+
+    /// Find the route matching the path with the highest priority, the first registered on ties
+    ///
+    /// A route is skipped without running its regex when the path doesn't start with its
+    /// [`static_prefix`](struct.Route.html#method.static_prefix), which finds the same route.
+    pub fn find(&self, path: impl AsRef<str>) -> Option<(&Route, MatchResult)> {
+        let path = path.as_ref();
+        self.routes.iter().find_map(|route| {
+            if route.excludes(path) {
+                return None;
+            }
+            route.matcher.find(path).map(|result| (route, result))
+        })
+    }
+
+    /// Explain the dispatch of the path, tracing every route considered up to the matching one
+    pub fn why(&self, path: impl AsRef<str>) -> Vec<RouteTrace> {
+        let path = path.as_ref();
+        let mut traces = vec![];
+        for route in &self.routes {
+            let excluded = route.excludes(path);
+            let matched = !excluded && route.matcher.find(path).is_some();
+            traces.push(RouteTrace {
+                name: route.name.clone(),
+                excluded,
+                matched,
+            });
+            if matched {
+                break;
+            }
+        }
+        traces
+    }
+
+    /// Describe the routes in the dispatch order
+    pub fn describe(&self) -> RoutesDescription {
+        RoutesDescription {
+            routes: self.routes.iter().map(RouteDescription::from).collect(),
+            prefiltered: self
+                .routes
+                .iter()
+                .filter(|route| !route.static_prefix.is_empty())
+                .count(),
+        }
+    }
+
+    /// Rebuild the routes of a description
+    ///
+    /// The functions of the options can't be described, the routes get the default ones.
+    /// It fails when a route rebuilds to another regex than the described one.
+    pub fn from_description(description: &RoutesDescription) -> Result<Self> {
+        let mut routes = Self::new();
+        for route in &description.routes {
+            routes.add_with_priority(
+                &route.name,
+                &route.path,
+                route.to_options(),
+                route.priority,
+            )?;
+            let rebuilt = routes.routes[routes.index_of(&route.name)?]
+                .matcher
+                .re
+                .as_str();
+            if rebuilt != route.pattern {
+                return Err(anyhow!(
+                    "Route \"{}\" rebuilt as \"{rebuilt}\" instead of \"{}\"",
+                    route.name,
+                    route.pattern
+                ));
+            }
+        }
+        Ok(routes)
+    }
+
+    /// The number of routes
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Whether there is no route
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    fn index_of(&self, name: &str) -> Result<usize> {
+        self.names
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown route name \"{name}\""))
+    }
+
+    /// Sort the routes in the dispatch order and index their names
+    fn reorder(&mut self) {
+        self.routes
+            .sort_by_key(|route| (std::cmp::Reverse(route.priority), route.order));
+        self.names = self
+            .routes
+            .iter()
+            .enumerate()
+            .map(|(i, route)| (route.name.clone(), i))
+            .collect();
+    }
+}
+
+impl<'a> IntoIterator for &'a Routes {
+    type Item = &'a Route;
+    type IntoIter = std::slice::Iter<'a, Route>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A step of [`Routes::why`](struct.Routes.html#method.why)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteTrace {
+    /// The name of the route
+    pub name: String,
+    /// Whether the static prefix of the route excluded the path before running the regex
+    pub excluded: bool,
+    /// Whether the route matched the path
+    pub matched: bool,
+}
+
+/// The table of [`Routes`](struct.Routes.html), for debugging and ops tooling
+///
+/// It converts to and from JSON, and implements `Serialize` and `Deserialize` with the `serde` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RoutesDescription {
+    /// The routes in the dispatch order
+    pub routes: Vec<RouteDescription>,
+    /// The number of routes with a static prefix, which the dispatch skips without running
+    /// their regex when the path doesn't start with it
+    pub prefiltered: usize,
+}
+
+/// A route of a [`RoutesDescription`](struct.RoutesDescription.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteDescription {
+    /// The name of the route
+    pub name: String,
+    /// The path of the route
+    pub path: String,
+    /// The priority of the route
+    pub priority: i32,
+    /// The source of the regex of the route
+    pub pattern: String,
+    /// The static text every matching path starts with, empty when unknown
+    pub static_prefix: String,
+    /// The number of static characters every matching path has, see
+    /// [`Route::specificity`](struct.Route.html#method.specificity)
+    pub specificity: usize,
+    /// The delimiters of the options
+    pub delimiter: String,
+    /// The prefixes of the options
+    pub prefixes: String,
+    /// The start delimiters of the options
+    pub start_delimiter: Option<String>,
+    /// The `query_params` option
+    pub query_params: bool,
+    /// The end characters of the options
+    pub ends_with: String,
+    /// The `sensitive` option
+    pub sensitive: bool,
+    /// The `strict` option
+    pub strict: bool,
+    /// The `end` option
+    pub end: bool,
+    /// The `start` option
+    pub start: bool,
+    /// The `confine_to_segment` option
+    pub confine_to_segment: bool,
+    /// The `blank_as_missing` option
+    pub blank_as_missing: bool,
+    /// The `matrix_params` option
+    pub matrix_params: bool,
+    /// The `fragment` option
+    pub fragment: Option<FragmentMode>,
+    /// The `max_captures` option
+    pub max_captures: Option<usize>,
+    /// The `cache_size` option
+    pub cache_size: Option<usize>,
+    /// The `repeat_separator` option
+    pub repeat_separator: Option<String>,
+    /// The `repeat_separators` option
+    pub repeat_separators: HashMap<String, String>,
+}
+
+impl From<&Route> for RouteDescription {
+    fn from(route: &Route) -> Self {
+        let MatcherOptions {
+            delimiter,
+            prefixes,
+            start_delimiter,
+            query_params,
+            ends_with,
+            sensitive,
+            strict,
+            end,
+            start,
+            confine_to_segment,
+            blank_as_missing,
+            matrix_params,
+            fragment,
+            max_captures,
+            cache_size,
+            repeat_separator,
+            repeat_separators,
+            ..
+        } = route.matcher.options.clone();
+        Self {
+            name: route.name.clone(),
+            path: route.path.clone(),
+            priority: route.priority,
+            pattern: route.matcher.re.as_str().to_owned(),
+            static_prefix: route.static_prefix.clone(),
+            specificity: route.specificity,
+            delimiter: delimiter.as_str().to_owned(),
+            prefixes,
+            start_delimiter,
+            query_params,
+            ends_with,
+            sensitive,
+            strict,
+            end,
+            start,
+            confine_to_segment,
+            blank_as_missing,
+            matrix_params,
+            fragment,
+            max_captures,
+            cache_size,
+            repeat_separator,
+            repeat_separators,
+        }
+    }
+}
+
+impl RouteDescription {
+    /// The options of the route, with the default functions
+    fn to_options(&self) -> MatcherOptions {
+        MatcherOptions {
+            delimiter: Delimiters::new(&self.delimiter),
+            prefixes: self.prefixes.clone(),
+            start_delimiter: self.start_delimiter.clone(),
+            query_params: self.query_params,
+            ends_with: self.ends_with.clone(),
+            sensitive: self.sensitive,
+            strict: self.strict,
+            end: self.end,
+            start: self.start,
+            confine_to_segment: self.confine_to_segment,
+            blank_as_missing: self.blank_as_missing,
+            matrix_params: self.matrix_params,
+            fragment: self.fragment,
+            max_captures: self.max_captures,
+            cache_size: self.cache_size,
+            repeat_separator: self.repeat_separator.clone(),
+            repeat_separators: self.repeat_separators.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn to_json(&self) -> DataValue {
+        serde_json::json!({
+            "name": self.name,
+            "path": self.path,
+            "priority": self.priority,
+            "pattern": self.pattern,
+            "static_prefix": self.static_prefix,
+            "specificity": self.specificity,
+            "delimiter": self.delimiter,
+            "prefixes": self.prefixes,
+            "start_delimiter": self.start_delimiter,
+            "query_params": self.query_params,
+            "ends_with": self.ends_with,
+            "sensitive": self.sensitive,
+            "strict": self.strict,
+            "end": self.end,
+            "start": self.start,
+            "confine_to_segment": self.confine_to_segment,
+            "blank_as_missing": self.blank_as_missing,
+            "matrix_params": self.matrix_params,
+            "fragment": self.fragment.map(|mode| match mode {
+                FragmentMode::Ignore => "ignore",
+                FragmentMode::Capture => "capture",
+                FragmentMode::Reject => "reject",
+            }),
+            "max_captures": self.max_captures,
+            "cache_size": self.cache_size,
+            "repeat_separator": self.repeat_separator,
+            "repeat_separators": self.repeat_separators,
+        })
+    }
+
+    fn from_json(value: &DataValue) -> Result<Self> {
+        let string = |name: &str| -> Result<String> {
+            value
+                .get(name)
+                .and_then(DataValue::as_str)
+                .map(str::to_owned)
+                .ok_or_else(|| anyhow!("Expected \"{name}\" to be a string"))
+        };
+        let optional_string = |name: &str| -> Result<Option<String>> {
+            match value.get(name) {
+                None | Some(DataValue::Null) => Ok(None),
+                Some(_) => string(name).map(Some),
+            }
+        };
+        let flag = |name: &str| -> Result<bool> {
+            value
+                .get(name)
+                .and_then(DataValue::as_bool)
+                .ok_or_else(|| anyhow!("Expected \"{name}\" to be a boolean"))
+        };
+        let size = |name: &str| -> Result<Option<usize>> {
+            match value.get(name) {
+                None | Some(DataValue::Null) => Ok(None),
+                Some(size) => size
+                    .as_u64()
+                    .and_then(|size| usize::try_from(size).ok())
+                    .map(Some)
+                    .ok_or_else(|| anyhow!("Expected \"{name}\" to be a size")),
+            }
+        };
+        let name = string("name")?;
+        let path = string("path")?;
+        let repeat_separators = match value.get("repeat_separators") {
+            None | Some(DataValue::Null) => HashMap::new(),
+            Some(DataValue::Object(separators)) => separators
+                .iter()
+                .map(|(name, separator)| match separator.as_str() {
+                    Some(separator) => Ok((name.to_owned(), separator.to_owned())),
+                    None => Err(anyhow!(
+                        "Expected the separator of \"{name}\" to be a string"
+                    )),
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => return Err(anyhow!("Expected \"repeat_separators\" to be an object")),
+        };
+        let priority = value
+            .get("priority")
+            .and_then(DataValue::as_i64)
+            .and_then(|priority| i32::try_from(priority).ok())
+            .ok_or_else(|| anyhow!("Expected \"priority\" to be an integer"))?;
+        let fragment = match optional_string("fragment")?.as_deref() {
+            None => None,
+            Some("ignore") => Some(FragmentMode::Ignore),
+            Some("capture") => Some(FragmentMode::Capture),
+            Some("reject") => Some(FragmentMode::Reject),
+            Some(mode) => return Err(anyhow!("Unknown fragment mode \"{mode}\"")),
+        };
+        Ok(Self {
+            name,
+            path,
+            priority,
+            pattern: string("pattern")?,
+            static_prefix: optional_string("static_prefix")?.unwrap_or_default(),
+            specificity: size("specificity")?.unwrap_or_default(),
+            delimiter: string("delimiter")?,
+            prefixes: string("prefixes")?,
+            start_delimiter: optional_string("start_delimiter")?,
+            query_params: value
+                .get("query_params")
+                .map_or(Ok(false), |_| flag("query_params"))?,
+            ends_with: string("ends_with")?,
+            sensitive: flag("sensitive")?,
+            strict: flag("strict")?,
+            end: flag("end")?,
+            start: flag("start")?,
+            confine_to_segment: flag("confine_to_segment")?,
+            blank_as_missing: flag("blank_as_missing")?,
+            matrix_params: flag("matrix_params")?,
+            fragment,
+            max_captures: size("max_captures")?,
+            cache_size: size("cache_size")?,
+            repeat_separator: optional_string("repeat_separator")?,
+            repeat_separators,
+        })
+    }
+}
+
+impl RoutesDescription {
+    /// Convert the description to JSON
+    pub fn to_json(&self) -> DataValue {
+        serde_json::json!({
+            "routes": self.routes.iter().map(RouteDescription::to_json).collect::<Vec<_>>(),
+            "prefiltered": self.prefiltered,
+        })
+    }
+
+    /// Read a description from JSON
+    pub fn from_json(value: &DataValue) -> Result<Self> {
+        let routes = value
+            .get("routes")
+            .and_then(DataValue::as_array)
+            .ok_or_else(|| anyhow!("Expected \"routes\" to be an array"))?;
+        let prefiltered = match value.get("prefiltered") {
+            None | Some(DataValue::Null) => 0,
+            Some(count) => count
+                .as_u64()
+                .and_then(|count| usize::try_from(count).ok())
+                .ok_or_else(|| anyhow!("Expected \"prefiltered\" to be a size"))?,
+        };
+        let routes = routes
+            .iter()
+            .enumerate()
+            .map(|(i, route)| {
+                RouteDescription::from_json(route)
+                    .with_context(|| format!("Invalid route description at {i}"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            routes,
+            prefiltered,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde_core::Serialize for RoutesDescription {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde_core::Serializer,
+    {
+        self.to_json().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde_core::Deserialize<'de> for RoutesDescription {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde_core::Deserializer<'de>,
+    {
+        let value = DataValue::deserialize(deserializer)?;
+        Self::from_json(&value).map_err(|err| serde_core::de::Error::custom(format!("{err:#}")))
+    }
+}
+
+/// The static text every path matching the route starts with, empty when unknown
+fn static_prefix(tokens: &[Token], options: &MatcherOptions) -> String {
+    // A path with matrix parameters has them stripped before matching
+    if !options.start || options.matrix_params {
+        return String::new();
+    }
+    let options = PathRegexOptions::from(options.clone());
+    let prefix = match tokens.first() {
+        Some(Token::Static(text)) => (options.encode_static)(text),
+        _ => return String::new(),
+    };
+    // Only ASCII is compared without the case, leave the rest to the regex
+    if options.sensitive || prefix.is_ascii() {
+        prefix
+    } else {
+        String::new()
+    }
+}
+
+/// The number of static characters every path matching the tokens has,
+/// counting the prefix and suffix of the required parameters
+fn specificity(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::Static(text) => text.chars().count(),
+            Token::Key(key) if matches!(key.modifier.as_str(), "" | "+") => {
+                key.prefix.chars().count() + key.suffix.chars().count()
+            }
+            Token::Key(_) => 0,
+        })
+        .sum()
+}
@@ -0,0 +1,59 @@
+//! Render a path without `serde_json`
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use regex::RegexBuilder;
+
+use crate::{Key, Parser, Token};
+
+/// Render the parameters into a path with the default options
+///
+/// Every parameter is rendered as a single segment, including the repeated ones.
+/// A missing parameter is rendered with its default value, when it has one.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let data = HashMap::from([("id".to_owned(), "123")]);
+/// assert_eq!(path2regex::compile_path("/user/:id", &data).unwrap(), "/user/123");
+/// ```
+pub fn compile_path(path: &str, data: &HashMap<String, &str>) -> Result<String> {
+    let tokens = Parser::new().parse_str(path)?;
+    let mut result = String::with_capacity(path.len());
+    for token in tokens.iter() {
+        let Key {
+            name,
+            prefix,
+            suffix,
+            pattern,
+            modifier,
+            default,
+        } = match token {
+            Token::Static(token) => {
+                result += token;
+                continue;
+            }
+            Token::Key(key) => key,
+        };
+
+        match data.get(name).copied().or(default.as_deref()) {
+            Some(value) => {
+                let re = RegexBuilder::new(&format!("^(?:{pattern})$"))
+                    .case_insensitive(true)
+                    .build()?;
+                if !re.is_match(value) {
+                    return Err(anyhow!(
+                        "Expected all \"{name}\" to match \"{pattern}\", but got \"{value}\""
+                    ));
+                }
+                result += prefix;
+                result += value;
+                result += suffix;
+            }
+            None if matches!(modifier.as_str(), "?" | "*") => {}
+            None => return Err(anyhow!("Expected \"{name}\" to be a string")),
+        }
+    }
+    Ok(result)
+}
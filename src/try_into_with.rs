@@ -1,16 +1,17 @@
-//! try
+//! Conversions of path sources with the options
 
 use anyhow::Result;
 
 use crate::{
+    internal::{END_WITH_DELIMITER, TRAILING_DELIMITER},
     parser::parse_str_with_options,
     re::{regex_to_path_regex, string_to_path_regex},
     ParserOptions, PathRegex, PathRegexOptions, Token,
 };
 
-///
+/// A fallible conversion of a path source into `T` with the options `O`
 pub trait TryIntoWith<T, O>: Clone {
-    ///
+    /// Performs the conversion with the options
     fn try_into_with(self, options: &O) -> Result<T>;
 }
 
@@ -36,7 +37,11 @@ impl TryIntoWith<PathRegex, PathRegexOptions> for regex::Regex {
     fn try_into_with(self, _: &PathRegexOptions) -> Result<PathRegex> {
         let mut keys = vec![];
         let re = regex_to_path_regex(self, &mut keys)?;
-        Ok(PathRegex { re, keys })
+        Ok(PathRegex {
+            re,
+            keys,
+            source: None,
+        })
     }
 }
 
@@ -46,7 +51,7 @@ impl TryIntoWith<PathRegex, PathRegexOptions> for String {
     }
 }
 
-impl<'a> TryIntoWith<PathRegex, PathRegexOptions> for &'a str {
+impl TryIntoWith<PathRegex, PathRegexOptions> for &str {
     fn try_into_with(self, options: &PathRegexOptions) -> Result<PathRegex> {
         string_to_path_regex(self, options)
     }
@@ -62,9 +67,21 @@ where
         for source in self.into_iter() {
             let mut re = source.try_into_with(options)?;
             keys.append(&mut re.keys);
-            parts.push(re.to_string());
+            parts.push(re.as_str().to_owned());
+        }
+        if parts.len() > 1 {
+            // The names of the groups of the delimiters can't repeat in the alternatives
+            for part in parts.iter_mut() {
+                for name in [END_WITH_DELIMITER, TRAILING_DELIMITER] {
+                    *part = part.replace(&format!("(?P<{name}>"), "(?:");
+                }
+            }
         }
         let re = regex::Regex::new(&format!("(?:{})", parts.join("|")))?;
-        Ok(PathRegex { re, keys })
+        Ok(PathRegex {
+            re,
+            keys,
+            source: None,
+        })
     }
 }
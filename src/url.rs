@@ -0,0 +1,171 @@
+//! Match complete URLs
+
+use anyhow::{anyhow, Result};
+
+use crate::{internal::DataValue, MatchResult, Matcher, MatcherBuilder, MatcherOptions};
+
+/// A matcher of complete URLs, like `https://:tenant.example.com/hooks/:id`
+///
+/// The scheme is literal or `*` for any scheme. The host is parsed like a path with `.` as
+/// the delimiter and the path with the options of the matcher, and their params are merged,
+/// so a name can't be used by both. An IPv6 host, like `[::1]`, is matched as text.
+///
+/// A pattern without a port matches the URLs without a port or with the default port of
+/// their scheme, and a pattern with a port, like `http://localhost:8080`, matches the URLs
+/// on that port, explicit or implied by their scheme.
+/// A pattern without a scheme matches the path like a [`Matcher`](struct.Matcher.html).
+#[derive(Debug, Clone)]
+pub struct UrlMatcher {
+    origin: Option<Origin>,
+    path: Matcher,
+}
+
+#[derive(Debug, Clone)]
+struct Origin {
+    /// `None` for any scheme
+    scheme: Option<String>,
+    host: Matcher,
+    port: Option<u16>,
+}
+
+impl UrlMatcher {
+    /// Create a [`UrlMatcher`](struct.UrlMatcher.html)
+    #[inline]
+    pub fn new(pattern: impl AsRef<str>) -> Result<Self> {
+        Self::new_with_options(pattern, Default::default())
+    }
+
+    /// Create a [`UrlMatcher`](struct.UrlMatcher.html) with the options of the path
+    pub fn new_with_options(pattern: impl AsRef<str>, options: MatcherOptions) -> Result<Self> {
+        let pattern = pattern.as_ref();
+        let (scheme, rest) = match pattern.split_once("://") {
+            Some(parts) => parts,
+            None => {
+                return Ok(Self {
+                    origin: None,
+                    path: Matcher::new_with_options(pattern, options)?,
+                })
+            }
+        };
+        let (authority, path) = split_authority(rest);
+        let (host, port) = match split_port(authority) {
+            (host, Some(port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid port \"{port}\" in \"{pattern}\""))?;
+                (host, Some(port))
+            }
+            _ => (authority, None),
+        };
+        if host.is_empty() {
+            return Err(anyhow!("Missing host in \"{pattern}\""));
+        }
+        // The colons of an IPv6 host are text, not parameters
+        let host = if host.starts_with('[') {
+            host.replace(':', "\\:")
+        } else {
+            host.to_owned()
+        };
+        let host = MatcherBuilder::new(host)
+            .set_delimiter('.')
+            .set_prefixes(".")
+            .set_strict(true)
+            .build()?;
+        let path = Matcher::new_with_options(path, options)?;
+        if let Some(key) = host
+            .keys()
+            .iter()
+            .find(|key| path.keys().iter().any(|other| other.name == key.name))
+        {
+            return Err(anyhow!(
+                "Duplicate parameter \"{}\" in the host and the path of \"{pattern}\"",
+                key.name
+            ));
+        }
+        Ok(Self {
+            origin: Some(Origin {
+                scheme: (scheme != "*").then(|| scheme.to_ascii_lowercase()),
+                host,
+                port,
+            }),
+            path,
+        })
+    }
+
+    /// matching parameters in the URL
+    ///
+    /// The query and the fragment of the URL are left out, the `path` of the result is the
+    /// matched part of the path of the URL.
+    pub fn find(&self, url: impl AsRef<str>) -> Option<MatchResult> {
+        let url = url.as_ref();
+        let Origin { scheme, host, port } = match &self.origin {
+            Some(origin) => origin,
+            None => return self.path.find(url),
+        };
+
+        let (url_scheme, rest) = url.split_once("://")?;
+        let url_scheme = url_scheme.to_ascii_lowercase();
+        if scheme
+            .as_ref()
+            .map_or(false, |scheme| *scheme != url_scheme)
+        {
+            return None;
+        }
+        let (authority, path) = split_authority(rest);
+        let authority = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host);
+        let (url_host, url_port) = match split_port(authority) {
+            (host, Some(port)) => (host, Some(port.parse::<u16>().ok()?)),
+            (host, None) => (host, None),
+        };
+        let default_port = default_port(&url_scheme);
+        let port_matches = match port {
+            Some(port) => url_port.or(default_port) == Some(*port),
+            None => url_port.is_none() || url_port == default_port,
+        };
+        if !port_matches {
+            return None;
+        }
+
+        let host = host.find(url_host)?;
+        let path = path.find(['?', '#']).map_or(path, |end| &path[..end]);
+        let mut result = self.path.find(path)?;
+        if let (DataValue::Object(host), DataValue::Object(params)) =
+            (host.params, &mut result.params)
+        {
+            for (name, value) in host {
+                params.insert(name, value);
+            }
+        }
+        Some(result)
+    }
+}
+
+/// Split the authority of a URL without its scheme from the rest of it
+fn split_authority(url: &str) -> (&str, &str) {
+    let end = url.find(['/', '?', '#']).unwrap_or(url.len());
+    url.split_at(end)
+}
+
+/// Split the port from the host of an authority, keeping the colons of an IPv6 host
+fn split_port(authority: &str) -> (&str, Option<&str>) {
+    let start = authority.rfind(']').map_or(0, |end| end + 1);
+    match authority[start..].rfind(':') {
+        Some(colon) => (
+            &authority[..start + colon],
+            Some(&authority[start + colon + 1..]),
+        ),
+        None => (authority, None),
+    }
+}
+
+/// The port implied by the scheme
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        _ => None,
+    }
+}
@@ -1,6 +1,13 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
-use path2regex::{Key, PathRegex, PathRegexBuilder};
+use path2regex::{
+    CompilerBuilder, CompilerOptions, Delimiters, Key, MatcherBuilder, ParseWarning,
+    ParseWarningKind, Parser, ParserOptions, PathRegex, PathRegexBuilder, PathRegexOptions, Token,
+    UnsupportedPattern,
+};
 use regex::Regex;
+use serde_json::json;
 
 pub const TEST_PATH: &str = "/user/:id";
 
@@ -23,6 +30,7 @@ fn should_get_keys() -> Result<()> {
             suffix: "".to_owned(),
             modifier: "".to_owned(),
             pattern: "[^/\\#\\?]+?".to_owned(),
+            default: None,
         }]
     );
     assert_eq!(
@@ -36,7 +44,8 @@ fn should_get_keys() -> Result<()> {
                 }
             })
             .collect::<Vec<_>>(),
-        vec!["/user/123/", "123", "/"]
+        // The optional trailing delimiter is not in the match, the end delimiter is
+        vec!["/user/123/", "123", "", "/"]
     );
     Ok(())
 }
@@ -82,3 +91,778 @@ fn should_throw_on_nested_groups() {
 fn should_throw_on_misplaced_modifier() {
     PathRegex::new("/foo?").unwrap();
 }
+
+#[test]
+fn should_match_with_start_delimiter() -> Result<()> {
+    let re = PathRegexBuilder::new("isbn::id")
+        .set_start_delimiter(":")
+        .build()?;
+    assert_eq!(
+        re.keys(),
+        &vec![Key {
+            name: "id".to_owned(),
+            prefix: ":".to_owned(),
+            suffix: "".to_owned(),
+            modifier: "".to_owned(),
+            pattern: "[^/\\#\\?]+?".to_owned(),
+            default: None,
+        }]
+    );
+    let caps = re.captures("isbn:12345").unwrap();
+    assert_eq!(&caps[1], "12345");
+    assert!(!re.is_match("isbn12345"));
+    assert!(PathRegex::new("isbn::id").is_err());
+    Ok(())
+}
+
+#[test]
+fn should_accept_parser_options() -> Result<()> {
+    let options = ParserOptions {
+        delimiter: ".".into(),
+        ..Default::default()
+    };
+
+    let mut builder = PathRegexBuilder::new("/:name");
+    builder.set_parser_options(options.clone());
+    assert_eq!(builder.parser_options().delimiter, ".");
+    assert_eq!(builder.build()?.keys()[0].pattern, "[^\\.]+?");
+
+    let matcher = MatcherBuilder::new("/:name")
+        .set_parser_options(options.clone())
+        .build()?;
+    assert!(matcher.find("/a.b").is_none());
+    assert!(matcher.find("/a/b").is_some());
+
+    let compiler = CompilerBuilder::new("/:name")
+        .set_parser_options(options)
+        .build()?;
+    assert!(compiler.render(&json!({"name": "a.b"})).is_err());
+    assert_eq!(compiler.render(&json!({"name": "a/b"}))?, "/a/b");
+    Ok(())
+}
+
+#[test]
+fn should_create_parser_options_from_chars() -> Result<()> {
+    let options = ParserOptions::from_chars('.', '.');
+    assert_eq!(options.delimiter, ".");
+    assert_eq!(options.prefixes, ".");
+    let tokens = Parser::new_with_options(options).parse_str("file.:ext")?;
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Static("file".to_owned()),
+            Token::Key(Key {
+                name: "ext".to_owned(),
+                prefix: ".".to_owned(),
+                pattern: "[^\\.]+?".to_owned(),
+                ..Default::default()
+            })
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn should_use_custom_delimiters() -> Result<()> {
+    let delimiters = Delimiters::from("/./");
+    assert_eq!(delimiters, "/.");
+    assert_eq!(delimiters.primary(), Some('/'));
+    assert!(delimiters.contains('.'));
+    assert!(!delimiters.contains('#'));
+
+    let tokens = Parser::new_with_options(ParserOptions {
+        delimiter: delimiters.clone(),
+        ..Default::default()
+    })
+    .parse_str("/:file.:ext")?;
+    assert_eq!(
+        tokens[1],
+        Token::Key(Key {
+            name: "ext".to_owned(),
+            prefix: ".".to_owned(),
+            pattern: "[^/\\.]+?".to_owned(),
+            ..Default::default()
+        })
+    );
+
+    let matcher = MatcherBuilder::new("/:file.:ext")
+        .set_delimiter(delimiters.clone())
+        .build()?;
+    let result = matcher.find("/a.txt").unwrap();
+    assert_eq!(result.params, json!({"file": "a", "ext": "txt"}));
+    assert!(matcher.find("/a.b.txt").is_none());
+
+    let compiler = CompilerBuilder::new("/:file.:ext")
+        .set_delimiter(delimiters)
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"file": "a", "ext": "txt"}))?,
+        "/a.txt"
+    );
+    assert!(compiler
+        .render(&json!({"file": "a", "ext": "b.txt"}))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn should_encode_static_text_and_affixes_separately() -> Result<()> {
+    fn encode(x: &str) -> String {
+        urlencoding::encode(x).into_owned()
+    }
+
+    let re = PathRegexBuilder::new("v 1/:id")
+        .set_encode_static(encode)
+        .build()?;
+    let caps = re.captures("v%201/42").unwrap();
+    assert_eq!(&caps[1], "42");
+    assert!(!re.is_match("v 1/42"));
+
+    let re = PathRegexBuilder::new("v 1/:id")
+        .set_encode(encode)
+        .build()?;
+    assert!(!re.is_match("v%201/42"));
+    assert!(re.is_match("v%201%2F42"));
+    Ok(())
+}
+
+#[test]
+fn should_build_path_regex_from_tokens() -> Result<()> {
+    let options = PathRegexOptions {
+        end: false,
+        ..Default::default()
+    };
+    let user =
+        PathRegex::try_from_tokens_with_options(Parser::new().parse_str("/user/:id")?, &options)?;
+    let post =
+        PathRegex::try_from_tokens_with_options(Parser::new().parse_str("/post/:id")?, &options)?;
+    assert_eq!(user.source(), None);
+    assert_eq!(user.keys()[0].name, "id");
+    assert!(user.is_match("/user/1/posts"));
+    assert!(post.is_match("/post/1/comments"));
+    assert_eq!(
+        user.as_str(),
+        PathRegex::new_with_options("/user/:id", options)?.as_str()
+    );
+    Ok(())
+}
+
+#[test]
+fn should_append_a_path() -> Result<()> {
+    let options = PathRegexOptions {
+        sensitive: true,
+        ..Default::default()
+    };
+    let user = PathRegex::new_with_options("/user/:id", options)?;
+    let details = (&user + "/details")?;
+    assert_eq!(details.source(), Some("/user/:id/details"));
+    assert_eq!(details.keys().len(), 1);
+    assert!(details.is_match("/user/1/details"));
+    assert!(!details.is_match("/USER/1/details"));
+    assert!(!details.is_match("/user/1"));
+
+    let tab = (details + "/:tab")?;
+    assert_eq!(tab.keys().len(), 2);
+    assert!(tab.is_match("/user/1/details/posts"));
+
+    let re = PathRegex::new(Regex::new("^/user$")?)?;
+    assert!((re + "/details").is_err());
+    Ok(())
+}
+
+#[test]
+fn should_get_the_key_of_a_capture_group() -> Result<()> {
+    let re = PathRegex::new("/user/:id/:tab")?;
+    let caps = re.captures("/user/1/posts").unwrap();
+    assert!(re.key_at(0).is_none());
+    assert_eq!(re.key_at(1).map(|key| key.name.as_str()), Some("id"));
+    assert_eq!(re.key_at(2).map(|key| key.name.as_str()), Some("tab"));
+    assert!(re.key_at(3).is_none());
+    let params = (1..caps.len())
+        .filter_map(|i| Some((re.key_at(i)?.name.as_str(), caps.get(i)?.as_str())))
+        .collect::<Vec<_>>();
+    assert_eq!(params, vec![("id", "1"), ("tab", "posts")]);
+    Ok(())
+}
+
+#[test]
+fn should_confine_params_to_a_segment() -> Result<()> {
+    let re = PathRegex::new("/:slug(.+)")?;
+    assert!(re.is_match("/a/b"));
+
+    let re = PathRegexBuilder::new("/:slug(.+)")
+        .set_confine_to_segment(true)
+        .build()?;
+    assert!(!re.is_match("/a/b"));
+    assert_eq!(&re.captures("/a").unwrap()[1], "a");
+    assert_eq!(re.keys()[0].pattern, ".+");
+
+    let re = PathRegexBuilder::new("/:file(a/b|\\w+\\.txt)")
+        .set_confine_to_segment(true)
+        .build()?;
+    assert!(!re.is_match("/a/b"));
+    assert!(re.is_match("/notes.txt"));
+
+    let matcher = MatcherBuilder::new("/:path(.+)+")
+        .set_confine_to_segment(true)
+        .build()?;
+    let result = matcher.find("/a/b").unwrap();
+    assert_eq!(result.params, json!({"path": ["a", "b"]}));
+    Ok(())
+}
+
+#[test]
+fn should_parse_default_values() -> Result<()> {
+    let tokens = Parser::new().parse_str("/list/:page(\\d+)?=1/items")?;
+    assert_eq!(
+        tokens[1],
+        Token::Key(Key {
+            name: "page".to_owned(),
+            prefix: "/".to_owned(),
+            pattern: "\\d+".to_owned(),
+            modifier: "?".to_owned(),
+            default: Some("1".to_owned()),
+            ..Default::default()
+        })
+    );
+    assert_eq!(tokens[2], Token::Static("/items".to_owned()));
+    assert_eq!(tokens[1].to_string(), "{/:page(\\d+)}?=1");
+
+    let tokens = Parser::new().parse_str("/:path*=a\\/b")?;
+    assert_eq!(tokens[0].to_string(), "{/:path([^/\\#\\?]+?)}*=a\\/b");
+
+    let err = Parser::new().parse_str("/list/:page(\\d+)=1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Default value of \"page\" at 16 is only allowed on an optional parameter"
+    );
+    let tokens = Parser::new().parse_str("/list/:page(\\d+)\\=1")?;
+    assert_eq!(tokens[2], Token::Static("=1".to_owned()));
+    let err = Parser::new().parse_str("/list/:page(\\d+)+=1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Default value of \"page\" at 17 is only allowed on an optional parameter"
+    );
+    let err = Parser::new().parse_str("/list/:page?=/items").unwrap_err();
+    assert_eq!(err.to_string(), "Missing default value of \"page\" at 12");
+    Ok(())
+}
+
+#[test]
+fn should_read_options_from_env() -> Result<()> {
+    std::env::set_var("PATH_REGEX_DELIMITER", "/.");
+    std::env::set_var("PATH_REGEX_SENSITIVE", "true");
+    std::env::set_var("PATH_REGEX_END", "false");
+    let options = PathRegexOptions::from_env()?;
+    assert_eq!(options.delimiter, "/.");
+    assert!(options.sensitive);
+    assert!(!options.end);
+    assert!(!options.strict);
+
+    std::env::set_var("PATH_REGEX_STRICT", "yes");
+    let err = PathRegexOptions::from_env().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"PATH_REGEX_STRICT\" to be \"true\" or \"false\", but got \"yes\""
+    );
+
+    for name in [
+        "PATH_REGEX_DELIMITER",
+        "PATH_REGEX_SENSITIVE",
+        "PATH_REGEX_END",
+        "PATH_REGEX_STRICT",
+    ] {
+        std::env::remove_var(name);
+    }
+    assert_eq!(
+        PathRegexOptions::from_env()?.to_string(),
+        PathRegexOptions::default().to_string()
+    );
+    Ok(())
+}
+
+#[test]
+fn should_get_the_modifier_of_tokens() -> Result<()> {
+    let tokens = Parser::new().parse_str("/user/:id/:tab?/:a+/:b*")?;
+    let modifiers = tokens.iter().map(Token::modifier).collect::<Vec<_>>();
+    assert_eq!(
+        modifiers,
+        vec![None, Some(""), Some("?"), Some("+"), Some("*")]
+    );
+    let has_modifier = tokens.iter().map(Token::has_modifier).collect::<Vec<_>>();
+    assert_eq!(has_modifier, vec![false, false, true, true, true]);
+    Ok(())
+}
+
+#[test]
+fn should_extend_the_keys() -> Result<()> {
+    let id = Key {
+        name: "id".to_owned(),
+        pattern: "\\d+".to_owned(),
+        ..Default::default()
+    };
+    let re = PathRegexBuilder::new(Regex::new(r"^/user/(\d+)$")?)
+        .extend_keys(vec![id.clone()])
+        .build()?;
+    assert_eq!(re.keys(), &vec![id.clone()]);
+    assert_eq!(re.key_at(1), Some(&id));
+
+    let tab = Key {
+        name: "tab".to_owned(),
+        ..Default::default()
+    };
+    let re = PathRegexBuilder::new("/user/:id(\\d+)")
+        .extend_keys(vec![tab.clone()])
+        .build()?;
+    assert_eq!(re.keys().len(), 2);
+    assert_eq!(re.keys()[1], tab);
+
+    // The keys past the parameters don't take the groups of the delimiters
+    let re = PathRegexBuilder::new("/a/:id")
+        .set_end(false)
+        .extend_keys(vec![Key::default().with_name("extra")])
+        .build()?;
+    assert_eq!(re.key_at(1).map(|key| key.name.as_str()), Some("id"));
+    assert_eq!(re.key_at(2), None);
+    assert_eq!(re.key_at(3), None);
+    let values = re.captures_iter("/a/1/b").collect::<Vec<_>>();
+    assert_eq!(values, vec![HashMap::from([("id", "1")])]);
+    Ok(())
+}
+
+#[test]
+fn should_parse_without_prefixes() -> Result<()> {
+    let options = ParserOptions::with_no_prefixes();
+    assert_eq!(options.prefixes, "");
+    assert_eq!(options.delimiter, ParserOptions::default().delimiter);
+    let tokens = Parser::new_with_options(options).parse_str("/file.:ext")?;
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Static("/file.".to_owned()),
+            Token::Key(Key {
+                name: "ext".to_owned(),
+                pattern: "[^/\\#\\?]+?".to_owned(),
+                ..Default::default()
+            })
+        ]
+    );
+    let tokens = Parser::new_with_options(ParserOptions::with_no_prefixes()).parse_str("/:id")?;
+    assert_eq!(tokens[0], Token::Static("/".to_owned()));
+    Ok(())
+}
+
+#[test]
+fn should_clone_builders_independently() -> Result<()> {
+    let parser = path2regex::ParserBuilder::new();
+    let mut cloned = parser.clone();
+    cloned.set_prefixes("");
+    assert_eq!(
+        parser.build().parse_str("/:id")?[0].to_string(),
+        "{/:id([^/\\#\\?]+?)}"
+    );
+    assert_eq!(
+        cloned.build().parse_str("/:id")?[0],
+        Token::Static("/".to_owned())
+    );
+
+    let re = PathRegexBuilder::new("/user/:id");
+    let mut cloned = re.clone();
+    cloned.set_end(false);
+    assert!(!re.build()?.is_match("/user/1/posts"));
+    assert!(cloned.build()?.is_match("/user/1/posts"));
+
+    let matcher = MatcherBuilder::new("/user/:id");
+    let mut cloned = matcher.clone();
+    cloned.set_strict(true);
+    assert!(matcher.build()?.find("/user/1/").is_some());
+    assert!(cloned.build()?.find("/user/1/").is_none());
+
+    let compiler = CompilerBuilder::new("/user/:id");
+    let mut cloned = compiler.clone();
+    cloned.set_encode(|x, _| x.to_uppercase());
+    assert_eq!(compiler.build()?.render(&json!({"id": "a"}))?, "/user/a");
+    assert_eq!(cloned.build()?.render(&json!({"id": "a"}))?, "/user/A");
+    Ok(())
+}
+
+#[test]
+fn should_create_a_path_regex_from_a_raw_pattern() -> Result<()> {
+    let re = PathRegex::from_pattern(r"^/(?P<user>\w+)/(\d+)(?:/(?<tab>[a-z]+))?$")?;
+    let names = re
+        .keys()
+        .iter()
+        .map(|key| key.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["user", "0", "tab"]);
+    assert_eq!(re.key_at(2).unwrap().name, "0");
+    assert_eq!(re.as_str(), r"^/(?P<user>\w+)/(\d+)(?:/(?<tab>[a-z]+))?$");
+    assert!(re.source().is_none());
+
+    // The pattern is a regex, `:id` is not a parameter
+    let re = PathRegex::from_pattern("^/:id$")?;
+    assert!(re.keys().is_empty());
+    assert!(re.is_match("/:id"));
+    assert!(!re.is_match("/123"));
+
+    assert!(PathRegex::from_pattern("/(abc").is_err());
+    Ok(())
+}
+
+#[test]
+fn should_reject_unsupported_regex_features_before_building() -> Result<()> {
+    let unsupported = |path: &str| -> UnsupportedPattern {
+        let err = PathRegex::new(path).unwrap_err();
+        err.downcast_ref::<UnsupportedPattern>().unwrap().clone()
+    };
+
+    let err = unsupported(r"/:id((?!new)\w+)");
+    assert_eq!(
+        (err.construct.as_str(), err.position, err.pattern.as_str()),
+        ("(?!", 0, r"(?!new)\w+")
+    );
+    assert_eq!(
+        err.to_string(),
+        "Unsupported \"(?!\" at 0 in the pattern \"(?!new)\\w+\" of \"/:id((?!new)\\w+)\": \
+         the Rust regex engine does not support lookahead, consider restructuring with ends_with or a custom decode"
+    );
+    assert_eq!(err.source.as_deref(), Some(r"/:id((?!new)\w+)"));
+    assert_eq!(unsupported(r"/:id(\w+(?=x))").construct, "(?=");
+    assert_eq!(unsupported(r"/:id((?<=a)b)").construct, "(?<=");
+    assert_eq!(unsupported(r"/:id((?<!a)b)").construct, "(?<!");
+
+    let err = unsupported(r"/:id((?:a)\1)");
+    assert_eq!((err.construct.as_str(), err.position), (r"\1", 5));
+    assert!(err.hint.contains("backreferences"));
+    assert_eq!(unsupported(r"/:id((?:a)\k<x>)").construct, r"\k<");
+    assert_eq!(unsupported(r"/:id(\cJ)").construct, r"\cJ");
+    assert_eq!(unsupported(r"/:id(\0)").construct, r"\0");
+
+    let err = PathRegex::from_pattern(r"^/(?P<id>\d+)(?=/)").unwrap_err();
+    let err = err.downcast_ref::<UnsupportedPattern>().unwrap();
+    assert_eq!((err.position, err.source.as_ref()), (13, None));
+    assert!(err.to_string().ends_with(
+        "in the pattern \"^/(?P<id>\\d+)(?=/)\": the Rust regex engine does not support lookahead, \
+         consider restructuring with ends_with or a custom decode"
+    ));
+
+    // Escaped and class characters are literal text
+    let re = PathRegex::new(r"/:id(\(?=)")?;
+    assert!(re.is_match("/(="));
+    assert!(PathRegex::from_pattern(r"^/[(?=]+$")?.is_match("/(?="));
+    assert!(PathRegex::new(r"/:id([]\\1]+)")?.is_match("/1]"));
+    assert!(PathRegex::new(r"/:id((?<name>\d+))").is_ok());
+    assert!(PathRegex::from_pattern(r"^/\\1$")?.is_match(r"/\1"));
+    Ok(())
+}
+
+#[test]
+fn should_parse_compact_optional_parameters() -> Result<()> {
+    let parser = Parser::new();
+    assert_eq!(
+        parser.parse_str("/users/{:id?}")?,
+        parser.parse_str("/users/{:id}?")?
+    );
+    assert_eq!(
+        parser.parse_str("/files/{:name(\\w+)?}")?,
+        parser.parse_str("/files/{:name(\\w+)}?")?
+    );
+    assert_eq!(
+        parser.parse_str("/list{(\\d+)?}")?,
+        parser.parse_str("/list{(\\d+)}?")?
+    );
+    assert_eq!(
+        parser.parse_str("/page/{:n?}=1")?[1],
+        parser.parse_str("/page/{:n}?=1")?[1]
+    );
+
+    let re = PathRegex::new("/users/{:id?}")?;
+    assert!(re.is_match("/users/"));
+    assert!(re.is_match("/users/42"));
+
+    let err = parser.parse_str("/users/{:id?}?").unwrap_err();
+    assert_eq!(err.to_string(), "Duplicate modifier of \"id\" at 13");
+    // Only `?` is compact, and only without a prefix or a suffix
+    assert!(parser.parse_str("/users/{:id+}").is_err());
+    assert!(parser.parse_str("/users{/:id?}").is_err());
+    assert!(parser.parse_str("/users/{:id?.json}").is_err());
+    Ok(())
+}
+
+#[test]
+fn should_build_keys_with_chained_setters() -> Result<()> {
+    let key = Key::default()
+        .with_name("id")
+        .with_prefix("/")
+        .with_pattern("[0-9]+")
+        .with_modifier("?")
+        .with_default("1");
+    assert_eq!(
+        key,
+        Key {
+            name: "id".to_owned(),
+            prefix: "/".to_owned(),
+            pattern: "[0-9]+".to_owned(),
+            modifier: "?".to_owned(),
+            default: Some("1".to_owned()),
+            ..Default::default()
+        }
+    );
+    assert_eq!(key.to_string(), "{/:id([0-9]+)}?=1");
+
+    let key = Key::default()
+        .with_name(String::from("ext"))
+        .with_prefix(".")
+        .with_suffix("!")
+        .with_pattern("\\w+");
+    assert_eq!(
+        Parser::new().parse_str("{.:ext(\\w+)!}")?,
+        vec![Token::Key(key)]
+    );
+    Ok(())
+}
+
+#[test]
+fn should_iterate_over_the_matches_with_named_values() -> Result<()> {
+    let re = PathRegexBuilder::new("/:kind/:id(\\d+)")
+        .set_start(false)
+        .set_end(false)
+        .set_ends_with(" ")
+        .build()?;
+    let text = "see /users/1 and /posts/22/ or /tags/x";
+    let matches = re.captures_iter(text).collect::<Vec<_>>();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0]["kind"], "users");
+    assert_eq!(matches[0]["id"], "1");
+    assert_eq!(matches[1]["kind"], "posts");
+    assert_eq!(matches[1]["id"], "22");
+
+    let re = PathRegexBuilder::new("/:id{.:ext}?")
+        .set_start(false)
+        .set_end(false)
+        .set_ends_with(" ")
+        .build()?;
+    let matches = re.captures_iter("/a.txt /b").collect::<Vec<_>>();
+    assert_eq!(matches[0].get("ext"), Some(&"txt"));
+    assert_eq!(matches[1].get("ext"), None);
+    assert_eq!(
+        AsRef::<Regex>::as_ref(&re).captures_iter("/a /b").count(),
+        2
+    );
+    Ok(())
+}
+
+#[test]
+fn should_parse_with_recovery() -> Result<()> {
+    let parser = Parser::new();
+    let (tokens, warnings) = parser.parse_with_recovery("/users/:id(\\d+)");
+    assert_eq!(tokens, parser.parse_str("/users/:id(\\d+)")?);
+    assert!(warnings.is_empty());
+
+    let (tokens, warnings) = parser.parse_with_recovery("/a/:");
+    assert_eq!(tokens, vec![Token::Static("/a/:".to_owned())]);
+    assert_eq!(
+        warnings,
+        vec![ParseWarning {
+            offset: 3,
+            kind: ParseWarningKind::MissingParameterName,
+            message: "Missing parameter name at 3".to_owned(),
+        }]
+    );
+
+    let (tokens, warnings) = parser.parse_with_recovery("/a{b");
+    assert_eq!(tokens, vec![Token::Static("/a{b".to_owned())]);
+    assert_eq!(warnings[0].offset, 2);
+    assert_eq!(warnings[0].kind, ParseWarningKind::UnexpectedToken);
+
+    let (tokens, warnings) = parser.parse_with_recovery("/:id(");
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[1], Token::Static("(".to_owned()));
+    assert_eq!(warnings[0].kind, ParseWarningKind::UnbalancedPattern);
+
+    // The messages point into the input, like the offsets
+    let (_, warnings) = parser.parse_with_recovery("/a/:/b{c");
+    assert_eq!(
+        warnings
+            .iter()
+            .map(|warning| (warning.offset, warning.message.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (3, "Missing parameter name at 3"),
+            (6, "Unexpected END at 6, expected CLOSE"),
+        ]
+    );
+
+    // The offsets are in the input, after the characters escaped by the recovery
+    let (tokens, warnings) = parser.parse_with_recovery("/:a(?x)/:b}/c\\");
+    let offsets = warnings
+        .iter()
+        .map(|warning| (warning.offset, warning.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        offsets,
+        vec![
+            (4, ParseWarningKind::PatternStartsWithModifier),
+            (10, ParseWarningKind::UnexpectedToken),
+            (13, ParseWarningKind::MissingEscapedChar),
+        ]
+    );
+    assert_eq!(
+        tokens.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec!["{/:a(\\?x)}", "{/:b([^/\\#\\?]+?)}", "\\}/c\\\\"]
+    );
+    assert!(parser.parse_str("/c\\").is_err());
+    Ok(())
+}
+
+#[test]
+fn should_convert_key_to_json() {
+    let key = Key::default()
+        .with_name("id")
+        .with_prefix("/")
+        .with_pattern("\\d+");
+    assert_eq!(
+        serde_json::Value::from(&key),
+        json!({
+            "name": "id",
+            "prefix": "/",
+            "suffix": "",
+            "pattern": "\\d+",
+            "modifier": "",
+        })
+    );
+    let key = key.with_modifier("?").with_default("1");
+    let value: serde_json::Value = key.into();
+    assert_eq!(value["modifier"], "?");
+    assert_eq!(value["default"], "1");
+}
+
+#[test]
+fn should_build_a_prefix_only_regex() -> Result<()> {
+    let re = PathRegex::new_prefix_only("/api/v1")?;
+    assert_eq!(
+        re.as_str(),
+        PathRegexBuilder::new("/api/v1")
+            .set_end(false)
+            .build()?
+            .as_str()
+    );
+    assert!(re.is_match("/api/v1"));
+    assert!(re.is_match("/api/v1/users"));
+    assert!(re.is_match("/api/v1?page=2"));
+    assert!(!re.is_match("/api/v10"));
+    assert!(!re.is_match("/v2/api/v1"));
+    Ok(())
+}
+
+#[test]
+fn should_parse_quoted_parameter_names() -> Result<()> {
+    let tokens = Parser::new().parse_str("/users/:\"user.id\"")?;
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Static("/users".to_owned()),
+            Token::Key(
+                Key::default()
+                    .with_name("user.id")
+                    .with_prefix("/")
+                    .with_pattern("[^/\\#\\?]+?")
+            ),
+        ]
+    );
+    assert_eq!(tokens[1].to_string(), "{/:\"user.id\"([^/\\#\\?]+?)}");
+    let err = Parser::new().parse_str("/users/:\"user.id").unwrap_err();
+    assert_eq!(err.to_string(), "Unterminated quote at 7");
+    let err = Parser::new().parse_str("/users/:\"\"").unwrap_err();
+    assert_eq!(err.to_string(), "Missing parameter name at 7");
+    Ok(())
+}
+
+#[test]
+fn should_parse_non_ascii_paths() -> Result<()> {
+    let tokens = Parser::new().parse_str("/café/:\"é.名\"(é+)/ü")?;
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Static("/café".to_owned()),
+            Token::Key(
+                Key::default()
+                    .with_name("é.名")
+                    .with_prefix("/")
+                    .with_pattern("é+")
+            ),
+            Token::Static("/ü".to_owned()),
+        ]
+    );
+    let re = PathRegex::new("/café/:\"é\"")?;
+    assert!(re.is_match("/café/thé"));
+    let err = Parser::new().parse_str("/é/:\"é").unwrap_err();
+    assert_eq!(err.to_string(), "Unterminated quote at 3");
+    Ok(())
+}
+
+#[test]
+fn should_parse_query_style_parameter_names() -> Result<()> {
+    let parser = path2regex::ParserBuilder::new()
+        .set_query_params(true)
+        .build();
+    let names = |path: &str| -> Result<Vec<String>> {
+        Ok(parser
+            .parse_str(path)?
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Key(key) => Some(key.name),
+                Token::Static(_) => None,
+            })
+            .collect())
+    };
+    assert_eq!(names("/users?page")?, ["page"]);
+    assert_eq!(names("/users?:page?:limit")?, ["page", "limit"]);
+    assert_eq!(names("/users?page&?limit")?, ["page", "limit"]);
+    assert_eq!(names("/users/{:id}?{?tab}")?, ["id", "tab"]);
+    assert_eq!(
+        parser.parse_str("/users/:id?")?,
+        Parser::new().parse_str("/users/:id?")?
+    );
+    assert!(Parser::new().parse_str("/users?page").is_err());
+
+    let re = PathRegexBuilder::new("/search?q")
+        .set_query_params(true)
+        .set_prefixes("")
+        .build()?;
+    assert_eq!(re.keys()[0].name, "q");
+    assert!(re.is_match("/searchrust"));
+    Ok(())
+}
+
+#[test]
+fn should_get_the_pattern_string() -> Result<()> {
+    let re = PathRegex::new("/user/:id")?;
+    assert_eq!(re.pattern_string(), re.as_str());
+    assert_eq!(re.source(), Some("/user/:id"));
+    assert_eq!(PathRegex::from_pattern("^/a$")?.pattern_string(), "^/a$");
+    Ok(())
+}
+
+#[test]
+fn should_match_the_paths_rendered_with_the_compiler_options() -> Result<()> {
+    let options = CompilerOptions {
+        delimiter: "/.".into(),
+        prefixes: "/.".to_owned(),
+        sensitive: true,
+        repeat_separator: Some(",".to_owned()),
+        ..Default::default()
+    };
+    let path = CompilerBuilder::new_with_options("/files/:name.:ext/:tags+", options.clone())
+        .build()?
+        .render(&json!({"name": "Readme", "ext": "md", "tags": ["a", "b"]}))?;
+    assert_eq!(path, "/files/Readme.md/a,b");
+
+    let re_options = PathRegexOptions::from_compiler_options(options);
+    assert!(re_options.sensitive);
+    assert_eq!(re_options.delimiter, Delimiters::from("/."));
+    let re = PathRegexBuilder::new_with_options("/files/:name.:ext/:tags+", re_options).build()?;
+    assert!(re.is_match(&path));
+    assert!(!re.is_match("/FILES/Readme.md/a,b"));
+    Ok(())
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "capi")]
+
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    ptr,
+};
+
+use path2regex::capi::*;
+
+unsafe fn take_string(s: *mut c_char) -> String {
+    assert!(!s.is_null());
+    let value = CStr::from_ptr(s).to_str().unwrap().to_owned();
+    p2r_string_free(s);
+    value
+}
+
+#[test]
+fn should_match_through_ffi() {
+    let pattern = CString::new("/user/:id").unwrap();
+    let path = CString::new("/USER/123").unwrap();
+    unsafe {
+        let matcher = p2r_matcher_new(pattern.as_ptr(), ptr::null(), ptr::null_mut());
+        assert!(!matcher.is_null());
+
+        let mut json = ptr::null_mut();
+        assert!(p2r_matcher_find(matcher, path.as_ptr(), &mut json));
+        assert_eq!(take_string(json), r#"{"id":"123"}"#);
+
+        let mut options = p2r_matcher_options_default();
+        options.sensitive = true;
+        let sensitive = p2r_matcher_new(pattern.as_ptr(), &options, ptr::null_mut());
+        assert!(!p2r_matcher_find(sensitive, path.as_ptr(), &mut json));
+        assert!(json.is_null());
+
+        p2r_matcher_free(sensitive);
+        p2r_matcher_free(matcher);
+    }
+}
+
+#[test]
+fn should_render_through_ffi() {
+    let pattern = CString::new("/user/:id(\\d+)").unwrap();
+    let data = CString::new(r#"{"id":123}"#).unwrap();
+    unsafe {
+        let compiler = p2r_compiler_new(pattern.as_ptr(), ptr::null(), ptr::null_mut());
+        assert!(!compiler.is_null());
+        let path = p2r_compiler_render(compiler, data.as_ptr(), ptr::null_mut());
+        assert_eq!(take_string(path), "/user/123");
+        assert!(p2r_last_error_message().is_null());
+
+        let invalid = CString::new("{").unwrap();
+        let mut err = ptr::null_mut();
+        assert!(p2r_compiler_render(compiler, invalid.as_ptr(), &mut err).is_null());
+        let message = take_string(err);
+        assert_eq!(
+            CStr::from_ptr(p2r_last_error_message()).to_str().unwrap(),
+            message
+        );
+
+        p2r_compiler_free(compiler);
+    }
+}
+
+#[test]
+fn should_report_errors_through_ffi() {
+    let pattern = CString::new("/:foo(abc").unwrap();
+    unsafe {
+        let mut err = ptr::null_mut();
+        let matcher = p2r_matcher_new(pattern.as_ptr(), ptr::null(), &mut err);
+        assert!(matcher.is_null());
+        assert_eq!(take_string(err), "Unbalanced pattern at 5");
+
+        let matcher = p2r_matcher_new(ptr::null(), ptr::null(), &mut err);
+        assert!(matcher.is_null());
+        assert_eq!(take_string(err), "`pattern` is null");
+
+        assert!(!p2r_matcher_find(ptr::null(), ptr::null(), ptr::null_mut()));
+        assert_eq!(
+            CStr::from_ptr(p2r_last_error_message()).to_str().unwrap(),
+            "`matcher` is null"
+        );
+
+        p2r_matcher_free(ptr::null_mut());
+        p2r_compiler_free(ptr::null_mut());
+        p2r_string_free(ptr::null_mut());
+    }
+}
@@ -0,0 +1,1397 @@
+use std::{borrow::Cow, sync::Arc};
+
+use anyhow::Result;
+use path2regex::{
+    describe_tokens, Compiler, CompilerBuilder, CompilerOptions, DelimiterPolicy, EncodeError, Key,
+    ParamDescription, ParamValue, Parser, PathDescription, PathExample, RenderCase, RenderError,
+    RenderErrors, RenderOverrides, RenderPosition, RenderProblem, RenderProblemKind, RenderWarning,
+    Token,
+};
+use serde_json::json;
+
+#[test]
+fn should_build_and_render() -> Result<()> {
+    let path = CompilerBuilder::new("/user/:id").build_and_render(&json!({"id": 123}))?;
+    assert_eq!(path, "/user/123");
+    assert!(CompilerBuilder::new("/user/:id")
+        .build_and_render(&json!({}))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn should_list_keys() -> Result<()> {
+    let compiler = Compiler::new("/user/:id{-:tab}?{.json}?/(.*)")?;
+    let names = compiler
+        .keys()
+        .map(|key| key.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["id", "tab", "0"]);
+    Ok(())
+}
+
+#[test]
+fn should_render_to_writer() -> Result<()> {
+    struct ByteCounter(usize);
+
+    impl std::fmt::Write for ByteCounter {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+
+    let compiler = Compiler::new("/user/:id/:tab?")?;
+    let data = json!({"id": 123, "tab": "posts"});
+
+    let mut path = String::from("https://example.com");
+    compiler.render_to(&data, &mut path)?;
+    assert_eq!(path, "https://example.com/user/123/posts");
+
+    let mut counter = ByteCounter(0);
+    compiler.render_to(&data, &mut counter)?;
+    assert_eq!(counter.0, compiler.render(&data)?.len());
+
+    assert!(compiler.render_to(&json!({}), &mut counter).is_err());
+    Ok(())
+}
+
+#[test]
+fn should_render_many_params() -> Result<()> {
+    let path = (0..50).map(|i| format!("/:p{i}")).collect::<String>();
+    let data = (0..50)
+        .map(|i| (format!("p{i}"), json!(i)))
+        .collect::<serde_json::Map<_, _>>();
+    let expected = (0..50).map(|i| format!("/{i}")).collect::<String>();
+    assert_eq!(Compiler::new(path)?.render(&data.into())?, expected);
+    Ok(())
+}
+
+#[test]
+fn should_borrow_static_paths() -> Result<()> {
+    let compiler = Compiler::new("/about/team")?;
+    assert_eq!(compiler.is_static(), Some("/about/team"));
+    let path = compiler.render_cow(&json!({}))?;
+    assert!(matches!(path, Cow::Borrowed(_)));
+    assert_eq!(path, "/about/team");
+    assert_eq!(compiler.render(&json!({}))?, "/about/team");
+
+    let compiler = Compiler::new("/about/:name")?;
+    assert_eq!(compiler.is_static(), None);
+    let path = compiler.render_cow(&json!({"name": "team"}))?;
+    assert!(matches!(path, Cow::Owned(_)));
+    assert_eq!(path, "/about/team");
+    Ok(())
+}
+
+#[test]
+fn should_override_options_per_render() -> Result<()> {
+    let compiler = Compiler::new_with_options(
+        "/user/:id(\\d+)",
+        CompilerOptions {
+            validate: false,
+            ..Default::default()
+        },
+    )?;
+    let data = json!({"id": "abc"});
+    assert_eq!(compiler.render(&data)?, "/user/abc");
+    let validated = RenderOverrides {
+        validate: Some(true),
+        ..Default::default()
+    };
+    assert!(compiler.render_with_options(&data, validated).is_err());
+    assert_eq!(
+        compiler.render_with_options(&json!({"id": 42}), validated)?,
+        "/user/42"
+    );
+
+    let compiler = Compiler::new("/search/:query")?;
+    let data = json!({"query": "Hello World"});
+    let encoded = RenderOverrides {
+        encode: Some(|value, _| urlencoding::encode(value).into_owned()),
+        case: Some(RenderCase::Lower),
+        ..Default::default()
+    };
+    assert_eq!(
+        compiler.render_with_options(&data, encoded)?,
+        "/search/hello%20world"
+    );
+    assert_eq!(compiler.render(&data)?, "/search/Hello World");
+    Ok(())
+}
+
+#[test]
+fn should_report_render_warnings() -> Result<()> {
+    let compiler = Compiler::new("/user/:id{-:tab}?/:rest*")?;
+    let (path, warnings) = compiler.render_checked(&json!({"id": 1, "rest": [], "page": 2}))?;
+    assert_eq!(path, "/user/1");
+    assert_eq!(
+        warnings,
+        vec![
+            RenderWarning::OptionalParamSkipped {
+                key_name: "tab".to_owned()
+            },
+            RenderWarning::OptionalParamSkipped {
+                key_name: "rest".to_owned()
+            },
+            RenderWarning::UnusedParam {
+                key_name: "page".to_owned()
+            },
+        ]
+    );
+
+    let compiler = Compiler::new_with_options(
+        "/user/:id(\\d+)",
+        CompilerOptions {
+            validate: false,
+            ..Default::default()
+        },
+    )?;
+    let (path, warnings) = compiler.render_checked(&json!({"id": "me"}))?;
+    assert_eq!(path, "/user/me");
+    assert_eq!(
+        warnings,
+        vec![RenderWarning::ValueNotMatched {
+            key_name: "id".to_owned(),
+            value: "me".to_owned()
+        }]
+    );
+    assert!(Compiler::new("/user/:id")?
+        .render_checked(&json!({}))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn should_not_build_without_a_validation_regex() -> Result<()> {
+    let err = Compiler::new("/:id([)").err().unwrap();
+    assert_eq!(err.to_string(), "Invalid pattern \"[\" of \"id\"");
+    let compiler = Compiler::new_with_options(
+        "/:id([)",
+        CompilerOptions {
+            validate: false,
+            ..Default::default()
+        },
+    );
+    assert!(compiler.is_err());
+    Ok(())
+}
+
+#[test]
+fn should_render_the_default_of_missing_params() -> Result<()> {
+    let compiler = Compiler::new("/list/:page(\\d+)?=1")?;
+    assert_eq!(compiler.render(&json!({}))?, "/list/1");
+    assert_eq!(compiler.render(&json!({"page": 3}))?, "/list/3");
+
+    let compiler = Compiler::new("/list/:page?=first")?;
+    assert_eq!(compiler.render(&json!({}))?, "/list/first");
+
+    let err = Compiler::new("/list/:page(\\d+)?=one")?
+        .render(&json!({}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"page\" to match \"\\d+\", but got \"one\""
+    );
+    Ok(())
+}
+
+#[test]
+fn should_render_to_an_io_writer() -> Result<()> {
+    let compiler = Compiler::new("/user/:id{-:tab}?/:rest*")?;
+    for data in [
+        json!({"id": 1}),
+        json!({"id": "a", "tab": "posts", "rest": ["x", "y"]}),
+    ] {
+        let mut buffer = Vec::new();
+        compiler.render_write(&data, &mut buffer)?;
+        assert_eq!(String::from_utf8(buffer)?, compiler.render(&data)?);
+    }
+
+    let mut buffer = [0u8; 4];
+    let err = compiler
+        .render_write(&json!({"id": 123}), &mut &mut buffer[..])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<std::io::Error>().map(|e| e.kind()),
+        Some(std::io::ErrorKind::WriteZero)
+    );
+    assert_eq!(&buffer, b"/use");
+    Ok(())
+}
+
+#[test]
+fn should_generate_matching_examples() -> Result<()> {
+    for route in [
+        "/about/team",
+        "/user/:id",
+        "/user/:id(\\d+)/:tab?",
+        "/files/:path+",
+        "/docs/:path*{.:ext(md|html)}?",
+        "/:lang(en|fr|de)/:slug([a-z0-9-]{3,8})",
+        "/user/:id{-:tab}?{.json}?",
+        "/list/:page(\\d+)?=1",
+    ] {
+        let compiler = Compiler::new(route)?;
+        let matcher = path2regex::Matcher::new(route)?;
+        let examples = compiler.examples(6);
+        assert!(!examples.is_empty(), "{route}");
+        for example in examples.iter() {
+            assert!(example.placeholders.is_empty(), "{route}: {example:?}");
+            assert!(
+                matcher.find(&example.path).is_some(),
+                "{route}: {}",
+                example.path
+            );
+        }
+        let mut paths = examples
+            .iter()
+            .map(|example| &example.path)
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), examples.len(), "{route}");
+    }
+
+    let examples = Compiler::new("/user/:id(\\d+)/:tab?")?.examples(2);
+    assert_eq!(examples.len(), 2);
+    assert!(examples
+        .iter()
+        .any(|example| example.path.matches('/').count() == 3));
+    assert!(examples
+        .iter()
+        .any(|example| example.path.matches('/').count() == 2));
+    assert_eq!(Compiler::new("/about")?.examples(5).len(), 1);
+
+    let examples = Compiler::new("/at/:word(\\bx\\b)")?.examples(1);
+    assert_eq!(
+        examples,
+        vec![PathExample {
+            path: "/at/{word}".to_owned(),
+            placeholders: vec!["word".to_owned()],
+        }]
+    );
+    Ok(())
+}
+
+#[test]
+fn should_split_required_and_optional_keys() -> Result<()> {
+    let compiler = Compiler::new("/user/:id/:tab?/:a+/:b*{.json}?")?;
+    let names = |keys: Vec<&path2regex::Key>| {
+        keys.into_iter()
+            .map(|key| key.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    assert_eq!(names(compiler.required_keys()), "id,a");
+    assert_eq!(names(compiler.optional_keys()), "tab,b");
+    assert!(Compiler::new("/about")?.required_keys().is_empty());
+    Ok(())
+}
+
+#[test]
+fn should_append_leftover_data_as_a_query() -> Result<()> {
+    let compiler = CompilerBuilder::new("/users/:id")
+        .set_append_query(true)
+        .build()?;
+    assert_eq!(compiler.render(&json!({"id": 7}))?, "/users/7");
+    assert_eq!(
+        compiler.render(&json!({"id": 7, "tab": "posts", "page": 2}))?,
+        "/users/7?page=2&tab=posts"
+    );
+    assert_eq!(
+        compiler.render(&json!({"id": 7, "tag": ["a b", "c&d"], "all": true, "none": null}))?,
+        "/users/7?all=true&tag=a%20b&tag=c%26d"
+    );
+    let (path, warnings) = compiler.render_checked(&json!({"id": 7, "q": "x"}))?;
+    assert_eq!((path.as_str(), warnings.len()), ("/users/7?q=x", 0));
+
+    let err = compiler
+        .render(&json!({"id": 7, "filter": {"a": 1}}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected the query parameter \"filter\" to be a string, a number, a boolean or an array of them"
+    );
+    assert!(compiler
+        .render(&json!({"id": 7, "filter": [["a"]]}))
+        .is_err());
+
+    // Only leftovers
+    let compiler = CompilerBuilder::new("/search")
+        .set_append_query(true)
+        .build()?;
+    assert_eq!(compiler.render(&json!({}))?, "/search");
+    assert_eq!(compiler.render(&json!({"q": "ü"}))?, "/search?q=%C3%BC");
+
+    let compiler = CompilerBuilder::new("/search\\?v=1")
+        .set_append_query(true)
+        .build()?;
+    assert_eq!(compiler.render(&json!({"q": "x"}))?, "/search?v=1&q=x");
+
+    let compiler = Compiler::new("/users/:id")?;
+    assert_eq!(
+        compiler.render(&json!({"id": 7, "tab": "posts"}))?,
+        "/users/7"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_fail_to_build_with_an_invalid_pattern() -> Result<()> {
+    let err = Compiler::new("/users/:id((?!me)\\w+)").err().unwrap();
+    assert_eq!(
+        format!("{err:#}"),
+        "Invalid pattern \"(?!me)\\w+\" of \"id\": Unsupported \"(?!\" at 0 in the pattern \"(?!me)\\w+\": \
+         the Rust regex engine does not support lookahead, consider restructuring with ends_with or a custom decode"
+    );
+    let err = Compiler::new("/users/:id(\\p{Unknown})").err().unwrap();
+    assert!(err
+        .to_string()
+        .starts_with("Invalid pattern \"\\p{Unknown}\" of \"id\""));
+    Ok(())
+}
+
+#[test]
+fn should_validate_values_against_key_patterns() -> Result<()> {
+    let compiler = Compiler::new("/:id(\\d+)")?;
+    assert_eq!(compiler.render(&json!({"id": 123}))?, "/123");
+    let err = compiler.render(&json!({"id": "abc"})).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"id\" to match \"\\d+\", but got \"abc\""
+    );
+    assert!(compiler.render(&json!({"id": "12a"})).is_err());
+
+    // The default pattern doesn't match the delimiter
+    let compiler = Compiler::new("/:id")?;
+    assert_eq!(compiler.render(&json!({"id": "a.b"}))?, "/a.b");
+    assert!(compiler.render(&json!({"id": "a/b"})).is_err());
+    assert!(compiler.render(&json!({"id": "a#b"})).is_err());
+
+    // Every value of a repeated parameter is validated
+    let compiler = Compiler::new("/:ids(\\d+)+")?;
+    assert_eq!(compiler.render(&json!({"ids": [1, "2", 3]}))?, "/1/2/3");
+    let err = compiler.render(&json!({"ids": [1, "b", 3]})).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"ids\" to match \"\\d+\", but got \"b\""
+    );
+
+    let compiler = CompilerBuilder::new("/:id(\\d+)")
+        .set_validate(false)
+        .build()?;
+    assert_eq!(compiler.render(&json!({"id": "abc"}))?, "/abc");
+    Ok(())
+}
+
+#[test]
+fn should_agree_with_the_matcher_on_the_case_of_values() -> Result<()> {
+    for sensitive in [false, true] {
+        let compiler = CompilerBuilder::new("/:code([A-Z]{3})")
+            .set_sensitive(sensitive)
+            .build()?;
+        let matcher = path2regex::MatcherBuilder::new("/:code([A-Z]{3})")
+            .set_sensitive(sensitive)
+            .build()?;
+        for code in ["ABC", "abc", "aBc"] {
+            let rendered = compiler.render(&json!({ "code": code }));
+            let matched = matcher.find(format!("/{code}")).is_some();
+            assert_eq!(
+                rendered.is_ok(),
+                matched,
+                "{code} with sensitive={sensitive}"
+            );
+            assert_eq!(matched, !sensitive || code == "ABC");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn should_limit_the_length_of_the_rendered_path() -> Result<()> {
+    let compiler = CompilerBuilder::new("/files/:path*")
+        .set_max_rendered_length(16)
+        .build()?;
+    assert_eq!(compiler.render(&json!({"path": ["a", "b"]}))?, "/files/a/b");
+    assert_eq!(
+        compiler.render(&json!({"path": ["abcd", "efgh"]}))?,
+        "/files/abcd/efgh"
+    );
+
+    let err = compiler
+        .render(&json!({"path": vec!["abc"; 1000]}))
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<RenderError>(),
+        Some(&RenderError::PathTooLong {
+            limit: 16,
+            actual: 4006
+        })
+    );
+    assert_eq!(
+        err.to_string(),
+        "Expected the path to be at most 16 bytes long, but got 4006 bytes"
+    );
+
+    let mut out = String::new();
+    assert!(compiler
+        .render_to(&json!({"path": ["abcdefgh", "ijk"]}), &mut out)
+        .is_err());
+    assert!(out.len() <= 16);
+
+    let compiler = CompilerBuilder::new("/a/very/long/static/path")
+        .set_max_rendered_length(8)
+        .build()?;
+    assert!(compiler.render(&json!({})).is_err());
+    Ok(())
+}
+
+#[test]
+fn should_render_with_the_encoders() -> Result<()> {
+    use path2regex::encoders;
+
+    let compiler = CompilerBuilder::new("/users/:name")
+        .set_encode(encoders::uri_component)
+        .build()?;
+    assert_eq!(compiler.render(&json!({"name": "a b"}))?, "/users/a%20b");
+    assert_eq!(compiler.render(&json!({"name": "100%"}))?, "/users/100%25");
+    assert_eq!(
+        compiler.render(&json!({"name": "café"}))?,
+        "/users/caf%C3%A9"
+    );
+    assert_eq!(
+        compiler.render(&json!({"name": "it's(1)!"}))?,
+        "/users/it's(1)!"
+    );
+    // The value is encoded before it is validated against `[^/#?]+?`
+    assert_eq!(
+        compiler.render(&json!({"name": "a/b?c"}))?,
+        "/users/a%2Fb%3Fc"
+    );
+    assert!(Compiler::new("/users/:name")?
+        .render(&json!({"name": "a/b"}))
+        .is_err());
+
+    let compiler = CompilerBuilder::new("/files/:path(.*)")
+        .set_encode(encoders::uri_path_segment)
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"path": "my docs/a b.txt"}))?,
+        "/files/my%20docs/a%20b.txt"
+    );
+
+    let compiler = CompilerBuilder::new("/:name")
+        .set_encode(encoders::noop)
+        .build()?;
+    assert_eq!(compiler.render(&json!({"name": "a b"}))?, "/a b");
+    Ok(())
+}
+
+#[test]
+fn should_render_values_of_a_resolver() -> Result<()> {
+    use std::collections::HashMap;
+
+    let compiler = Compiler::new("/users/:id(\\d+)/:tab?")?;
+    let values = HashMap::from([("id", "7"), ("tab", "posts")]);
+    let path =
+        compiler.render_with(|key| values.get(key.name.as_str()).map(|v| ParamValue::Str(v)));
+    assert_eq!(path?, "/users/7/posts");
+    let path = compiler.render_with(|key| match key.name.as_str() {
+        "id" => Some(ParamValue::Str("8")),
+        _ => None,
+    });
+    assert_eq!(path?, "/users/8");
+
+    let err = compiler.render_with(|_| None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"id\" to be a string or a number"
+    );
+    let err = compiler
+        .render_with(|_| Some(ParamValue::Str("x")))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"id\" to match \"\\d+\", but got \"x\""
+    );
+
+    struct Row {
+        org: String,
+        id: u32,
+        tags: Vec<String>,
+    }
+    let row = Row {
+        org: "my org".to_owned(),
+        id: 42,
+        tags: vec!["a".to_owned(), "b".to_owned()],
+    };
+    let resolve = |key: &Key| match key.name.as_str() {
+        "org" => Some(ParamValue::Str(&row.org)),
+        "id" => Some(ParamValue::Display(&row.id)),
+        "tags" => Some(ParamValue::Many(Box::new(
+            row.tags.iter().map(String::as_str),
+        ))),
+        _ => None,
+    };
+    let compiler = CompilerBuilder::new("/:org/:id/:tags*")
+        .set_encode(path2regex::encoders::uri_component)
+        .build()?;
+    assert_eq!(compiler.render_with(resolve)?, "/my%20org/42/a/b");
+
+    let compiler = Compiler::new("/:org/:id")?;
+    assert_eq!(compiler.render_with(resolve)?, "/my org/42");
+    let compiler = Compiler::new("/:tags")?;
+    let err = compiler.render_with(resolve).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"tags\" to not repeat, but got an array"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_render_partial_as_a_pattern() -> Result<()> {
+    let path = "/org/:org/{item-:id(\\d+)}?/:rest*";
+    let compiler = Compiler::new(path)?;
+    let partial = compiler.render_partial(&json!({"org": "a+b"}))?;
+    assert_eq!(
+        partial,
+        "/org/a\\+b/{item-:id(\\d+)}?{/:rest([^/\\#\\?]+?)}*"
+    );
+
+    let mut expected: Vec<Token> = vec![];
+    for token in Parser::new().parse_str(path)? {
+        let token = match token {
+            Token::Key(key) if key.name == "org" => Token::Static("/a+b".to_owned()),
+            token => token,
+        };
+        match (expected.last_mut(), token) {
+            (Some(Token::Static(last)), Token::Static(text)) => last.push_str(&text),
+            (_, token) => expected.push(token),
+        }
+    }
+    assert_eq!(Parser::new().parse_str(&partial)?, expected);
+
+    let err = compiler.render_partial(&json!({"id": "x"})).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"id\" to match \"\\d+\", but got \"x\""
+    );
+    Ok(())
+}
+
+#[test]
+fn should_render_partial_like_render_with_all_data() -> Result<()> {
+    let compiler = Compiler::new("/org/:org/{item-:id(\\d+)}?/:rest*")?;
+    let data = json!({"org": "acme", "id": 7, "rest": ["a", "b"]});
+    assert_eq!(compiler.render_partial(&data)?, compiler.render(&data)?);
+    assert_eq!(compiler.render_partial(&data)?, "/org/acme/item-7/a/b");
+    Ok(())
+}
+
+#[test]
+fn should_prepend_the_prefix_segment() -> Result<()> {
+    let compiler = CompilerBuilder::new("/user/:id")
+        .with_prefix_segment("/api")
+        .with_prefix_segment("/v2")
+        .build()?;
+    assert_eq!(compiler.render(&json!({"id": 1}))?, "/api/v2/user/1");
+    assert_eq!(
+        compiler.render_partial(&json!({}))?,
+        "/api/v2/user{/:id([^/\\#\\?]+?)}"
+    );
+
+    let compiler = CompilerBuilder::new(":id")
+        .with_prefix_segment("/v:1")
+        .build()?;
+    assert_eq!(compiler.render(&json!({"id": 1}))?, "/v:11");
+    assert_eq!(compiler.is_static(), None);
+
+    let compiler = CompilerBuilder::new("/health")
+        .with_prefix_segment("/api")
+        .build()?;
+    assert_eq!(compiler.is_static(), Some("/api/health"));
+    Ok(())
+}
+
+#[test]
+fn should_report_every_problem_of_try_render() -> Result<()> {
+    let compiler = Compiler::new("/:org/:id(\\d+)/:tag/:flag/:rest*")?;
+    let data = json!({"id": "abc", "tag": ["a", "b"], "flag": true, "rest": ["x"]});
+    let err = compiler.try_render(&data).unwrap_err();
+    let problem = |key_name: &str, kind, value: Option<&str>, message: &str| RenderProblem {
+        key_name: key_name.to_owned(),
+        kind,
+        value: value.map(str::to_owned),
+        message: message.to_owned(),
+        position: Some(RenderPosition {
+            index: ["org", "id", "tag", "flag"]
+                .iter()
+                .position(|name| *name == key_name)
+                .unwrap(),
+            prefix: "/".to_owned(),
+            suffix: "".to_owned(),
+            rendered: "".to_owned(),
+        }),
+    };
+    assert_eq!(
+        err,
+        RenderErrors {
+            problems: vec![
+                problem(
+                    "org",
+                    RenderProblemKind::Missing,
+                    None,
+                    "Expected \"org\" to be a string or a number"
+                ),
+                problem(
+                    "id",
+                    RenderProblemKind::PatternMismatch,
+                    Some("abc"),
+                    "Expected all \"id\" to match \"\\d+\", but got \"abc\""
+                ),
+                problem(
+                    "tag",
+                    RenderProblemKind::UnexpectedArray,
+                    Some("[\"a\",\"b\"]"),
+                    "Expected \"tag\" to not repeat, but got an array"
+                ),
+                problem(
+                    "flag",
+                    RenderProblemKind::WrongType,
+                    Some("true"),
+                    "Expected \"flag\" to be a string or a number"
+                ),
+            ],
+        }
+    );
+    assert_eq!(err.to_string().lines().count(), 4);
+    assert_eq!(
+        compiler.render(&data).unwrap_err().to_string(),
+        "Expected \"org\" to be a string or a number"
+    );
+
+    let data = json!({"org": "o", "id": 1, "tag": "t", "flag": "f"});
+    assert_eq!(compiler.try_render(&data)?, compiler.render(&data)?);
+
+    let compiler = CompilerBuilder::new("/:org")
+        .set_max_rendered_length(3)
+        .build()?;
+    let err = compiler.try_render(&json!({"org": "acme"})).unwrap_err();
+    assert_eq!(err.problems[0].kind, RenderProblemKind::Other);
+    assert_eq!(
+        err.to_string(),
+        "Expected the path to be at most 3 bytes long, but got 5 bytes"
+    );
+
+    let compiler = CompilerBuilder::new("/users/:user_id")
+        .set_deny_unknown_keys(true)
+        .build()?;
+    let err = compiler.try_render(&json!({"userid": 7})).unwrap_err();
+    let kinds = err
+        .problems
+        .iter()
+        .map(|problem| (problem.key_name.as_str(), problem.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ("user_id", RenderProblemKind::Missing),
+            ("", RenderProblemKind::Other)
+        ]
+    );
+    assert_eq!(
+        err.problems[1].message,
+        "Unknown parameter \"userid\" (did you mean \"user_id\"?)"
+    );
+
+    let compiler = CompilerBuilder::new("/search/:q")
+        .set_append_query(true)
+        .build()?;
+    let data = json!({"q": "a", "page": 2, "tags": ["x", "y"]});
+    assert_eq!(compiler.try_render(&data)?, compiler.render(&data)?);
+    let err = compiler
+        .try_render(&json!({"q": "a", "page": {"n": 2}}))
+        .unwrap_err();
+    assert_eq!(err.problems.len(), 1);
+    assert_eq!(err.problems[0].kind, RenderProblemKind::Other);
+    Ok(())
+}
+
+#[test]
+fn should_render_the_defaults_of_absent_parameters() -> Result<()> {
+    let compiler = CompilerBuilder::new("/list/:page?/:tags*")
+        .set_default("page", 1)
+        .set_default("tags", json!(["a", "b c"]))
+        .set_encode(path2regex::encoders::uri_component)
+        .build()?;
+    assert_eq!(compiler.render(&json!({}))?, "/list/1/a/b%20c");
+    assert_eq!(
+        compiler.render(&json!({"page": null, "tags": null}))?,
+        "/list"
+    );
+    assert_eq!(
+        compiler.render(&json!({"page": 3, "tags": ["x"]}))?,
+        "/list/3/x"
+    );
+    assert_eq!(
+        compiler.render_with(|_| None::<ParamValue<'_>>)?,
+        "/list/1/a/b%20c"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_not_build_with_invalid_defaults() {
+    let err = CompilerBuilder::new("/list/:page(\\d+)?")
+        .set_default("page", "first")
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "Invalid default of \"page\"");
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Expected all \"page\" to match \"\\d+\", but got \"first\""
+    );
+
+    let err = CompilerBuilder::new("/list/:page?")
+        .set_default("size", 10)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"size\" to be a parameter of the path to have a default"
+    );
+}
+
+#[test]
+fn should_join_repeated_values_with_the_separator() -> Result<()> {
+    let data = json!({"tags": ["a", "b", "c"]});
+    let compiler = Compiler::new("/tags/:tags+")?;
+    assert_eq!(compiler.render(&data)?, "/tags/a/b/c");
+
+    let compiler = CompilerBuilder::new("/tags/:tags+")
+        .set_repeat_separator(",")
+        .build()?;
+    assert_eq!(compiler.render(&data)?, "/tags/a,b,c");
+    let compiler = CompilerBuilder::new("/tags/{:tags}+.json")
+        .set_repeat_separator(",")
+        .build()?;
+    assert_eq!(compiler.render(&data)?, "/tags/a,b,c.json");
+
+    let compiler = CompilerBuilder::new("/:ids(\\d+)+/:tags+")
+        .set_repeat_separator(",")
+        .set_key_repeat_separator("tags", ";")
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"ids": [1, 2], "tags": ["a", "b"]}))?,
+        "/1,2/a;b"
+    );
+    let err = compiler
+        .render(&json!({"ids": [1, "x"], "tags": ["a"]}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"ids\" to match \"\\d+\", but got \"x\""
+    );
+    Ok(())
+}
+
+#[test]
+fn should_round_trip_repeated_values_with_the_separator() -> Result<()> {
+    let path = "/tags/:tags+/:ids*";
+    let compiler = CompilerBuilder::new(path)
+        .set_repeat_separator(",")
+        .set_key_repeat_separator("ids", "~")
+        .build()?;
+    let matcher = path2regex::MatcherBuilder::new(path)
+        .set_repeat_separator(",")
+        .set_key_repeat_separator("ids", "~")
+        .build()?;
+    let data = json!({"tags": ["a", "b", "c"], "ids": ["1", "2"]});
+    let rendered = compiler.render(&data)?;
+    assert_eq!(rendered, "/tags/a,b,c/1~2");
+    assert_eq!(matcher.find(&rendered).unwrap().params, data);
+    Ok(())
+}
+
+#[test]
+fn should_deny_unknown_keys() -> Result<()> {
+    let compiler = CompilerBuilder::new("/users/:user_id/:tab?")
+        .set_deny_unknown_keys(true)
+        .build()?;
+    let err = compiler.render(&json!({"userid": 7})).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unknown parameter \"userid\" (did you mean \"user_id\"?)"
+    );
+    let err = compiler
+        .render(&json!({"user_id": 7, "page": 2, "tabs": "posts"}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unknown parameters \"page\", \"tabs\" (did you mean \"tab\"?)"
+    );
+    assert_eq!(compiler.render(&json!({"user_id": 7}))?, "/users/7");
+
+    let compiler = CompilerBuilder::new("/health")
+        .set_deny_unknown_keys(true)
+        .build()?;
+    let err = compiler.render(&json!({"verbose": true})).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown parameter \"verbose\"");
+
+    let compiler = Compiler::new("/users/:user_id")?;
+    assert_eq!(
+        compiler.render(&json!({"user_id": 7, "userid": 8}))?,
+        "/users/7"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_append_unknown_keys_as_query_when_denied() -> Result<()> {
+    let compiler = CompilerBuilder::new("/users/:user_id")
+        .set_deny_unknown_keys(true)
+        .set_append_query(true)
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"user_id": 7, "page": 2}))?,
+        "/users/7?page=2"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_stringify_booleans_with_the_hook() -> Result<()> {
+    let path = "/flags/:enabled(true|false)/:tag?";
+    let compiler = Compiler::new(path)?;
+    let err = compiler.render(&json!({"enabled": true})).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"enabled\" to be a string or a number"
+    );
+
+    let compiler = CompilerBuilder::new(path)
+        .set_stringify(path2regex::stringify_bool)
+        .build()?;
+    assert_eq!(compiler.render(&json!({"enabled": false}))?, "/flags/false");
+    assert_eq!(
+        compiler.render(&json!({"enabled": true, "tag": null}))?,
+        "/flags/true"
+    );
+    let err = compiler
+        .render(&json!({"enabled": {"on": true}}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"enabled\" to be a string or a number"
+    );
+
+    let compiler = CompilerBuilder::new("/:flags(true|false)+")
+        .set_stringify(path2regex::stringify_bool)
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"flags": [true, "false"]}))?,
+        "/true/false"
+    );
+    let err = compiler
+        .render(&json!({"flags": [true, null]}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"flags\" to be an array containing only strings or numbers"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_name_the_path_of_an_invalid_template() -> Result<()> {
+    let err = Compiler::new_checked("/users/:id(").err().unwrap();
+    assert_eq!(err.to_string(), "Invalid route template '/users/:id('");
+    assert_eq!(
+        format!("{err:#}"),
+        format!(
+            "Invalid route template '/users/:id(': {}",
+            Compiler::new("/users/:id(").err().unwrap()
+        )
+    );
+    let compiler = Compiler::new_checked("/users/:id")?;
+    assert_eq!(compiler.render(&json!({"id": 1}))?, "/users/1");
+    Ok(())
+}
+
+#[test]
+fn should_look_up_dotted_names_in_nested_data() -> Result<()> {
+    let path = "/orgs/:\"org.slug\"/users/:\"user.id\"(\\d+)/:\"items.0\"?";
+    let data = json!({"user": {"id": 7}, "org": {"slug": "acme"}, "items": ["first"]});
+    let compiler = CompilerBuilder::new(path)
+        .set_nested_lookup(true)
+        .set_deny_unknown_keys(true)
+        .build()?;
+    assert_eq!(compiler.render(&data)?, "/orgs/acme/users/7/first");
+
+    let err = compiler
+        .render(&json!({"org": {"slug": "acme"}}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"user.id\" to be a string or a number"
+    );
+    let err = compiler
+        .render(&json!({"org": {"slug": "acme"}, "user": {"id": "me"}}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"user.id\" to match \"\\d+\", but got \"me\""
+    );
+
+    let compiler = Compiler::new(path)?;
+    let flat = json!({"org.slug": "acme", "user.id": 7});
+    assert_eq!(compiler.render(&flat)?, "/orgs/acme/users/7");
+    assert!(compiler.render(&data).is_err());
+    assert_eq!(
+        compiler.render_partial(&json!({"org.slug": "acme"}))?,
+        "/orgs/acme/users{/:\"user.id\"(\\d+)}{/:\"items.0\"([^/\\#\\?]+?)}?"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_report_the_position_of_a_render_failure() -> Result<()> {
+    let compiler = Compiler::new("/org/:org/team/:team/{user-:user(\\d+).}/:tab/:page")?;
+    let data = json!({"org": "acme", "team": "core", "user": "me", "tab": "posts", "page": 2});
+    let err = compiler.render(&data).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"user\" to match \"\\d+\", but got \"me\""
+    );
+    assert_eq!(
+        err.downcast_ref::<RenderError>(),
+        Some(&RenderError::InvalidParam {
+            key_name: "user".to_owned(),
+            position: RenderPosition {
+                index: 5,
+                prefix: "user-".to_owned(),
+                suffix: ".".to_owned(),
+                rendered: "/org/acme/team/core/".to_owned(),
+            },
+            partial_path: "/org/acme/team/core/".to_owned(),
+            error: Arc::new(anyhow::anyhow!(err.to_string())),
+        })
+    );
+    // The message is not repeated by the alternate format showing the causes
+    assert_eq!(format!("{err:#}"), err.to_string());
+
+    let err = compiler.try_render(&data).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"user\" to match \"\\d+\", but got \"me\" \
+         (at token 5 between \"user-\" and \".\", after \"/org/acme/team/core/\")"
+    );
+
+    let compiler = Compiler::new("/:long/:id(\\d+)")?;
+    let err = compiler
+        .render(&json!({"long": "x".repeat(100), "id": "me"}))
+        .unwrap_err();
+    match err.downcast_ref::<RenderError>() {
+        Some(RenderError::InvalidParam {
+            position,
+            partial_path,
+            ..
+        }) => {
+            assert_eq!(position.rendered, format!("...{}", "x".repeat(64)));
+            assert_eq!(partial_path, &format!("/{}", "x".repeat(100)));
+        }
+        other => panic!("{other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn should_render_with_the_key_encoders() -> Result<()> {
+    let compiler = CompilerBuilder::new("/posts/:slug/search/:q/:tags*")
+        .set_encode(path2regex::encoders::uri_component)
+        .set_key_encoder("slug", |x, _| x.replace(' ', "-").to_lowercase())
+        .set_key_encoder("tags", |x, _| x.to_uppercase())
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"slug": "Hello World", "q": "a b&c", "tags": ["x", "y"]}))?,
+        "/posts/hello-world/search/a%20b%26c/X/Y"
+    );
+    // The encoded value is validated
+    assert!(compiler.render(&json!({"slug": "a/b", "q": "c"})).is_err());
+    Ok(())
+}
+
+#[test]
+fn should_not_build_with_an_encoder_of_an_unknown_key() {
+    let err = CompilerBuilder::new("/posts/:slug")
+        .set_key_encoder("title", |x, _| x.to_owned())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"title\" to be a parameter of the path to have an encoder"
+    );
+}
+
+#[test]
+fn should_report_the_partial_path_of_a_render_failure() -> Result<()> {
+    let err = Compiler::new("/user/:id/posts/:post")?
+        .render(&json!({"post": 1}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"id\" to be a string or a number"
+    );
+    match err.downcast_ref::<RenderError>() {
+        Some(RenderError::InvalidParam { partial_path, .. }) => {
+            assert_eq!(partial_path, "/user");
+        }
+        other => panic!("{other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn should_render_with_the_fallible_encoders() -> Result<()> {
+    fn reject_traversal(value: &str, _: &Key) -> Result<String, EncodeError> {
+        if value.split('/').any(|segment| segment == "..") {
+            return Err(EncodeError::new("path traversal is not allowed"));
+        }
+        Ok(value.to_owned())
+    }
+
+    let compiler = CompilerBuilder::new("/files/:path(.*)")
+        .set_try_encode(reject_traversal)
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"path": "docs/readme.md"}))?,
+        "/files/docs/readme.md"
+    );
+    let err = compiler.render(&json!({"path": "../etc"})).unwrap_err();
+    assert_eq!(err.to_string(), "Failed to encode \"path\"");
+    assert_eq!(
+        format!("{err:#}"),
+        "Failed to encode \"path\": path traversal is not allowed"
+    );
+    assert_eq!(
+        err.root_cause().downcast_ref::<EncodeError>(),
+        Some(&EncodeError::new("path traversal is not allowed"))
+    );
+    match err.downcast_ref::<RenderError>() {
+        Some(RenderError::InvalidParam { error, .. }) => {
+            assert!(error.downcast_ref::<EncodeError>().is_some());
+        }
+        other => panic!("{other:?}"),
+    }
+
+    let compiler = CompilerBuilder::new("/:root/files/:path(.*)")
+        .set_key_try_encoder("path", reject_traversal)
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"root": "..", "path": "a"}))?,
+        "/../files/a"
+    );
+    assert!(compiler
+        .render(&json!({"root": "home", "path": "a/../b"}))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn should_get_the_template_string() -> Result<()> {
+    for path in [
+        "/users/:id(\\d+)/:tab?",
+        "/files/:path*",
+        "/{a\\:b-:name}+.json",
+        "/static\\(1\\)",
+        "/:\"user.id\"",
+        "/:page?\\=x",
+        "/list/:page?=1\\=2/items",
+    ] {
+        let compiler = Compiler::new(path)?;
+        let template = compiler.template_string();
+        assert_eq!(
+            Parser::new().parse_str(&template)?,
+            Parser::new().parse_str(path)?,
+            "{template}"
+        );
+        assert_eq!(
+            Compiler::new(template.as_str())?.template_string(),
+            template
+        );
+    }
+    assert_eq!(
+        Compiler::new("/users/:id")?.template_string(),
+        "/users{/:id([^/\\#\\?]+?)}"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_describe_the_parameters() -> Result<()> {
+    let description =
+        Compiler::new("/users/:id(\\d+)/(\\w+)/:tab?=posts{-:tags}*/:rest+{.json}?")?.describe();
+    assert_eq!(
+        description.skeleton,
+        "/users/{id}/{0}[/{tab}][-{tags}]/{rest}[.json]"
+    );
+    assert_eq!(
+        description.params,
+        vec![
+            ParamDescription {
+                name: "id".to_owned(),
+                required: true,
+                repeatable: false,
+                pattern: "\\d+".to_owned(),
+                prefix: "/".to_owned(),
+                suffix: "".to_owned(),
+                default: None,
+            },
+            ParamDescription {
+                name: "0".to_owned(),
+                required: true,
+                repeatable: false,
+                pattern: "\\w+".to_owned(),
+                prefix: "/".to_owned(),
+                suffix: "".to_owned(),
+                default: None,
+            },
+            ParamDescription {
+                name: "tab".to_owned(),
+                required: false,
+                repeatable: false,
+                pattern: "[^/\\#\\?]+?".to_owned(),
+                prefix: "/".to_owned(),
+                suffix: "".to_owned(),
+                default: Some("posts".to_owned()),
+            },
+            ParamDescription {
+                name: "tags".to_owned(),
+                required: false,
+                repeatable: true,
+                pattern: "[^/\\#\\?]+?".to_owned(),
+                prefix: "-".to_owned(),
+                suffix: "".to_owned(),
+                default: None,
+            },
+            ParamDescription {
+                name: "rest".to_owned(),
+                required: true,
+                repeatable: true,
+                pattern: "[^/\\#\\?]+?".to_owned(),
+                prefix: "/".to_owned(),
+                suffix: "".to_owned(),
+                default: None,
+            },
+        ]
+    );
+    assert_eq!(description.required_param_names(), ["id", "0", "rest"]);
+    assert_eq!(description.optional_param_names(), ["tab", "tags"]);
+    assert_eq!(
+        describe_tokens(&Parser::new().parse_str("/users/:id(\\d+)")?),
+        PathDescription {
+            skeleton: "/users/{id}".to_owned(),
+            params: vec![description.params[0].clone()],
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn should_render_the_delimiters_in_values_by_policy() -> Result<()> {
+    let build = |policy| {
+        CompilerBuilder::new("/files/:name(.+)/:tags(.+)*")
+            .set_delimiter_in_value(policy)
+            .build()
+    };
+    let data = json!({"name": "a/b", "tags": ["x", "y?z"]});
+
+    assert_eq!(
+        build(DelimiterPolicy::Allow)?.render(&data)?,
+        "/files/a/b/x/y?z"
+    );
+
+    let compiler = build(DelimiterPolicy::Reject)?;
+    assert_eq!(
+        compiler.render(&data).unwrap_err().to_string(),
+        "Expected \"name\" to not contain the delimiter \"/\", but got \"a/b\""
+    );
+    // The repeated parameters span many segments
+    assert_eq!(
+        compiler.render(&json!({"name": "a", "tags": ["x", "y?z"]}))?,
+        "/files/a/x/y?z"
+    );
+
+    assert_eq!(
+        build(DelimiterPolicy::Encode)?.render(&data)?,
+        "/files/a%2Fb/x/y%3Fz"
+    );
+    Ok(())
+}
+
+#[cfg(feature = "percent-encoding")]
+#[test]
+fn should_match_back_the_values_with_encoded_delimiters() -> Result<()> {
+    use path2regex::{Matcher, MatcherOptions};
+
+    let path = CompilerBuilder::new("/files/:name")
+        .set_delimiter_in_value(DelimiterPolicy::Encode)
+        .build()?
+        .render(&json!({"name": "a%2Fb/c#d"}))?;
+    assert_eq!(path, "/files/a%252Fb%2Fc%23d");
+    let matcher =
+        Matcher::new_with_options("/files/:name", MatcherOptions::decode_percent_encoded())?;
+    assert_eq!(
+        matcher.find(&path).unwrap().params,
+        json!({"name": "a%2Fb/c#d"})
+    );
+    Ok(())
+}
+
+#[test]
+fn should_render_the_numbers_by_format() -> Result<()> {
+    use path2regex::number_formats;
+
+    let compiler = Compiler::new("/items/:id(\\d+)/:page")?;
+    assert_eq!(
+        compiler.render(&json!({"id": 2.0, "page": 1.5}))?,
+        "/items/2/1.5"
+    );
+    assert_eq!(
+        compiler.render(&json!({"id": u64::MAX, "page": i64::MIN}))?,
+        "/items/18446744073709551615/-9223372036854775808"
+    );
+    assert!(compiler.render(&json!({"id": 1e20, "page": 1})).is_err());
+
+    let compiler = CompilerBuilder::new("/items/:id(\\d+)/:tags*")
+        .set_number_format(number_formats::integer_lossless)
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"id": 1e20, "tags": [3.0, -0.5]}))?,
+        "/items/100000000000000000000/3/-0.5"
+    );
+    assert_eq!(
+        compiler
+            .render(&json!({"id": 1.5}))
+            .unwrap_err()
+            .to_string(),
+        "Expected all \"id\" to match \"\\d+\", but got \"1.5\""
+    );
+
+    let compiler = CompilerBuilder::new("/items/:id")
+        .set_number_format(|value, _| format!("{:04}", value.as_u64().unwrap_or_default()))
+        .set_append_query(true)
+        .build()?;
+    assert_eq!(
+        compiler.render(&json!({"id": 7, "page": 2}))?,
+        "/items/0007?page=0002"
+    );
+    Ok(())
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn should_render_the_numbers_with_arbitrary_precision() -> Result<()> {
+    use path2regex::number_formats;
+
+    let data: serde_json::Value = serde_json::from_str(
+        r#"{"id": 123456789012345678901234567890, "min": -9223372036854775808, "max": 18446744073709551615, "whole": 12.000}"#,
+    )?;
+    let compiler = CompilerBuilder::new("/:id/:min/:max/:whole")
+        .set_number_format(number_formats::integer_lossless)
+        .build()?;
+    assert_eq!(
+        compiler.render(&data)?,
+        "/123456789012345678901234567890/-9223372036854775808/18446744073709551615/12"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_parse_to_compiler() -> Result<()> {
+    let compiler = Parser::parse_to_compiler("/user/:id", CompilerOptions::default())?;
+    assert_eq!(compiler.render(&json!({"id": 1}))?, "/user/1");
+
+    let options = CompilerOptions {
+        delimiter: ".".into(),
+        prefixes: ".".to_owned(),
+        ..Default::default()
+    };
+    let compiler = Parser::parse_to_compiler("api.:version.:name", options)?;
+    assert_eq!(
+        compiler.render(&json!({"version": "v1", "name": "users"}))?,
+        "api.v1.users"
+    );
+    assert!(Parser::parse_to_compiler("/user/:", CompilerOptions::default()).is_err());
+    Ok(())
+}
+
+#[test]
+fn should_render_many_rows() -> Result<()> {
+    let compiler = CompilerBuilder::new("/posts/:year(\\d+)/:slug/:tags*")
+        .set_append_query(true)
+        .build()?;
+    let rows = (0..100)
+        .map(|i| json!({"year": 2000 + i, "slug": format!("post-{i}"), "tags": ["a", "b"], "page": i % 3}))
+        .collect::<Vec<_>>();
+    let expected = rows
+        .iter()
+        .map(|row| compiler.render(row))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(compiler.render_many(&rows).unwrap(), expected);
+
+    let mut paths = vec![];
+    compiler
+        .render_each(&rows, |i, path| paths.push((i, path.to_owned())))
+        .unwrap();
+    assert_eq!(paths, expected.into_iter().enumerate().collect::<Vec<_>>());
+
+    let rows = [
+        json!({"year": 2020, "slug": "a"}),
+        json!({"year": "last", "slug": "b"}),
+        json!({"slug": "c"}),
+    ];
+    let (i, err) = compiler.render_many(&rows).unwrap_err();
+    assert_eq!(i, 1);
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"year\" to match \"\\d+\", but got \"last\""
+    );
+    let mut rendered = 0;
+    let (i, _) = compiler
+        .render_each(&rows, |_, _| rendered += 1)
+        .unwrap_err();
+    assert_eq!((i, rendered), (1, 1));
+
+    // The errors of a row only hold the context of that row
+    let (_, err) = compiler.render_many(&rows).unwrap_err();
+    let expected = compiler.render(&rows[1]).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<RenderError>(),
+        expected.downcast_ref::<RenderError>()
+    );
+    match err.downcast_ref::<RenderError>() {
+        Some(RenderError::InvalidParam { position, .. }) => {
+            assert_eq!(position.rendered, "/posts")
+        }
+        err => panic!("unexpected error {err:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn should_mark_the_optional_groups_of_the_skeleton() -> Result<()> {
+    let skeleton = |path: &str| -> Result<String> { Ok(Compiler::new(path)?.describe().skeleton) };
+    assert_eq!(skeleton("/files/:path{.json}?")?, "/files/{path}[.json]");
+    assert_eq!(skeleton("/files/:path{.json}")?, "/files/{path}.json");
+    assert_eq!(skeleton("/files{/list}*")?, "/files[/list]");
+    assert_eq!(skeleton("/x{/:y}?")?, "/x[/{y}]");
+    assert_eq!(skeleton("/files/:path*")?, "/files[/{path}]");
+    assert_eq!(skeleton("/files/:path+")?, "/files/{path}");
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use anyhow::Result;
+use path2regex::{
+    CompilerOptions, Matcher, MatcherOptions, Parser, ParserOptions, PathRegex, PathRegexOptions,
+};
+
+#[test]
+fn should_pretty_print_tokens() -> Result<()> {
+    let tokens = Parser::new().parse_str("/user/:id(\\d+)?")?;
+    assert_eq!(
+        format!("{tokens:#?}"),
+        r#"[
+    Static(
+        "/user",
+    ),
+    Key(
+        Key {
+            name: "id",
+            prefix: "/",
+            suffix: "",
+            pattern: "\\d+",
+            modifier: "?",
+        },
+    ),
+]"#
+    );
+    assert_eq!(
+        format!("{tokens:?}"),
+        r#"[Static("/user"), Key(Key { name: "id", prefix: "/", suffix: "", pattern: "\\d+", modifier: "?" })]"#
+    );
+    Ok(())
+}
+
+#[test]
+fn should_display_tokens_as_path() -> Result<()> {
+    let tokens = Parser::new().parse_str("/user/:id(\\d+)?/(.*)/\\:raw")?;
+    let path = tokens.iter().map(|x| x.to_string()).collect::<String>();
+    assert_eq!(path, "/user{/:id(\\d+)}?{/(.*)}/\\:raw");
+    assert_eq!(Parser::new().parse_str(path)?, tokens);
+    Ok(())
+}
+
+#[test]
+fn should_debug_path_regex() -> Result<()> {
+    let re = PathRegex::new("/user/:id")?;
+    assert_eq!(
+        format!("{re:#?}"),
+        r#"PathRegex {
+    source: Some(
+        "/user/:id",
+    ),
+    pattern: "(?i)^/user(?:/([^/\\#\\?]+?))(?P<TRAILING_DELIMITER>[/\\#\\?])?$$",
+    keys: [
+        Key {
+            name: "id",
+            prefix: "/",
+            suffix: "",
+            pattern: "[^/\\#\\?]+?",
+            modifier: "",
+        },
+    ],
+}"#
+    );
+    assert_eq!(
+        re.to_string(),
+        "(?i)^/user(?:/([^/\\#\\?]+?))(?P<TRAILING_DELIMITER>[/\\#\\?])?$$"
+    );
+
+    let re = PathRegex::new(regex::Regex::new("^/test$")?)?;
+    assert_eq!(
+        format!("{re:?}"),
+        r#"PathRegex { source: None, pattern: "^/test$", keys: [] }"#
+    );
+    Ok(())
+}
+
+#[test]
+fn should_debug_match_result() -> Result<()> {
+    let matcher = Matcher::new("/user/:id")?;
+    let result = matcher.find("/user/1").unwrap();
+    assert_eq!(
+        format!("{result:#?}"),
+        r#"MatchResult {
+    path: "/user/1",
+    index: 0,
+    params: {
+      "id": "1"
+    },
+    trailing_delimiter: None,
+    fragment: None,
+}"#
+    );
+    assert_eq!(
+        format!("{result:?}"),
+        r#"MatchResult { path: "/user/1", index: 0, params: {"id":"1"}, trailing_delimiter: None, fragment: None }"#
+    );
+    Ok(())
+}
+
+#[test]
+fn should_display_options() {
+    assert_eq!(
+        ParserOptions::default().to_string(),
+        r#"delimiter="/#?", prefixes="./""#
+    );
+    assert_eq!(
+        PathRegexOptions::default().to_string(),
+        r#"delimiter="/#?", prefixes="./", sensitive=false, strict=false, end=true, start=true, ends_with="""#
+    );
+    assert_eq!(
+        MatcherOptions::default().to_string(),
+        r#"delimiter="/#?", prefixes="./", sensitive=false, strict=false, end=true, start=true, ends_with="""#
+    );
+    assert_eq!(
+        CompilerOptions::default().to_string(),
+        r#"delimiter="/#?", prefixes="./", sensitive=false, validate=true"#
+    );
+}
+
+#[test]
+fn should_debug_the_defaults_in_order() {
+    let mut options = CompilerOptions::default();
+    for name in ["c", "a", "b", "e", "d"] {
+        options.defaults.insert(name.to_owned(), name.into());
+    }
+    assert!(format!("{options:?}").contains(
+        r#"defaults: {"a": String("a"), "b": String("b"), "c": String("c"), "d": String("d"), "e": String("e")}"#
+    ));
+}
+
+#[test]
+fn should_debug_the_repeat_separators_in_order() {
+    let separators = ["c", "a", "b", "e", "d"]
+        .iter()
+        .map(|name| (name.to_string(), ",".to_owned()))
+        .collect::<std::collections::HashMap<_, _>>();
+    let expected = r#"repeat_separators: {"a": ",", "b": ",", "c": ",", "d": ",", "e": ","}"#;
+    let options = PathRegexOptions {
+        repeat_separators: separators.clone(),
+        ..Default::default()
+    };
+    assert!(format!("{options:?}").contains(expected));
+    let options = MatcherOptions {
+        repeat_separators: separators.clone(),
+        ..Default::default()
+    };
+    assert!(format!("{options:?}").contains(expected));
+    let options = CompilerOptions {
+        repeat_separators: separators,
+        ..Default::default()
+    };
+    assert!(format!("{options:?}").contains(expected));
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "cache")]
+
+use path2regex::{lazy_compiler, lazy_matcher, lazy_path_regex, Matcher, PathRegex};
+use serde_json::json;
+
+fn user_re() -> &'static PathRegex {
+    lazy_path_regex!("/user/:id")
+}
+
+fn user_matcher() -> &'static Matcher {
+    lazy_matcher!("/user/:id")
+}
+
+#[test]
+fn should_build_once_on_first_use() {
+    assert!(std::ptr::eq(user_re(), user_re()));
+    assert!(user_re().is_match("/user/123"));
+    assert!(std::ptr::eq(user_matcher(), user_matcher()));
+    let result = user_matcher().find("/user/123").unwrap();
+    assert_eq!(result.params, json!({"id": "123"}));
+}
+
+#[test]
+fn should_build_with_options() {
+    let compiler = lazy_compiler!(
+        "/user/:id",
+        path2regex::CompilerOptions {
+            encode: |value, _| value.to_uppercase(),
+            ..Default::default()
+        }
+    );
+    assert_eq!(compiler.render(&json!({"id": "abc"})).unwrap(), "/user/ABC");
+}
@@ -0,0 +1,663 @@
+use std::{borrow::Cow, time::Duration};
+
+use anyhow::Result;
+use path2regex::{
+    DecodeError, FragmentMode, MatchResult, MatchTimeout, Matcher, MatcherBuilder, MatcherOptions,
+};
+use serde_json::json;
+
+#[test]
+fn should_keep_percent_encoded_params_by_default() -> Result<()> {
+    let matcher = Matcher::new_with_options("/:name", MatcherOptions::default())?;
+    let result = matcher.find("/hello%20world").unwrap();
+    assert_eq!(result.params, json!({"name": "hello%20world"}));
+    Ok(())
+}
+
+#[cfg(feature = "percent-encoding")]
+#[test]
+fn should_decode_percent_encoded_params() -> Result<()> {
+    let matcher = Matcher::new_with_options("/:name", MatcherOptions::decode_percent_encoded())?;
+    let result = matcher.find("/hello%20world").unwrap();
+    assert_eq!(result.params, json!({"name": "hello world"}));
+    let result = matcher.find("/%FF").unwrap();
+    assert_eq!(result.params, json!({"name": "%FF"}));
+    Ok(())
+}
+
+#[test]
+fn should_return_cached_results() -> Result<()> {
+    let uncached = Matcher::new("/user/:id")?;
+    let cached = Matcher::new_with_options(
+        "/user/:id",
+        MatcherOptions {
+            cache_size: Some(2),
+            ..Default::default()
+        },
+    )?;
+    for path in [
+        "/user/1", "/user/2", "/user/1", "/user/3", "/user/2", "/nope", "/nope",
+    ] {
+        assert_eq!(cached.find(path), uncached.find(path), "{path}");
+    }
+    let long = format!("/user/{}", "1".repeat(1024));
+    assert_eq!(cached.find(&long), uncached.find(&long));
+
+    // The cache grows with the paths, not up front
+    let unbounded = MatcherBuilder::new("/user/:id")
+        .set_cache_size(usize::MAX)
+        .build()?;
+    assert_eq!(unbounded.find("/user/1"), uncached.find("/user/1"));
+    assert_eq!(unbounded.find("/user/1"), uncached.find("/user/1"));
+    assert_eq!(
+        unbounded
+            .clone()
+            .find_with_timeout("/user/2", Duration::from_secs(10))?,
+        uncached.find("/user/2")
+    );
+    Ok(())
+}
+
+#[test]
+fn should_split_repeated_params() -> Result<()> {
+    let matcher = Matcher::new("/:path*")?;
+    let result = matcher.find("/a/b/c").unwrap();
+    assert_eq!(result.params, json!({"path": ["a", "b", "c"]}));
+    let result = matcher.find("/").unwrap();
+    assert_eq!(result.params, json!({"path": []}));
+
+    let matcher = Matcher::new("/x{-:id}+")?;
+    let result = matcher.find("/x-1-2").unwrap();
+    assert_eq!(result.params, json!({"id": ["1", "2"]}));
+    Ok(())
+}
+
+#[test]
+fn should_clone_matcher_across_threads() -> Result<()> {
+    let matcher = Matcher::new_with_options(
+        "/user/:id",
+        MatcherOptions {
+            cache_size: Some(4),
+            ..Default::default()
+        },
+    )?;
+    assert!(matcher.find("/user/1").is_some());
+    let cloned = matcher.clone();
+    let handle = std::thread::spawn(move || cloned.find("/user/2").map(|result| result.params));
+    assert_eq!(handle.join().unwrap(), Some(json!({"id": "2"})));
+    assert_eq!(matcher.clone().find("/user/1"), matcher.find("/user/1"));
+    Ok(())
+}
+
+#[test]
+fn should_limit_the_captured_params() -> Result<()> {
+    let matcher = MatcherBuilder::new("/:a/:b/:c")
+        .set_max_captures(2)
+        .build()?;
+    let result = matcher.find("/1/2/3").unwrap();
+    assert_eq!(result.path, "/1/2/3");
+    assert_eq!(result.params, json!({"a": "1", "b": "2"}));
+    Ok(())
+}
+
+#[test]
+fn should_report_the_trailing_delimiter() -> Result<()> {
+    let matcher = Matcher::new("/users/:id")?;
+    let result = matcher.find("/users/7").unwrap();
+    assert_eq!(result.trailing_delimiter, None);
+    let result = matcher.find("/users/7/").unwrap();
+    assert_eq!(result.path, "/users/7/");
+    assert_eq!(result.params, json!({"id": "7"}));
+    assert_eq!(result.trailing_delimiter, Some('/'));
+
+    let strict = MatcherBuilder::new("/users/:id").set_strict(true).build()?;
+    assert!(strict.find("/users/7/").is_none());
+    let strict = MatcherBuilder::new("/users/:id/")
+        .set_strict(true)
+        .build()?;
+    assert_eq!(strict.find("/users/7/").unwrap().trailing_delimiter, None);
+
+    let prefix = MatcherBuilder::new("/users/:id").set_end(false).build()?;
+    let result = prefix.find("/users/7/posts").unwrap();
+    assert_eq!(result.path, "/users/7");
+    assert_eq!(result.trailing_delimiter, Some('/'));
+    let result = prefix.find("/users/7").unwrap();
+    assert_eq!(result.path, "/users/7");
+    assert_eq!(result.trailing_delimiter, None);
+    let result = prefix.find("/users/7/").unwrap();
+    assert_eq!(result.trailing_delimiter, Some('/'));
+
+    // A parameter taking the delimiter leaves no trailing delimiter
+    let greedy = Matcher::new("/:p(.*)")?;
+    let result = greedy.find("/a/").unwrap();
+    assert_eq!(result.params, json!({"p": "a/"}));
+    assert_eq!(result.trailing_delimiter, None);
+
+    let several = Matcher::new(vec!["/users/:id", "/posts/:id"])?;
+    assert_eq!(
+        several.find("/posts/7/").unwrap().params,
+        json!({"id": "7"})
+    );
+    Ok(())
+}
+
+#[test]
+fn should_find_anchored_at_an_offset() -> Result<()> {
+    let offset = "/api".len();
+    let matchers = [
+        Matcher::new("/users/:id")?,
+        MatcherBuilder::new("/users/:id").set_end(false).build()?,
+        MatcherBuilder::new("/users/:id").set_start(false).build()?,
+        MatcherBuilder::new("/users/:id")
+            .set_start(false)
+            .set_fragment(FragmentMode::Capture)
+            .build()?,
+        MatcherBuilder::new("/users/:id")
+            .set_start(false)
+            .set_matrix_params(true)
+            .build()?,
+    ];
+    for path in [
+        "/api/users/7/posts",
+        "/api/users/7",
+        "/api/users/7#top",
+        "/api/users;v=1/7",
+        "/api/x/users/7",
+    ] {
+        for matcher in matchers.iter() {
+            // Only a match starting at the offset is anchored
+            let sliced = matcher
+                .find(&path[offset..])
+                .filter(|result| result.index == 0)
+                .map(|mut result| {
+                    result.index += offset;
+                    result
+                });
+            assert_eq!(matcher.find_anchored_at(path, offset), sliced, "{path}");
+        }
+    }
+    let result = matchers[3]
+        .find_anchored_at("/api/users/7#top", offset)
+        .unwrap();
+    assert_eq!(result.fragment.as_deref(), Some("top"));
+    let result = matchers[4]
+        .find_anchored_at("/api/users;v=1/7", offset)
+        .unwrap();
+    assert_eq!(result.params["id"], "7");
+
+    let path = "/api/users/7/posts";
+
+    let matcher = MatcherBuilder::new("/users/:id")
+        .set_start(false)
+        .set_end(false)
+        .build()?;
+    let result = matcher.find_anchored_at(path, offset).unwrap();
+    assert_eq!(result.index, offset);
+    assert_eq!(result.path, "/users/7");
+    assert!(matcher.find_anchored_at(path, 0).is_none());
+    assert!(matcher.find_anchored_at(path, path.len() + 1).is_none());
+    Ok(())
+}
+
+#[test]
+fn should_use_the_default_of_missing_params() -> Result<()> {
+    let matcher = Matcher::new("/list/:page(\\d+)?=1")?;
+    assert_eq!(matcher.find("/list").unwrap().params, json!({"page": "1"}));
+    assert_eq!(
+        matcher.find("/list/3").unwrap().params,
+        json!({"page": "3"})
+    );
+
+    let matcher = Matcher::new("/docs/:path*=index")?;
+    assert_eq!(
+        matcher.find("/docs").unwrap().params,
+        json!({"path": ["index"]})
+    );
+    Ok(())
+}
+
+#[test]
+fn should_find_with_a_timeout() -> Result<()> {
+    let matcher = Matcher::new("/user/:id")?;
+    let result = matcher.find_with_timeout("/user/1", Duration::from_secs(10))?;
+    assert_eq!(result, matcher.find("/user/1"));
+    assert_eq!(
+        matcher.find_with_timeout("/nope", Duration::from_secs(10))?,
+        None
+    );
+
+    let slow = MatcherBuilder::new("/user/:id")
+        .set_decode(|value, _| {
+            std::thread::sleep(Duration::from_millis(200));
+            value.into()
+        })
+        .build()?;
+    let err = slow
+        .find_with_timeout("/user/1", Duration::from_millis(5))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        MatchTimeout {
+            timeout: Duration::from_millis(5),
+        }
+    );
+    assert_eq!(err.to_string(), "Matching took longer than 5ms");
+    Ok(())
+}
+
+#[test]
+fn should_treat_blank_params_as_missing() -> Result<()> {
+    let lenient = Matcher::new("/search/:q(.*)")?;
+    assert_eq!(lenient.find("/search/").unwrap().params, json!({"q": ""}));
+
+    let matcher = MatcherBuilder::new("/search/:q(.*)")
+        .set_blank_as_missing(true)
+        .build()?;
+    assert_eq!(matcher.find("/search/").unwrap().params, json!({}));
+    assert_eq!(
+        matcher.find("/search/%20").unwrap().params,
+        json!({"q": "%20"})
+    );
+
+    let matcher = MatcherBuilder::new("/search/:q(.*)?=all")
+        .set_blank_as_missing(true)
+        .build()?;
+    assert_eq!(
+        matcher.find("/search/").unwrap().params,
+        json!({"q": "all"})
+    );
+    assert_eq!(matcher.find("/search").unwrap().params, json!({"q": "all"}));
+
+    let matcher = MatcherBuilder::new("/:path*")
+        .set_decode(|x, _| x.trim().into())
+        .set_blank_as_missing(true)
+        .build()?;
+    assert_eq!(
+        matcher.find("/a/ /b").unwrap().params,
+        json!({"path": ["a", "b"]})
+    );
+    assert_eq!(matcher.find("/ ").unwrap().params, json!({}));
+    Ok(())
+}
+
+#[cfg(feature = "percent-encoding")]
+#[test]
+fn should_keep_decoded_blank_params() -> Result<()> {
+    let matcher = Matcher::new_with_options(
+        "/search/:q(.*)",
+        MatcherOptions {
+            blank_as_missing: true,
+            ..MatcherOptions::decode_percent_encoded()
+        },
+    )?;
+    assert_eq!(matcher.find("/search/").unwrap().params, json!({}));
+    assert_eq!(
+        matcher.find("/search/%20").unwrap().params,
+        json!({"q": " "})
+    );
+    Ok(())
+}
+
+#[test]
+fn should_collect_matrix_params() -> Result<()> {
+    let matcher = MatcherBuilder::new("/items/:id")
+        .set_matrix_params(true)
+        .build()?;
+    let result = matcher.find("/items;color=red;size=m/42").unwrap();
+    assert_eq!(result.path, "/items/42");
+    assert_eq!(
+        result.params,
+        json!({"id": "42", "matrix": {"1": {"color": "red", "size": "m"}}})
+    );
+
+    let result = matcher.find("/items/42;rev=3;tag=a;tag=b;draft").unwrap();
+    assert_eq!(
+        result.params,
+        json!({"id": "42", "matrix": {"id": {"rev": "3", "tag": ["a", "b"], "draft": ""}}})
+    );
+    assert_eq!(
+        matcher.find("/items/42").unwrap().params,
+        json!({"id": "42"})
+    );
+    assert!(Matcher::new("/items/:id")?
+        .find("/items;color=red/42")
+        .is_none());
+
+    let err = MatcherBuilder::new("/:matrix/:id")
+        .set_matrix_params(true)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The parameter \"matrix\" is taken by the matrix parameters"
+    );
+    assert!(Matcher::new("/:matrix/:id")?.find("/red/42").is_some());
+    Ok(())
+}
+
+#[cfg(feature = "percent-encoding")]
+#[test]
+fn should_decode_matrix_params() -> Result<()> {
+    let matcher = Matcher::new_with_options(
+        "/items/:id",
+        MatcherOptions {
+            matrix_params: true,
+            ..MatcherOptions::decode_percent_encoded()
+        },
+    )?;
+    let result = matcher.find("/items/a%20b;color=dark%20red").unwrap();
+    assert_eq!(
+        result.params,
+        json!({"id": "a b", "matrix": {"id": {"color": "dark red"}}})
+    );
+    Ok(())
+}
+
+#[test]
+fn should_handle_the_fragment() -> Result<()> {
+    let path = "/docs/intro#setup";
+    assert!(Matcher::new("/docs/:page")?.find(path).is_none());
+
+    let ignore = MatcherBuilder::new("/docs/:page")
+        .set_fragment(FragmentMode::Ignore)
+        .build()?;
+    let result = ignore.find(path).unwrap();
+    assert_eq!(result.path, "/docs/intro");
+    assert_eq!(result.params, json!({"page": "intro"}));
+    assert_eq!(result.fragment, None);
+
+    let capture = MatcherBuilder::new("/docs/:page")
+        .set_fragment(FragmentMode::Capture)
+        .build()?;
+    let result = capture.find(path).unwrap();
+    assert_eq!(result.params, json!({"page": "intro"}));
+    assert_eq!(result.fragment.as_deref(), Some("setup"));
+    assert_eq!(capture.find("/docs/intro").unwrap().fragment, None);
+
+    let reject = MatcherBuilder::new("/docs/:page")
+        .set_fragment(FragmentMode::Reject)
+        .build()?;
+    assert!(reject.find(path).is_none());
+    assert!(reject.find("/docs/intro").is_some());
+
+    for matcher in [&ignore, &capture, &reject] {
+        let result = matcher.find("/docs/c%23").unwrap();
+        assert_eq!(result.params, json!({"page": "c%23"}));
+        assert_eq!(result.fragment, None);
+    }
+    Ok(())
+}
+
+#[test]
+fn should_match_a_prefix() -> Result<()> {
+    let matcher = MatcherBuilder::new("/users/:id").set_end(false).build()?;
+    let (rest, result) = matcher.match_prefix("/users/7/posts/1").unwrap();
+    assert_eq!(rest, "/posts/1");
+    assert_eq!(result.path, "/users/7");
+    assert_eq!(result.params, json!({"id": "7"}));
+
+    let (rest, _) = matcher.match_prefix("/users/7").unwrap();
+    assert_eq!(rest, "");
+    assert!(matcher.match_prefix("/posts/7").is_none());
+
+    let matcher = Matcher::new("/users/:id")?;
+    assert_eq!(matcher.match_prefix("/users/7/").unwrap().0, "");
+    Ok(())
+}
+
+#[test]
+fn should_match_a_prefix_with_matrix_params() -> Result<()> {
+    let matcher = MatcherBuilder::new("/users/:id")
+        .set_end(false)
+        .set_matrix_params(true)
+        .set_fragment(FragmentMode::Capture)
+        .build()?;
+    let (rest, result) = matcher.match_prefix("/users;x=1/7/posts").unwrap();
+    assert_eq!(rest, "/posts");
+    assert_eq!(result.params["id"], "7");
+    let (rest, _) = matcher.match_prefix("/users;é/7").unwrap();
+    assert_eq!(rest, "");
+    let (rest, _) = matcher.match_prefix("/users/7;v=2/posts;é#top").unwrap();
+    assert_eq!(rest, "/posts;é#top");
+    Ok(())
+}
+
+#[test]
+fn should_borrow_unchanged_decoded_params() {
+    let key = path2regex::Key::default();
+    let value = "hello";
+    match (MatcherOptions::default().decode)(value, &key) {
+        Cow::Borrowed(decoded) => assert_eq!(decoded.as_ptr(), value.as_ptr()),
+        Cow::Owned(_) => panic!("the identity decode must borrow"),
+    }
+
+    #[cfg(feature = "percent-encoding")]
+    {
+        let decode = MatcherOptions::decode_percent_encoded().decode;
+        assert!(matches!(decode(value, &key), Cow::Borrowed(_)));
+        assert!(matches!(decode("a%20b", &key), Cow::Owned(_)));
+    }
+}
+
+#[test]
+fn should_decompose_into_a_router_entry() -> Result<()> {
+    let matcher = MatcherBuilder::new("/user/:id").set_strict(true).build()?;
+    let (re, options) = matcher.into_router_entry();
+    assert!(options.strict);
+    assert_eq!(re.keys()[0].name, "id");
+    let set = regex::RegexSet::new([re.as_str(), "^/about$"])?;
+    assert_eq!(
+        set.matches("/user/1").into_iter().collect::<Vec<_>>(),
+        vec![0]
+    );
+    Ok(())
+}
+
+#[test]
+fn should_replace_params_in_place() -> Result<()> {
+    let matcher = MatcherBuilder::new("/users/:user_id/posts/:post_id")
+        .set_end(false)
+        .build()?;
+    let path = "/users/7/posts/3?x=1";
+    assert_eq!(
+        matcher.replace_params(path, &json!({"user_id": 8}))?,
+        "/users/8/posts/3?x=1"
+    );
+    assert_eq!(
+        matcher.replace_params(path, &json!({"user_id": "a", "post_id": "b"}))?,
+        "/users/a/posts/b?x=1"
+    );
+    assert_eq!(matcher.replace_params(path, &json!({}))?, path);
+
+    let err = matcher.replace_params(path, &json!({"id": 8})).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown parameter \"id\"");
+    let err = matcher
+        .replace_params(path, &json!({"user_id": "a/b"}))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"user_id\" to match \"[^/\\#\\?]+?\", but got \"a/b\""
+    );
+    assert!(matcher.replace_params("/nope", &json!({})).is_err());
+    Ok(())
+}
+
+#[test]
+fn should_replace_params_around_matrix_params_and_fragments() -> Result<()> {
+    let matcher = MatcherBuilder::new("/users/:id")
+        .set_matrix_params(true)
+        .build()?;
+    assert!(matcher.find("/users;v=1/7").is_some());
+    assert_eq!(
+        matcher.replace_params("/users;v=1/7", &json!({"id": "8"}))?,
+        "/users;v=1/8"
+    );
+    assert_eq!(
+        matcher.replace_params("/users/7;rev=2", &json!({"id": "8"}))?,
+        "/users/8;rev=2"
+    );
+
+    let matcher = MatcherBuilder::new("/docs/:page")
+        .set_fragment(FragmentMode::Capture)
+        .build()?;
+    assert_eq!(
+        matcher.replace_params("/docs/intro#setup", &json!({"page": "usage"}))?,
+        "/docs/usage#setup"
+    );
+    let matcher = MatcherBuilder::new("/docs/:page")
+        .set_fragment(FragmentMode::Reject)
+        .build()?;
+    assert!(matcher
+        .replace_params("/docs/intro#setup", &json!({"page": "usage"}))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn should_replace_repeated_and_unanchored_params() -> Result<()> {
+    let matcher = MatcherBuilder::new("/files/:path+")
+        .set_encode_value(|x, _| x.replace(' ', "%20"))
+        .build()?;
+    assert_eq!(
+        matcher.replace_params("/files/a/b", &json!({"path": ["x y", "z"]}))?,
+        "/files/x%20y/z"
+    );
+
+    let matcher = MatcherBuilder::new("/v:version(\\d+)/")
+        .set_start(false)
+        .set_end(false)
+        .build()?;
+    assert_eq!(
+        matcher.replace_params("see https://api.test/v1/users", &json!({"version": 2}))?,
+        "see https://api.test/v2/users"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_catch_a_panic_of_the_decode_function() -> Result<()> {
+    let matcher = MatcherBuilder::new("/:name")
+        .set_decode(|value, _| {
+            if value == "boom" {
+                panic!("Invalid value \"{value}\"");
+            }
+            Cow::Borrowed(value)
+        })
+        .build()?;
+    let result = matcher.find_opt("/ok")?.unwrap();
+    assert_eq!(result.params, json!({"name": "ok"}));
+    assert!(matcher.find_opt("/a/b")?.is_none());
+
+    let err = matcher.find_opt("/boom").unwrap_err();
+    assert_eq!(err, DecodeError::Panic("Invalid value \"boom\"".to_owned()));
+    assert_eq!(err.to_string(), "Decoding panicked: Invalid value \"boom\"");
+
+    // The matcher is still usable, even with a cache
+    assert!(matcher.find_opt("/ok")?.is_some());
+    let matcher = MatcherBuilder::new("/:name")
+        .set_decode(|_, _| panic!())
+        .set_cache_size(4)
+        .build()?;
+    assert!(matcher.find_opt("/x").is_err());
+    assert!(matcher.find_opt("/x").is_err());
+    Ok(())
+}
+
+#[test]
+fn should_get_the_path_of_a_match_as_bytes() -> Result<()> {
+    let matcher = MatcherBuilder::new("/users/:id").set_end(false).build()?;
+    let result = matcher.find("/users/42/posts").unwrap();
+    assert_eq!(result.path_bytes(), b"/users/42");
+    assert_eq!(result.path_bytes(), result.path.as_bytes());
+    Ok(())
+}
+
+#[test]
+fn should_expose_the_keys_of_the_matcher() -> Result<()> {
+    let matcher = Matcher::new("/user/:id(\\d+)/:tab?")?;
+    let names = matcher
+        .keys()
+        .iter()
+        .map(|key| key.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["id", "tab"]);
+    assert_eq!(matcher.keys()[0].pattern, "\\d+");
+    assert_eq!(matcher.keys()[1].modifier, "?");
+    Ok(())
+}
+
+#[test]
+fn should_parse_a_logged_match_result() -> Result<()> {
+    let matcher = Matcher::new("/user/:id")?;
+    let result: MatchResult = "/user/42|0|{\"id\":\"42\"}".parse()?;
+    assert_eq!(Some(result), matcher.find("/user/42"));
+
+    let result: MatchResult = "/a|3|{\"q\":\"x|y\"}".parse()?;
+    assert_eq!(result.index, 3);
+    assert_eq!(result.params, json!({"q": "x|y"}));
+
+    let err = "/user/42|0".parse::<MatchResult>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected \"/user/42|0\" to be \"path|index|params\""
+    );
+    let err = "/user/42|x|{}".parse::<MatchResult>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected the index to be an integer, but got \"x\""
+    );
+    let err = "/user/42|0|[]".parse::<MatchResult>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected the params to be an object, but got []"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_find_raw_values_borrowed_from_the_path() -> Result<()> {
+    let matcher = Matcher::new("/users/:id/:tab?/:tags*")?;
+    let path = String::from("/users/hello%20world/posts/a/b");
+    let result = matcher.find_raw(&path).unwrap();
+    assert_eq!(result.path, "/users/hello%20world/posts/a/b");
+    assert_eq!(result.index, 0);
+    assert_eq!(result.get("id"), Some("hello%20world"));
+    assert_eq!(result.get("tags"), Some("a/b"));
+    assert_eq!(result.get("missing"), None);
+    assert_eq!(
+        result.iter().collect::<Vec<_>>(),
+        [
+            ("id", Some("hello%20world")),
+            ("tab", Some("posts")),
+            ("tags", Some("a/b"))
+        ]
+    );
+
+    let result = matcher.find_raw("/users/1/").unwrap();
+    assert_eq!(result.path, matcher.find("/users/1/").unwrap().path);
+    assert_eq!(result.get("tab"), None);
+    assert!(matcher.find_raw("/posts/1").is_none());
+    Ok(())
+}
+
+#[test]
+fn should_split_the_path_segments() -> Result<()> {
+    let matcher = Matcher::new("/users/:id/:tab?")?;
+    let result = matcher.find("/users/1/posts/").unwrap();
+    assert_eq!(result.path_segments(), ["users", "1", "posts"]);
+    assert_eq!(matcher.path_segments(&result), ["users", "1", "posts"]);
+    assert!(Matcher::new("/")?
+        .find("/")
+        .unwrap()
+        .path_segments()
+        .is_empty());
+
+    let matcher = MatcherBuilder::new("api.:version.:name")
+        .set_delimiter(".")
+        .set_prefixes(".")
+        .build()?;
+    let result = matcher.find("api.v1.users").unwrap();
+    assert_eq!(result.path_segments(), ["api.v1.users"]);
+    assert_eq!(matcher.path_segments(&result), ["api", "v1", "users"]);
+    Ok(())
+}
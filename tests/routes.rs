@@ -0,0 +1,314 @@
+use anyhow::Result;
+use path2regex::{FragmentMode, MatcherOptions, RouteTrace, Routes, RoutesDescription};
+use serde_json::json;
+
+#[test]
+fn should_generate_and_dispatch_named_routes() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("user_detail", "/users/:id", MatcherOptions::default())?;
+    routes.add("user_posts", "/users/:id/posts", MatcherOptions::default())?;
+
+    let detail = routes.url_for("user_detail", &json!({"id": 7}))?;
+    let posts = routes.url_for("user_posts", &json!({"id": 7}))?;
+    assert_eq!(detail, "/users/7");
+    assert_eq!(posts, "/users/7/posts");
+
+    let (route, result) = routes.find(&posts).unwrap();
+    assert_eq!(route.name(), "user_posts");
+    assert_eq!(result.params, json!({"id": "7"}));
+    assert_eq!(routes.find(&detail).unwrap().0.name(), "user_detail");
+    assert!(routes
+        .matcher("user_detail")
+        .unwrap()
+        .find("/users/8")
+        .is_some());
+    assert!(routes.matcher("missing").is_none());
+    Ok(())
+}
+
+#[test]
+fn should_reject_duplicate_route_names() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("home", "/", MatcherOptions::default())?;
+    let err = routes
+        .add("home", "/index", MatcherOptions::default())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Duplicate route name \"home\"");
+    assert_eq!(routes.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn should_name_the_route_in_url_for_errors() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("user_detail", "/users/:id", MatcherOptions::default())?;
+
+    let err = routes.url_for("missing", &json!({})).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown route name \"missing\"");
+
+    let err = routes.url_for("user_detail", &json!({})).unwrap_err();
+    assert_eq!(
+        format!("{err:#}"),
+        "Failed to render route \"user_detail\": Expected \"id\" to be a string or a number"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_remove_replace_and_clear_routes() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("user", "/users/:id", MatcherOptions::default())?;
+    routes.add("any_user", "/:kind/:id", MatcherOptions::default())?;
+    routes.add("home", "/", MatcherOptions::default())?;
+    assert_eq!(routes.find("/users/1").unwrap().0.name(), "user");
+
+    let removed = routes.remove("user").unwrap();
+    assert_eq!(removed.path(), "/users/:id");
+    assert!(routes.remove("user").is_none());
+    assert_eq!(routes.find("/users/1").unwrap().0.name(), "any_user");
+    assert_eq!(routes.url_for("home", &json!({}))?, "/");
+    assert!(routes.url_for("user", &json!({"id": 1})).is_err());
+
+    routes.replace("any_user", "/members/:id")?;
+    assert_eq!(routes.find("/members/1").unwrap().0.name(), "any_user");
+    assert!(routes.find("/users/1").is_none());
+    assert!(routes.replace("user", "/users/:id").is_err());
+    assert!(routes.replace("home", "/:id(abc").is_err());
+    assert_eq!(routes.route("home").unwrap().path(), "/");
+
+    let names = routes.iter().map(|route| route.name()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["any_user", "home"]);
+
+    routes.clear();
+    assert!(routes.is_empty());
+    assert!(routes.route("home").is_none());
+    routes.add("home", "/", MatcherOptions::default())?;
+    assert_eq!(routes.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn should_dispatch_by_priority() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("user", "/users/:id", MatcherOptions::default())?;
+    routes.add("beta", "/:any*", MatcherOptions::default())?;
+    routes.add("admin", "/users/admin", MatcherOptions::default())?;
+    assert_eq!(routes.find("/users/1").unwrap().0.name(), "user");
+
+    routes.set_priority("beta", 10)?;
+    assert_eq!(routes.find("/users/1").unwrap().0.name(), "beta");
+    assert_eq!(routes.route("beta").unwrap().priority(), 10);
+    assert!(routes.set_priority("missing", 1).is_err());
+
+    routes.set_priority("beta", 0)?;
+    assert_eq!(routes.find("/users/admin").unwrap().0.name(), "user");
+    routes.add_with_priority("admin_first", "/users/admin", MatcherOptions::default(), 1)?;
+    assert_eq!(routes.find("/users/admin").unwrap().0.name(), "admin_first");
+
+    let names = routes.iter().map(|route| route.name()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["admin_first", "user", "beta", "admin"]);
+    assert_eq!(routes.url_for("beta", &json!({"any": ["a", "b"]}))?, "/a/b");
+    Ok(())
+}
+
+#[test]
+fn should_recompile_a_match() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("file", "/files/:path+{.:ext}?", MatcherOptions::default())?;
+    let (route, result) = routes.find("/files/a/b.txt").unwrap();
+    assert_eq!(result.params, json!({"path": ["a", "b"], "ext": "txt"}));
+    assert_eq!(route.recompile(&result)?, "/files/a/b.txt");
+
+    let (route, result) = routes.find("/FILES/a/").unwrap();
+    assert_eq!(result.to_data(), json!({"path": ["a"]}));
+    assert_eq!(route.recompile(&result)?, "/files/a");
+    Ok(())
+}
+
+#[test]
+fn should_round_trip_the_description_of_routes() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("user", "/users/:id", MatcherOptions::default())?;
+    routes.add_with_priority(
+        "admin",
+        "/Admin/:page?",
+        MatcherOptions {
+            sensitive: true,
+            fragment: Some(FragmentMode::Reject),
+            ..Default::default()
+        },
+        5,
+    )?;
+    routes.add("file", "/files/:path+{.:ext}?", MatcherOptions::default())?;
+    routes.add(
+        "tags",
+        "/tags/:tags+",
+        MatcherOptions {
+            repeat_separator: Some(",".to_owned()),
+            ..Default::default()
+        },
+    )?;
+    routes.add("any", "/:any*", MatcherOptions::default())?;
+
+    let description = routes.describe();
+    assert_eq!(description.routes[0].name, "admin");
+    assert_eq!(description.routes[1].static_prefix, "/users");
+    assert_eq!(description.routes[3].repeat_separator.as_deref(), Some(","));
+    assert_eq!(description.routes[4].static_prefix, "");
+    assert_eq!(description.routes[1].specificity, "/users/".len());
+    assert_eq!(description.routes[2].specificity, "/files/".len());
+    assert_eq!(description.routes[4].specificity, 0);
+    assert_eq!(description.prefiltered, 4);
+
+    let json = description.to_json();
+    let restored = Routes::from_description(&RoutesDescription::from_json(&json)?)?;
+    assert_eq!(restored.describe(), description);
+    for path in [
+        "/users/1",
+        "/USERS/1",
+        "/Admin",
+        "/admin/2",
+        "/Admin/2#top",
+        "/files/a/b.txt",
+        "/tags/a,b",
+        "/",
+        "/other/path",
+    ] {
+        let expected = routes
+            .find(path)
+            .map(|(route, result)| (route.name(), result));
+        let actual = restored
+            .find(path)
+            .map(|(route, result)| (route.name(), result));
+        assert_eq!(actual, expected, "{path}");
+    }
+
+    let mut changed = json.clone();
+    changed["routes"][0]["pattern"] = json!("^/admin$");
+    let err = Routes::from_description(&RoutesDescription::from_json(&changed)?).unwrap_err();
+    assert!(err.to_string().starts_with("Route \"admin\" rebuilt as"));
+    let err = RoutesDescription::from_json(&json!({"routes": [{"name": "a"}]})).unwrap_err();
+    assert_eq!(
+        format!("{err:#}"),
+        "Invalid route description at 0: Expected \"path\" to be a string"
+    );
+    Ok(())
+}
+
+#[test]
+fn should_explain_the_dispatch_of_a_path() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("user", "/users/:id", MatcherOptions::default())?;
+    routes.add("post", "/posts/:id", MatcherOptions::default())?;
+    routes.add("posts", "/posts", MatcherOptions::default())?;
+    routes.add("any", "/:any*", MatcherOptions::default())?;
+
+    let trace = |name: &str, excluded, matched| RouteTrace {
+        name: name.to_owned(),
+        excluded,
+        matched,
+    };
+    assert_eq!(
+        routes.why("/POSTS"),
+        vec![
+            trace("user", true, false),
+            trace("post", false, false),
+            trace("posts", false, true),
+        ]
+    );
+    assert_eq!(routes.why("/x/y").last(), Some(&trace("any", false, true)));
+    assert_eq!(routes.why("/users/1/2").len(), 4);
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn should_serialize_the_description_of_routes() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("user", "/users/:id", MatcherOptions::default())?;
+    let description = routes.describe();
+    let json = serde_json::to_value(&description)?;
+    assert_eq!(json, description.to_json());
+    assert_eq!(
+        serde_json::from_value::<RoutesDescription>(json)?,
+        description
+    );
+    Ok(())
+}
+
+#[test]
+fn should_score_the_specificity_of_routes() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("me", "/users/me", MatcherOptions::default())?;
+    routes.add("user", "/users/:id", MatcherOptions::default())?;
+    routes.add("tab", "/users/:id/:tab?", MatcherOptions::default())?;
+    routes.add("any", "/:any*", MatcherOptions::default())?;
+
+    let scores: Vec<_> = routes
+        .iter()
+        .map(|route| (route.name(), route.specificity()))
+        .collect();
+    assert_eq!(scores, [("me", 9), ("user", 7), ("tab", 7), ("any", 0)]);
+    Ok(())
+}
+
+#[test]
+fn should_find_the_same_route_with_the_static_prefix_prefilter() -> Result<()> {
+    let mut routes = Routes::new();
+    routes.add("user", "/users/:id", MatcherOptions::default())?;
+    routes.add(
+        "admin",
+        "/Admin/:page?",
+        MatcherOptions {
+            sensitive: true,
+            ..Default::default()
+        },
+    )?;
+    routes.add("kelvin", "/k/:id", MatcherOptions::default())?;
+    routes.add(
+        "matrix",
+        "/m/:id",
+        MatcherOptions {
+            matrix_params: true,
+            ..Default::default()
+        },
+    )?;
+    routes.add(
+        "loose",
+        "/loose",
+        MatcherOptions {
+            start: false,
+            ..Default::default()
+        },
+    )?;
+
+    assert_eq!(routes.route("user").unwrap().static_prefix(), "/users");
+    assert_eq!(routes.route("admin").unwrap().static_prefix(), "/Admin");
+    assert_eq!(routes.route("matrix").unwrap().static_prefix(), "");
+    assert_eq!(routes.route("loose").unwrap().static_prefix(), "");
+    for path in [
+        "/users/1",
+        "/USERS/1",
+        "/user",
+        "/Admin/2",
+        "/admin/2",
+        "/\u{212A}/1",
+        "/m;a=1/2",
+        "/x/loose",
+        "",
+    ] {
+        let expected = routes.iter().find_map(|route| {
+            route
+                .matcher()
+                .find(path)
+                .map(|result| (route.name(), result))
+        });
+        let actual = routes
+            .find(path)
+            .map(|(route, result)| (route.name(), result));
+        assert_eq!(actual, expected, "{path}");
+    }
+    assert!(routes.why("/admin/2")[1].excluded);
+    assert!(!routes.why("/\u{212A}/1")[2].excluded);
+    Ok(())
+}
@@ -67,10 +67,11 @@ fn assert_compile(
         let options = CompilerOptions {
             delimiter: options.delimiter.clone(),
             prefixes: options.prefixes.clone(),
+            start_delimiter: options.start_delimiter.clone(),
             sensitive: options.sensitive,
             encode: options.encode,
             validate: options.validate,
-            ..case.options
+            ..case.options.clone()
         };
         let compiler = CompilerBuilder::new_with_options(path.clone(), options).build()?;
         if case.result.is_empty() {
@@ -91,20 +92,22 @@ fn assert_compile(
     Ok(())
 }
 
-fn assert_match(
-    path: impl TryIntoWith<PathRegex, PathRegexOptions>,
-    re: &PathRegex,
-    match_cases: &Vec<MatchCase>,
-) -> Result<()> {
+fn assert_match<P>(path: P, re: &PathRegex, match_cases: &Vec<MatchCase>) -> Result<()>
+where
+    P: TryIntoWith<PathRegex, PathRegexOptions> + TryIntoWith<Vec<Token>, ParserOptions>,
+{
     for case in match_cases {
         let message = format!(
             "should {}match {}",
             if case.matches.is_none() { "not " } else { "" },
             case.path_name
         );
+        // The optional trailing delimiter is captured for the matcher only
         let matches = re.captures(case.path_name).map(|cap| {
             cap.iter()
-                .map(|x| match x {
+                .zip(re.capture_names())
+                .filter(|(_, name)| *name != Some("TRAILING_DELIMITER"))
+                .map(|(x, _)| match x {
                     Some(x) => x.as_str(),
                     None => Default::default(),
                 })
@@ -113,7 +116,7 @@ fn assert_match(
 
         assert_eq!(matches, case.matches, "{message}");
 
-        if case.params.is_some() {
+        if let Some(result) = &case.params {
             let matcher =
                 MatcherBuilder::new_with_options(path.clone(), case.options.clone()).build()?;
             assert_eq!(
@@ -121,6 +124,15 @@ fn assert_match(
                 case.params,
                 "{message} params"
             );
+
+            let options = CompilerOptions::from(case.options.clone());
+            let compiler = CompilerBuilder::new_with_options(path.clone(), options).build()?;
+            let rendered = compiler.render(&result.to_data())?;
+            assert_eq!(
+                matcher.find(&rendered).map(|result| result.params),
+                Some(result.params.clone()),
+                "{message} round trip"
+            );
         }
     }
     Ok(())
@@ -163,6 +175,7 @@ fn test_rule_1() -> Result<()> {
                     path: "/".to_owned(),
                     index: 0,
                     params: json!({}),
+                    ..Default::default()
                 }),
                 ..Default::default()
             },
@@ -206,6 +219,7 @@ fn test_rule_2() -> Result<()> {
                     path: "/test".to_owned(),
                     index: 0,
                     params: json!({}),
+                    ..Default::default()
                 }),
                 ..Default::default()
             },
@@ -224,6 +238,8 @@ fn test_rule_2() -> Result<()> {
                     path: "/test/".to_owned(),
                     index: 0,
                     params: json!({}),
+                    trailing_delimiter: Some('/'),
+                    ..Default::default()
                 }),
                 ..Default::default()
             },
@@ -0,0 +1,42 @@
+use path2regex::{
+    Compiler, CompilerOptions, Key, MatchResult, Matcher, MatcherOptions, Parser, PathRegex,
+    PathRegexOptions, Route, Routes, Token,
+};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn should_be_send_and_sync() {
+    assert_send_sync::<PathRegex>();
+    assert_send_sync::<PathRegexOptions>();
+    assert_send_sync::<Parser>();
+    assert_send_sync::<Key>();
+    assert_send_sync::<Token>();
+    assert_send_sync::<Compiler>();
+    assert_send_sync::<CompilerOptions>();
+    assert_send_sync::<Matcher>();
+    assert_send_sync::<MatcherOptions>();
+    assert_send_sync::<MatchResult>();
+    assert_send_sync::<Route>();
+    assert_send_sync::<Routes>();
+    #[cfg(feature = "url")]
+    assert_send_sync::<path2regex::UrlMatcher>();
+}
+
+#[test]
+fn should_share_a_matcher_between_threads() -> anyhow::Result<()> {
+    let matcher = std::sync::Arc::new(Matcher::new("/users/:id")?);
+    let handles = (0..4)
+        .map(|i| {
+            let matcher = matcher.clone();
+            std::thread::spawn(move || matcher.find(format!("/users/{i}")).unwrap().params)
+        })
+        .collect::<Vec<_>>();
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(
+            handle.join().unwrap(),
+            serde_json::json!({"id": i.to_string()})
+        );
+    }
+    Ok(())
+}
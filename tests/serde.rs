@@ -0,0 +1,87 @@
+#![cfg(feature = "serde")]
+
+use anyhow::Result;
+use path2regex::{Key, PathRegex, PathRegexBuilder, Token};
+use serde_json::json;
+
+#[test]
+fn should_serialize_path_regex() -> Result<()> {
+    let re = PathRegex::new("/user/:id")?;
+    assert_eq!(
+        serde_json::to_value(&re)?,
+        json!({
+            "pattern": re.as_str(),
+            "keys": [{
+                "name": "id",
+                "prefix": "/",
+                "suffix": "",
+                "pattern": "[^/\\#\\?]+?",
+                "modifier": "",
+            }],
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_path_regex() -> Result<()> {
+    let re = PathRegexBuilder::new("/user/:id").set_end(false).build()?;
+    let restored: PathRegex = serde_json::from_str(&serde_json::to_string(&re)?)?;
+    assert_eq!(restored.as_str(), re.as_str());
+    assert_eq!(restored.keys(), re.keys());
+    assert!(restored.is_match("/USER/123/show"));
+
+    let sensitive = PathRegexBuilder::new("/user").set_sensitive(true).build()?;
+    let restored: PathRegex = serde_json::from_str(&serde_json::to_string(&sensitive)?)?;
+    assert!(!restored.is_match("/USER"));
+    Ok(())
+}
+
+#[test]
+fn should_fail_to_deserialize_invalid_path_regex() {
+    assert!(serde_json::from_value::<PathRegex>(json!({ "keys": [] })).is_err());
+    assert!(serde_json::from_value::<PathRegex>(json!({ "pattern": "(" })).is_err());
+}
+
+#[test]
+fn should_deserialize_key_with_missing_fields() -> Result<()> {
+    let key: Key = serde_json::from_value(json!({ "name": "id" }))?;
+    assert_eq!(
+        key,
+        Key {
+            name: "id".to_owned(),
+            ..Default::default()
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn should_serialize_path_description() -> Result<()> {
+    let description = path2regex::describe_tokens(&[
+        Token::Static("/files".to_owned()),
+        Token::Key(
+            Key::default()
+                .with_name("path")
+                .with_prefix("/")
+                .with_pattern(".*")
+                .with_modifier("*"),
+        ),
+    ]);
+    assert_eq!(
+        serde_json::to_value(&description)?,
+        json!({
+            "skeleton": "/files[/{path}]",
+            "params": [{
+                "name": "path",
+                "required": false,
+                "repeatable": true,
+                "pattern": ".*",
+                "prefix": "/",
+                "suffix": "",
+                "default": null,
+            }],
+        })
+    );
+    Ok(())
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "compile-simple")]
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use path2regex::compile_path;
+
+#[test]
+fn should_compile_path_from_a_map() -> Result<()> {
+    let mut data = HashMap::new();
+    data.insert("id".to_owned(), "123");
+    assert_eq!(compile_path("/user/:id", &data)?, "/user/123");
+    assert_eq!(compile_path("/user/:id{-:tab}?", &data)?, "/user/123");
+    assert_eq!(compile_path(r"/user/:id(\d+)", &data)?, "/user/123");
+    assert_eq!(
+        compile_path("/user/:id/:tab?=posts", &data)?,
+        "/user/123/posts"
+    );
+
+    data.insert("tab".to_owned(), "posts");
+    assert_eq!(compile_path("/user/:id{-:tab}?", &data)?, "/user/123-posts");
+
+    data.insert("id".to_owned(), "a/b");
+    let err = compile_path("/user/:id", &data).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected all \"id\" to match \"[^/\\#\\?]+?\", but got \"a/b\""
+    );
+    let err = compile_path("/post/:slug", &data).unwrap_err();
+    assert_eq!(err.to_string(), "Expected \"slug\" to be a string");
+    Ok(())
+}